@@ -0,0 +1,70 @@
+use wasm_bindgen::prelude::*;
+
+use crate::render::{DEFAULT_BACKGROUND, DEFAULT_FOREGROUND};
+use crate::Emulator;
+
+/// Built-in palettes, colors are listed in `set_plane_palette` order.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PalettePreset {
+    /// The emulator's own blue and white.
+    #[default]
+    Default,
+    /// White on black, the XO-CHIP planes in yellow and cyan.
+    HighContrast,
+    /// Okabe-Ito colors that stay apart with every kind of color blindness.
+    ColorblindSafe,
+}
+
+impl PalettePreset {
+    pub fn colors(self) -> [u32; 4] {
+        match self {
+            PalettePreset::Default => {
+                [DEFAULT_BACKGROUND, DEFAULT_FOREGROUND, DEFAULT_FOREGROUND, DEFAULT_FOREGROUND]
+            }
+            PalettePreset::HighContrast => [0x000000, 0xffffff, 0xffff00, 0x00ffff],
+            PalettePreset::ColorblindSafe => [0x000000, 0xf0e442, 0x0072b2, 0xe69f00],
+        }
+    }
+}
+
+/// Display settings for players with visual impairments or photosensitivity.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccessibilityOptions {
+    pub palette: PalettePreset,
+    /// Blend rapid alternations of pixels, e.g. full-screen inversions, into steady colors.
+    pub reduce_flashes: bool,
+}
+
+#[wasm_bindgen]
+impl AccessibilityOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(palette: PalettePreset, reduce_flashes: bool) -> AccessibilityOptions {
+        AccessibilityOptions { palette, reduce_flashes }
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Applies a palette preset and the flash reduction to the `render_rgba` output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{AccessibilityOptions, Emulator, PalettePreset};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_accessibility(AccessibilityOptions::new(PalettePreset::HighContrast, true));
+    /// // i := digit 0, draw it, loop
+    /// emulator.load(&[0xa0, 0x00, 0xd0, 0x05, 0x12, 0x04]).unwrap();
+    /// emulator.render_rgba();
+    /// emulator.tick_frame();
+    /// emulator.render_rgba();
+    /// // Pixels that just lit up are shown grey, they reach white over the next frames.
+    /// assert!(emulator.rgba().chunks(4).any(|rgba| rgba[0] > 0 && rgba[0] < 0xff));
+    /// ```
+    pub fn set_accessibility(&mut self, options: AccessibilityOptions) {
+        self.set_plane_palette(options.palette.colors());
+        self.renderer.set_reduce_flashes(options.reduce_flashes);
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use crate::stats::{instruction_size, instruction_starts};
+use crate::{Emulator, Instruction};
+
+/// What the references to an address suggest it is, in the order the kinds win over each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum LabelKind {
+    Data,
+    Label,
+    Subroutine,
+}
+
+/// Code and references found in the loaded program.
+pub(crate) struct Analysis {
+    /// Address of the first and one past the last program byte.
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    /// Whether an instruction starts at every program address, found by following every path
+    /// from the load address and from the addresses coverage saw executed.
+    pub(crate) code: Vec<bool>,
+    /// Program addresses referenced by jumps, calls and I loads.
+    pub(crate) labels: BTreeMap<usize, LabelKind>,
+    /// Addresses of the instructions referring to every label.
+    pub(crate) xrefs: BTreeMap<usize, Vec<usize>>,
+}
+
+impl Analysis {
+    pub(crate) fn is_code(&self, address: usize) -> bool {
+        address >= self.start && address < self.end && self.code[address - self.start]
+    }
+
+    /// Name of a label, `sub_`, `label_` or `data_` followed by the address.
+    pub(crate) fn label_name(&self, address: usize) -> Option<String> {
+        let prefix = match self.labels.get(&address)? {
+            LabelKind::Subroutine => "sub",
+            LabelKind::Label => "label",
+            LabelKind::Data => "data",
+        };
+        Some(format!("{}_{:03X}", prefix, address))
+    }
+}
+
+/// Address an instruction refers to and the kind of label it suggests.
+pub(crate) fn reference(instruction: Instruction) -> Option<(usize, LabelKind)> {
+    match instruction {
+        Instruction::Jump(nnn) | Instruction::JumpOffset(nnn) => Some((usize::from(nnn), LabelKind::Label)),
+        Instruction::Call(nnn) => Some((usize::from(nnn), LabelKind::Subroutine)),
+        Instruction::SetI(nnn) => Some((usize::from(nnn), LabelKind::Data)),
+        _ => None,
+    }
+}
+
+impl Emulator {
+    /// Two bytes of memory as an opcode, reading 0 past the end.
+    pub(crate) fn word_at(&self, address: usize) -> u16 {
+        let byte = |at: usize| u16::from(self.memory.get(at).copied().unwrap_or(0));
+        byte(address) << 8 | byte(address + 1)
+    }
+
+    /// Analyzes the program in memory. It starts at the load address and ends at the last
+    /// non-zero byte, so zero padding at the end of a ROM is not part of it.
+    pub(crate) fn analyze(&self) -> Analysis {
+        let start = self.variant.load_address().min(self.memory.len());
+        let end = self.memory[start..].iter().rposition(|&byte| byte != 0).map_or(start, |last| {
+            let end = start + last + 1;
+            (end + (end - start) % 2).min(self.memory.len())
+        });
+
+        let program = &self.memory[start..end];
+        let mut code = instruction_starts(program, self.variant);
+        for (offset, starts) in code.iter_mut().enumerate() {
+            *starts |= self.coverage.was_executed(start + offset);
+        }
+
+        let mut labels = BTreeMap::new();
+        let mut xrefs: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (offset, _) in code.iter().enumerate().filter(|&(_, &starts)| starts) {
+            let address = start + offset;
+            let target = reference(Instruction::decode(self.word_at(address), self.variant));
+            if let Some((target, kind)) = target.filter(|&(target, _)| target >= start && target < end) {
+                let label = labels.entry(target).or_insert(kind);
+                *label = (*label).max(kind);
+                xrefs.entry(target).or_default().push(address);
+            }
+        }
+
+        Analysis { start, end, code, labels, xrefs }
+    }
+
+    /// Size of the instruction at `address`, 4 for the XO-CHIP long load and 2 otherwise.
+    pub(crate) fn instruction_size_at(&self, address: usize) -> usize {
+        instruction_size(Instruction::decode(self.word_at(address), self.variant))
+    }
+}
@@ -0,0 +1,198 @@
+//! Reading ROMs out of .zip archives, enabled by the `zip` feature.
+//!
+//! Only what ROM packs use is supported: stored and deflated entries without encryption or ZIP64.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Variant};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// Reason an archive or one of its entries could not be read.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveError {
+    /// The data is not a zip archive.
+    NotAnArchive,
+    /// The archive ends in the middle of a record or an entry fails to inflate.
+    Corrupted,
+    /// The entry is encrypted, uses ZIP64 or another compression method.
+    Unsupported,
+    /// No file with the given name is in the archive.
+    EntryNotFound,
+    /// The entry is larger than the program memory of the current variant, or of every variant
+    /// for `extract_archive_entry`.
+    TooLarge,
+}
+
+struct Entry {
+    name: String,
+    method: u16,
+    flags: u16,
+    compressed_size: usize,
+    size: usize,
+    local_offset: usize,
+}
+
+fn u16_at(data: &[u8], at: usize) -> Result<u16, ArchiveError> {
+    match data.get(at..at.saturating_add(2)) {
+        Some(bytes) => Ok(u16::from_le_bytes([bytes[0], bytes[1]])),
+        None => Err(ArchiveError::Corrupted),
+    }
+}
+
+fn u32_at(data: &[u8], at: usize) -> Result<u32, ArchiveError> {
+    match data.get(at..at.saturating_add(4)) {
+        Some(bytes) => Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        None => Err(ArchiveError::Corrupted),
+    }
+}
+
+fn entries(archive: &[u8]) -> Result<Vec<Entry>, ArchiveError> {
+    // The end of central directory record is at least 22 bytes and may be followed by a comment.
+    let end = (0..archive.len().saturating_sub(21))
+        .rev()
+        .take(0xffff + 22)
+        .find(|&at| u32_at(archive, at) == Ok(END_OF_DIRECTORY))
+        .ok_or(ArchiveError::NotAnArchive)?;
+
+    let count = u16_at(archive, end + 10)? as usize;
+    let mut at = u32_at(archive, end + 16)? as usize;
+    if count == 0xffff || at == 0xffff_ffff {
+        return Err(ArchiveError::Unsupported);
+    }
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if u32_at(archive, at)? != CENTRAL_HEADER {
+            return Err(ArchiveError::Corrupted);
+        }
+
+        let name_len = u16_at(archive, at + 28)? as usize;
+        let extra_len = u16_at(archive, at + 30)? as usize;
+        let comment_len = u16_at(archive, at + 32)? as usize;
+        let name = archive.get(at + 46..at + 46 + name_len).ok_or(ArchiveError::Corrupted)?;
+
+        entries.push(Entry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method: u16_at(archive, at + 10)?,
+            flags: u16_at(archive, at + 8)?,
+            compressed_size: u32_at(archive, at + 20)? as usize,
+            size: u32_at(archive, at + 24)? as usize,
+            local_offset: u32_at(archive, at + 42)? as usize,
+        });
+
+        at += 46 + name_len + extra_len + comment_len;
+    }
+
+    Ok(entries)
+}
+
+// Reads an entry of at most `limit` bytes. The size is checked before anything is inflated.
+fn read(archive: &[u8], entry: &Entry, limit: usize) -> Result<Vec<u8>, ArchiveError> {
+    if entry.flags & 1 != 0 || entry.size == 0xffff_ffff || entry.compressed_size == 0xffff_ffff {
+        return Err(ArchiveError::Unsupported);
+    }
+    if entry.size > limit {
+        return Err(ArchiveError::TooLarge);
+    }
+
+    let at = entry.local_offset;
+    if u32_at(archive, at)? != LOCAL_HEADER {
+        return Err(ArchiveError::Corrupted);
+    }
+    let name_len = u16_at(archive, at + 26)? as usize;
+    let extra_len = u16_at(archive, at + 28)? as usize;
+    let start = at + 30 + name_len + extra_len;
+    let data = archive.get(start..start.saturating_add(entry.compressed_size)).ok_or(ArchiveError::Corrupted)?;
+
+    let content = match entry.method {
+        STORED => data.to_vec(),
+        DEFLATED => miniz_oxide::inflate::decompress_to_vec_with_limit(data, limit)
+            .map_err(|_| ArchiveError::Corrupted)?,
+        _ => return Err(ArchiveError::Unsupported),
+    };
+
+    if content.len() != entry.size {
+        return Err(ArchiveError::Corrupted);
+    }
+    Ok(content)
+}
+
+/// Lists the files of a zip archive in the order they are stored, directories excluded.
+///
+/// # Example
+///
+/// ```
+/// use wasm_chip8::{extract_archive_entry, list_archive, ArchiveError, Emulator};
+/// # fn zip(name: &str, content: &[u8]) -> Vec<u8> {
+/// #     let u16le = |value: usize| (value as u16).to_le_bytes().to_vec();
+/// #     let u32le = |value: usize| (value as u32).to_le_bytes().to_vec();
+/// #     let mut local = [&b"PK\x03\x04"[..], &[0; 14]].concat();
+/// #     local.extend(u32le(content.len()));
+/// #     local.extend(u32le(content.len()));
+/// #     local.extend(u16le(name.len()));
+/// #     local.extend(u16le(0));
+/// #     local.extend(name.bytes());
+/// #     local.extend(content);
+/// #     let mut central = [&b"PK\x01\x02"[..], &[0; 16]].concat();
+/// #     central.extend(u32le(content.len()));
+/// #     central.extend(u32le(content.len()));
+/// #     central.extend(u16le(name.len()));
+/// #     central.extend([0; 12]);
+/// #     central.extend(u32le(0));
+/// #     central.extend(name.bytes());
+/// #     let mut end = [&b"PK\x05\x06"[..], &[0; 4]].concat();
+/// #     end.extend(u16le(1));
+/// #     end.extend(u16le(1));
+/// #     end.extend(u32le(central.len()));
+/// #     end.extend(u32le(local.len()));
+/// #     end.extend(u16le(0));
+/// #     [local, central, end].concat()
+/// # }
+/// let archive = zip("games/pong.ch8", &[0x12, 0x00]);
+/// assert_eq!(list_archive(&archive).unwrap(), ["games/pong.ch8"]);
+/// assert_eq!(extract_archive_entry(&archive, "games/pong.ch8").unwrap(), [0x12, 0x00]);
+///
+/// // Files larger than a program are rejected before they are read.
+/// let archive = zip("huge.bin", &[0; 0x10000]);
+/// assert_eq!(extract_archive_entry(&archive, "huge.bin"), Err(ArchiveError::TooLarge));
+/// let archive = zip("big.ch8", &[0; 0x1000]);
+/// assert_eq!(Emulator::new().load_from_archive(&archive, "big.ch8"), Err(ArchiveError::TooLarge));
+/// ```
+#[wasm_bindgen]
+pub fn list_archive(archive: &[u8]) -> Result<Vec<String>, ArchiveError> {
+    Ok(entries(archive)?.into_iter().map(|entry| entry.name).filter(|name| !name.ends_with('/')).collect())
+}
+
+/// Returns the content of a file stored in a zip archive. Files too large to be a program of any
+/// variant are rejected.
+#[wasm_bindgen]
+pub fn extract_archive_entry(archive: &[u8], name: &str) -> Result<Vec<u8>, ArchiveError> {
+    let limit = Variant::ALL.iter().map(|variant| variant.max_rom_size()).max().unwrap_or(0);
+    extract(archive, name, limit)
+}
+
+fn extract(archive: &[u8], name: &str, limit: usize) -> Result<Vec<u8>, ArchiveError> {
+    let entries = entries(archive)?;
+    let entry = entries.iter().find(|entry| entry.name == name).ok_or(ArchiveError::EntryNotFound)?;
+    read(archive, entry, limit)
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Loads a file of a zip archive as the program.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - Bytes of the whole .zip file.
+    /// * `name` - Full name of the entry as returned by `list_archive`.
+    pub fn load_from_archive(&mut self, archive: &[u8], name: &str) -> Result<(), ArchiveError> {
+        let program = extract(archive, name, self.max_rom_size())?;
+        self.load(&program).map_err(|_| ArchiveError::TooLarge)
+    }
+}
@@ -0,0 +1,295 @@
+use std::f32::consts::PI;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::io::AudioBackend;
+use crate::profile::Phase;
+use crate::{Emulator, EventKind};
+
+pub const DEFAULT_TONE_FREQUENCY: f32 = 440.0;
+pub const DEFAULT_SAMPLE_RATE: f32 = 44_100.0;
+const DEFAULT_ATTACK_MS: f32 = 2.0;
+const DEFAULT_RELEASE_MS: f32 = 10.0;
+
+/// A frame run by `step_frame_with_audio`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FrameAudio {
+    /// The frame changed the display.
+    pub drew: bool,
+    /// Samples of the beep during the frame at the sample rate of `fill_audio`.
+    pub samples: Vec<f32>,
+}
+
+/// Beep generator driven by the sound timer.
+///
+/// The tone fades in and out with a linear attack/release envelope, so starting or stopping the
+/// sound in the middle of a buffer does not produce audible clicks.
+#[derive(Clone, Debug)]
+pub struct Synth {
+    frequency: f32,
+    sample_rate: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    // Gain applied to every sample, 0..1.
+    volume: f32,
+    // Phase of the oscillator in turns (0..1) and current envelope level (0..1).
+    phase: f32,
+    level: f32,
+}
+
+impl Default for Synth {
+    fn default() -> Synth {
+        Synth {
+            frequency: DEFAULT_TONE_FREQUENCY,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            attack_ms: DEFAULT_ATTACK_MS,
+            release_ms: DEFAULT_RELEASE_MS,
+            volume: 1.0,
+            phase: 0.0,
+            level: 0.0,
+        }
+    }
+}
+
+impl Synth {
+    pub fn set_frequency(&mut self, hz: f32) {
+        if hz.is_finite() && hz > 0.0 {
+            self.frequency = hz;
+        }
+    }
+
+    pub fn frequency(&self) -> f32 { self.frequency }
+
+    pub fn set_sample_rate(&mut self, rate: f32) {
+        if rate.is_finite() && rate > 0.0 {
+            self.sample_rate = rate;
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        if volume.is_finite() {
+            self.volume = volume.clamp(0.0, 1.0);
+        }
+    }
+
+    pub fn volume(&self) -> f32 { self.volume }
+
+    pub fn sample_rate(&self) -> f32 { self.sample_rate }
+
+    pub fn set_envelope(&mut self, attack_ms: f32, release_ms: f32) {
+        self.attack_ms = attack_ms.max(0.0);
+        self.release_ms = release_ms.max(0.0);
+    }
+
+    /// Fills the buffer with mono samples in -1..1, the tone is heard while `on` is set.
+    pub fn fill(&mut self, buffer: &mut [f32], on: bool) {
+        let step = self.frequency / self.sample_rate;
+        let (target, ramp_ms) = if on { (1.0, self.attack_ms) } else { (0.0, self.release_ms) };
+        let ramp = if ramp_ms > 0.0 { 1000.0 / (ramp_ms * self.sample_rate) } else { 1.0 };
+
+        for sample in buffer.iter_mut() {
+            if self.level < target {
+                self.level = (self.level + ramp).min(target);
+            } else if self.level > target {
+                self.level = (self.level - ramp).max(target);
+            }
+
+            if self.level == 0.0 {
+                // Restart the wave from zero so the next beep starts without a jump.
+                self.phase = 0.0;
+                *sample = 0.0;
+                continue;
+            }
+
+            *sample = (self.phase * 2.0 * PI).sin() * self.level * self.volume;
+            self.phase = (self.phase + step).fract();
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Set the frequency of the beep in Hz.
+    pub fn set_tone_frequency(&mut self, hz: f32) {
+        self.synth.set_frequency(hz);
+        self.audio.set_frequency(self.synth.frequency());
+    }
+
+    /// Set the sample rate `fill_audio` produces samples for.
+    pub fn set_sample_rate(&mut self, rate: f32) { self.synth.set_sample_rate(rate); }
+
+    /// Set how long the beep takes to fade in and out, in milliseconds.
+    pub fn set_envelope(&mut self, attack_ms: f32, release_ms: f32) {
+        self.synth.set_envelope(attack_ms, release_ms);
+    }
+
+    /// Silences the beep without touching the sound timer, which keeps counting down so programs
+    /// using it as a timer still work.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_muted(true);
+    /// // v0 := 2, buzzer := v0
+    /// emulator.load(&[0x60, 0x02, 0xf0, 0x18]).unwrap();
+    /// emulator.tick_frame();
+    /// let mut buffer = [1.0; 64];
+    /// emulator.fill_audio(&mut buffer);
+    /// assert!(buffer.iter().all(|&sample| sample == 0.0));
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.sound_timer(), 0);
+    /// ```
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.sync_audio();
+    }
+
+    pub fn is_muted(&self) -> bool { self.muted }
+
+    /// Set the loudness of the beep from 0 to 1, for `fill_audio` and the audio device alike.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.synth.set_volume(volume);
+        self.audio.set_volume(self.synth.volume());
+    }
+
+    pub fn volume(&self) -> f32 { self.synth.volume() }
+
+    /// Fills the buffer with mono samples of the beep for the current sound timer state.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - Samples in -1..1 are written to it, e.g. from an `AudioWorklet`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// let mut buffer = [1.0; 64];
+    /// emulator.fill_audio(&mut buffer);
+    /// assert!(buffer.iter().all(|&sample| sample == 0.0));
+    ///
+    /// // v0 := 10, buzzer := v0
+    /// emulator.load(&[0x60, 0x0a, 0xf0, 0x18]).unwrap();
+    /// emulator.tick_frame();
+    /// emulator.fill_audio(&mut buffer);
+    /// assert!(buffer[1].abs() < 0.1);
+    /// assert!(buffer.iter().any(|&sample| sample.abs() > 0.5));
+    /// ```
+    pub fn fill_audio(&mut self, buffer: &mut [f32]) {
+        let started = self.profile_start();
+        self.synth.fill(buffer, self.is_beeping());
+        self.profile(Phase::Audio, started);
+    }
+}
+
+impl Emulator {
+    /// Runs one frame like `tick_frame` and returns the audio of that frame, so single-stepping
+    /// frames in a debugger still plays the beep. The frame gets its share of a second of audio,
+    /// rounded so that `frame_rate` frames add up to exactly one second. Nothing runs and no
+    /// samples are returned while paused.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 10, buzzer := v0, halt: jump halt
+    /// emulator.load(&[0x60, 0x0a, 0xf0, 0x18, 0x12, 0x04]).unwrap();
+    /// let frame = emulator.step_frame_with_audio();
+    /// assert!(!frame.drew);
+    /// assert_eq!(frame.samples.len(), 735);
+    /// assert!(frame.samples.iter().any(|&sample| sample.abs() > 0.5));
+    /// ```
+    pub fn step_frame_with_audio(&mut self) -> FrameAudio {
+        let frame = self.frame;
+        self.tick_frame();
+        if self.frame == frame {
+            return FrameAudio { drew: false, samples: vec![] };
+        }
+
+        let (rate, frame_rate) = (f64::from(self.synth.sample_rate()), f64::from(self.frame_rate()));
+        let samples = |frame: u64| (frame as f64 * rate / frame_rate) as usize;
+        let mut buffer = vec![0.0; samples(frame + 1) - samples(frame)];
+        self.fill_audio(&mut buffer);
+        FrameAudio { drew: self.draw_flag, samples: buffer }
+    }
+
+    /// Replaces the sound output without touching the rest of the emulator, e.g. to switch from
+    /// `SilentAudio` to a real device once the user interacted with the page. The tone, the
+    /// volume and a beep that is sounding carry over to the new backend.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    /// use wasm_chip8::{AudioBackend, Emulator};
+    ///
+    /// struct Speaker(Rc<Cell<bool>>);
+    ///
+    /// impl AudioBackend for Speaker {
+    ///     fn start(&mut self) { self.0.set(true); }
+    ///     fn stop(&mut self) { self.0.set(false); }
+    ///     fn is_active(&self) -> bool { self.0.get() }
+    /// }
+    ///
+    /// let mut emulator = Emulator::headless();
+    /// // v0 := 60, buzzer := v0
+    /// emulator.load(&[0x60, 0x3c, 0xf0, 0x18]).unwrap();
+    /// emulator.tick_frame();
+    /// let playing = Rc::new(Cell::new(false));
+    /// emulator.set_audio_backend(Box::new(Speaker(playing.clone())));
+    /// assert!(playing.get());
+    /// assert_eq!(emulator.take_events().len(), 1);
+    /// ```
+    pub fn set_audio_backend(&mut self, mut audio: Box<dyn AudioBackend>) {
+        if self.audio.is_active() {
+            self.audio.stop();
+        }
+        audio.set_frequency(self.synth.frequency());
+        audio.set_volume(self.synth.volume());
+        if self.is_beeping() {
+            audio.start();
+        }
+        self.audio = audio;
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `step_frame_with_audio` as a plain JS object.
+    #[wasm_bindgen(js_name = step_frame_with_audio)]
+    pub fn step_frame_with_audio_js(&mut self) -> JsValue { crate::utils::to_js(&self.step_frame_with_audio()) }
+
+    /// Replaces the sound output with a JS object that has the methods of the `Audio` class in
+    /// `js/io-interfaces/audio.js`, see `set_audio_backend`. `null` or `undefined` silence it.
+    #[wasm_bindgen(js_name = set_audio_backend)]
+    pub fn set_audio_backend_js(&mut self, backend: JsValue) {
+        if backend.is_null() || backend.is_undefined() {
+            self.set_audio_backend(Box::new(crate::io::SilentAudio::default()));
+        } else {
+            self.set_audio_backend(Box::new(backend.unchecked_into::<crate::io::Audio>()));
+        }
+    }
+}
+
+impl Emulator {
+    /// Returns `true` while the beep should be heard.
+    pub(crate) fn is_beeping(&self) -> bool { self.sound_timer > 0 && !self.paused && !self.muted }
+
+    /// Starts or stops the audio device so it follows `is_beeping`.
+    pub(crate) fn sync_audio(&mut self) {
+        if self.is_beeping() && !self.audio.is_active() {
+            self.audio.start();
+            self.emit(EventKind::SoundStart);
+        } else if !self.is_beeping() && self.audio.is_active() {
+            self.audio.stop();
+            self.emit(EventKind::SoundStop);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+// Weight of the latest frame in the running average of frame times.
+const SMOOTHING: f64 = 0.2;
+
+// Fraction of the budget the average has to stay under before the speed goes back up.
+const RECOVER_BELOW: f64 = 0.75;
+
+/// State of the auto-speed mode of `tick_frame`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct AutoSpeed {
+    enabled: bool,
+    // Milliseconds a frame may take on the host.
+    budget_ms: f64,
+    // Cycles per frame asked for with `set_cycles_per_frame`, the most auto-speed runs.
+    target: u32,
+    // Running average of the time `tick_frame` took, 0 until the first frame is measured.
+    average_ms: f64,
+}
+
+impl AutoSpeed {
+    pub(crate) fn is_enabled(&self) -> bool { self.enabled }
+
+    pub(crate) fn set_target(&mut self, cycles: u32) {
+        self.target = cycles;
+        self.average_ms = 0.0;
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Lets `tick_frame` measure how long it takes on the host and run fewer instructions per
+    /// frame when it takes longer than `budget_ms`, so slow devices run the program slower
+    /// instead of dropping frames. The cycles per frame set before go back up while frames are
+    /// fast again, and disabling auto-speed restores them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, MockClock};
+    /// let clock = MockClock::default();
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_time_source(Box::new(clock.clone()));
+    /// emulator.set_cycles_per_frame(10);
+    /// emulator.set_auto_speed(true, 5.0);
+    /// // loop: v0 += 1, jump loop
+    /// emulator.load(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    /// // Every frame takes 10ms.
+    /// clock.set_step(10.0);
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.effective_speed(), 0.9);
+    /// emulator.set_auto_speed(false, 0.0);
+    /// assert_eq!(emulator.effective_speed(), 1.0);
+    /// ```
+    pub fn set_auto_speed(&mut self, enabled: bool, budget_ms: f64) {
+        if self.auto_speed.enabled {
+            self.cycles_per_frame = self.auto_speed.target;
+        }
+        self.auto_speed = AutoSpeed { enabled, budget_ms, target: self.cycles_per_frame, average_ms: 0.0 };
+    }
+
+    pub fn is_auto_speed(&self) -> bool { self.auto_speed.enabled }
+
+    /// Fraction of the configured cycles per frame `tick_frame` currently runs, 1.0 unless
+    /// auto-speed slowed it down.
+    pub fn effective_speed(&self) -> f32 {
+        if !self.auto_speed.enabled || self.auto_speed.target == 0 {
+            return 1.0;
+        }
+        self.cycles_per_frame as f32 / self.auto_speed.target as f32
+    }
+}
+
+impl Emulator {
+    /// Adjusts the cycles per frame after a frame took `elapsed_ms` on the host.
+    pub(crate) fn adapt_speed(&mut self, elapsed_ms: f64) {
+        let speed = &mut self.auto_speed;
+        speed.average_ms = if speed.average_ms == 0.0 {
+            elapsed_ms
+        } else {
+            speed.average_ms + (elapsed_ms - speed.average_ms) * SMOOTHING
+        };
+
+        let cycles = self.cycles_per_frame;
+        if speed.average_ms > speed.budget_ms {
+            self.cycles_per_frame = cycles.saturating_sub((cycles / 10).max(1)).max(1).min(cycles);
+        } else if speed.average_ms < speed.budget_ms * RECOVER_BELOW && cycles < speed.target {
+            self.cycles_per_frame = (cycles + (speed.target / 20).max(1)).min(speed.target);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use wasm_bindgen::prelude::*;
+
+use crate::events::EventKind;
+use crate::keypad::KeyWait;
+use crate::{Emulator, Instruction, Quirks, Variant};
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Lets a program loaded as plain CHIP-8 switch the emulator to XO-CHIP the first time it
+    /// executes an opcode only XO-CHIP defines (`F000 NNNN` and `FN01`), instead of skipping it.
+    /// Users then don't have to know which platform a ROM targets.
+    ///
+    /// The switch keeps the program running: memory grows to 64K with the program in place and
+    /// the quirks and the `key_wait` become the ones of XO-CHIP. A `VariantUpgrade` event tells
+    /// the frontend, the variant stays XO-CHIP until `set_variant` is called. The journal of
+    /// `step_back` is cleared as the instructions before cannot be reversed across the switch.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, EventKind, Variant};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_auto_variant(true);
+    /// // i := long 0xfff0, v0 := 255, bcd v0
+    /// emulator.load(&[0xf0, 0x00, 0xff, 0xf0, 0x60, 0xff, 0xf0, 0x33]).unwrap();
+    /// for _ in 0..3 {
+    ///     emulator.tick();
+    /// }
+    /// assert_eq!(emulator.variant(), Variant::XoChip);
+    /// assert_eq!(&emulator.memory()[0xfff0..0xfff3], &[2, 5, 5]);
+    /// let events = emulator.take_events();
+    /// assert_eq!(events[0].kind, EventKind::VariantUpgrade { variant: Variant::XoChip });
+    /// assert_eq!(events[0].pc, 0x200);
+    /// ```
+    pub fn set_auto_variant(&mut self, enabled: bool) { self.auto_variant = enabled; }
+
+    pub fn is_auto_variant(&self) -> bool { self.auto_variant }
+}
+
+impl Emulator {
+    /// Switches to XO-CHIP if auto-detection is enabled and XO-CHIP decodes the current opcode
+    /// differently, returning the instruction to execute.
+    pub(crate) fn detect_variant(&mut self, instruction: Instruction) -> Instruction {
+        if !self.auto_variant || self.variant != Variant::Chip8 {
+            return instruction;
+        }
+        let upgraded = Instruction::decode(self.opcode, Variant::XoChip);
+        if upgraded == instruction {
+            return instruction;
+        }
+
+        log::debug!("opcode {:04X} at {:#05x} needs XO-CHIP, switching", self.opcode, self.pc);
+        let mut memory = Emulator::prepare_memory(Variant::XoChip, self.memory_init);
+        memory[..self.memory.len()].copy_from_slice(&self.memory);
+        self.memory = memory;
+        self.variant = Variant::XoChip;
+        self.quirks = Quirks::for_variant(Variant::XoChip);
+        self.keypad.set_wait(KeyWait::for_variant(Variant::XoChip));
+        self.journal.clear();
+        self.emit(EventKind::VariantUpgrade { variant: Variant::XoChip });
+        upgraded
+    }
+}
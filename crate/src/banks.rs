@@ -0,0 +1,129 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// How many program banks every emulator has.
+pub const BANK_COUNT: usize = 16;
+
+/// Reason a bank operation was rejected.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BankError {
+    /// The bank index is not below `BANK_COUNT`.
+    InvalidBank,
+    /// Nothing was loaded into the bank.
+    EmptyBank,
+    /// The program does not fit into memory after the load address of the current variant.
+    TooLarge,
+}
+
+/// Program loaded into a bank and, if the bank preserves its state, where it was left.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Bank {
+    rom: Vec<u8>,
+    preserve: bool,
+    state: Option<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Stores a program in a bank without touching the running one. A state the bank preserved
+    /// for its previous program is dropped.
+    pub fn load_into_bank(&mut self, bank: u8, rom: &[u8]) -> Result<(), BankError> {
+        let index = bank_index(bank)?;
+        if rom.len() > self.max_rom_size() {
+            return Err(BankError::TooLarge);
+        }
+        if self.banks.len() < BANK_COUNT {
+            self.banks.resize(BANK_COUNT, None);
+        }
+        let preserve = self.banks[index].as_ref().is_some_and(|saved| saved.preserve);
+        self.banks[index] = Some(Bank { rom: rom.to_vec(), preserve, state: None });
+        Ok(())
+    }
+
+    /// Makes a bank remember where its program was left when another bank is switched to, so
+    /// switching back resumes it instead of restarting it.
+    pub fn set_bank_preserve(&mut self, bank: u8, preserve: bool) -> Result<(), BankError> {
+        let saved = self.bank_mut(bank)?;
+        saved.preserve = preserve;
+        if !preserve {
+            saved.state = None;
+        }
+        Ok(())
+    }
+
+    /// Runs the program of a bank. It is restarted unless the bank preserves its state and was
+    /// run before, the state of the bank being left is saved if it preserves it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 1, halt and v1 := 2, halt
+    /// emulator.load_into_bank(0, &[0x60, 0x01, 0x12, 0x02]).unwrap();
+    /// emulator.load_into_bank(1, &[0x61, 0x02, 0x12, 0x02]).unwrap();
+    /// emulator.set_bank_preserve(0, true).unwrap();
+    /// emulator.switch_bank(0).unwrap();
+    /// emulator.tick();
+    /// emulator.switch_bank(1).unwrap();
+    /// assert_eq!((emulator.v(0), emulator.pc()), (0, 0x200));
+    /// emulator.switch_bank(0).unwrap();
+    /// assert_eq!((emulator.v(0), emulator.pc()), (1, 0x202));
+    /// ```
+    pub fn switch_bank(&mut self, bank: u8) -> Result<(), BankError> {
+        let index = bank_index(bank)?;
+        if !self.banks.get(index).is_some_and(Option::is_some) {
+            return Err(BankError::EmptyBank);
+        }
+
+        if let Some(current) = self.current_bank {
+            if self.banks[current as usize].as_ref().is_some_and(|saved| saved.preserve) {
+                let state = self.save_state();
+                if let Some(saved) = self.banks[current as usize].as_mut() {
+                    saved.state = Some(state);
+                }
+            }
+        }
+
+        let target = self.banks[index].clone().unwrap_or_default();
+        let resumed = match target.state {
+            Some(state) => self.load_state(&state).is_ok(),
+            None => false,
+        };
+        if !resumed {
+            self.reload(&target.rom).map_err(|_| BankError::TooLarge)?;
+        }
+        self.current_bank = Some(bank);
+        Ok(())
+    }
+
+    /// Bank whose program runs, nothing if no bank was switched to since the emulator was created.
+    pub fn current_bank(&self) -> Option<u8> { self.current_bank }
+
+    /// Empties a bank, the running program is not affected.
+    pub fn clear_bank(&mut self, bank: u8) -> Result<(), BankError> {
+        let index = bank_index(bank)?;
+        if let Some(saved) = self.banks.get_mut(index) {
+            *saved = None;
+        }
+        if self.current_bank == Some(bank) {
+            self.current_bank = None;
+        }
+        Ok(())
+    }
+
+    fn bank_mut(&mut self, bank: u8) -> Result<&mut Bank, BankError> {
+        let index = bank_index(bank)?;
+        self.banks.get_mut(index).and_then(Option::as_mut).ok_or(BankError::EmptyBank)
+    }
+}
+
+fn bank_index(bank: u8) -> Result<usize, BankError> {
+    if (bank as usize) < BANK_COUNT {
+        Ok(bank as usize)
+    } else {
+        Err(BankError::InvalidBank)
+    }
+}
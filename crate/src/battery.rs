@@ -0,0 +1,113 @@
+//! Battery-backed save games.
+//!
+//! Programs written for this emulator can keep data across sessions in the save window, the
+//! `SAVE_WINDOW_SIZE` bytes from `SAVE_WINDOW_START`, as cartridges with battery-backed RAM do.
+//! When the host sets a save storage, loading a program fills the window with the save of that
+//! program, found by its ROM hash, and the window is stored again at the end of every frame that
+//! wrote to it. Programs save with `FX55` (or any other write) into the window and find the data
+//! there after the next load. Without a storage the window is ordinary memory, so the same
+//! program runs on any emulator, only without persistence.
+//!
+//! The window is in the last 512 bytes of a 4K machine, where the VIP kept its stack and
+//! display, so programs using it have to end before `0xE00`, 3K at most. Vip2K lacks the memory
+//! and never saves.
+
+use wasm_bindgen::prelude::*;
+
+use crate::io::SaveStorage;
+use crate::Emulator;
+
+/// First address of the save window.
+pub const SAVE_WINDOW_START: usize = 0xe00;
+
+/// Size of the save window in bytes.
+pub const SAVE_WINDOW_SIZE: usize = 0x100;
+
+/// Save storage of the emulator and whether the window changed since it was stored.
+#[derive(Default)]
+pub(crate) struct Battery {
+    storage: Option<Box<dyn SaveStorage>>,
+    dirty: bool,
+}
+
+impl Battery {
+    /// Notes a write to memory, marking the save for storing if it hit the window.
+    pub(crate) fn write(&mut self, address: usize) {
+        if self.storage.is_some() && (SAVE_WINDOW_START..SAVE_WINDOW_START + SAVE_WINDOW_SIZE).contains(&address) {
+            self.dirty = true;
+        }
+    }
+}
+
+impl Emulator {
+    /// Sets the storage saves are kept in, enabling save games. The save of the loaded program,
+    /// if the storage has one, is restored right away.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, MemorySaveStorage, SAVE_WINDOW_START};
+    /// let storage = MemorySaveStorage::default();
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_save_storage(Box::new(storage.clone()));
+    /// // v0 := 42, i := 0xe00, save v0, loop: jump loop
+    /// let program = [0x60, 0x2a, 0xae, 0x00, 0xf0, 0x55, 0x12, 0x06];
+    /// emulator.load(&program).unwrap();
+    /// emulator.tick_frame();
+    /// assert_eq!(storage.get(emulator.rom_hash()).unwrap()[0], 42);
+    ///
+    /// // The next session of the program finds its save in memory.
+    /// let mut next = Emulator::new();
+    /// next.set_save_storage(Box::new(storage.clone()));
+    /// next.load(&program).unwrap();
+    /// assert_eq!(next.memory()[SAVE_WINDOW_START], 42);
+    /// ```
+    pub fn set_save_storage(&mut self, storage: Box<dyn SaveStorage>) {
+        self.battery = Battery { storage: Some(storage), dirty: false };
+        self.restore_save();
+    }
+
+    /// Fills the save window with the save of the loaded program.
+    pub(crate) fn restore_save(&mut self) {
+        let end = SAVE_WINDOW_START + SAVE_WINDOW_SIZE;
+        if self.rom_hash == 0 || self.memory.len() < end {
+            return;
+        }
+        if let Some(save) = self.battery.storage.as_ref().and_then(|storage| storage.load(self.rom_hash)) {
+            let len = save.len().min(SAVE_WINDOW_SIZE);
+            self.memory[SAVE_WINDOW_START..SAVE_WINDOW_START + len].copy_from_slice(&save[..len]);
+        }
+        self.battery.dirty = false;
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Keeps saves in the `localStorage` of the browser.
+    pub fn use_local_save_storage(&mut self) { self.set_save_storage(Box::new(crate::io::LocalSaveStorage::new())); }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Stores a pending save and disables save games.
+    pub fn clear_save_storage(&mut self) {
+        self.flush_save();
+        self.battery = Battery::default();
+    }
+
+    pub fn has_save_storage(&self) -> bool { self.battery.storage.is_some() }
+
+    /// Stores the save window now if it changed since it was last stored, e.g. before the page is
+    /// closed in the middle of a frame. The end of every frame does this as well.
+    pub fn flush_save(&mut self) {
+        if !self.battery.dirty || self.rom_hash == 0 {
+            return;
+        }
+        self.battery.dirty = false;
+        let window = &self.memory[SAVE_WINDOW_START..SAVE_WINDOW_START + SAVE_WINDOW_SIZE];
+        if let Some(storage) = self.battery.storage.as_mut() {
+            storage.store(self.rom_hash, window);
+        }
+    }
+}
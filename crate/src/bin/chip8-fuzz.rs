@@ -0,0 +1,149 @@
+//! Runs random programs on every variant and reports the first one that panics:
+//! `chip8-fuzz [programs] [seed]`. Every run also renders with a random scale, display style and
+//! overlay, loads a random input script and loads a corrupted savestate.
+//!
+//! The core is meant to be panic free, a trapped wasm instance cannot be recovered by the
+//! embedder. Failing programs are written to `chip8-fuzz-<seed>.ch8` for reproduction.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::{env, fs, process};
+
+use wasm_chip8::{DisplayStyle, Emulator, Variant};
+
+// Frames every program runs for.
+const FRAMES: usize = 30;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let parse = |value: Option<String>, default: u64| match value.map(|value| value.parse::<u64>()) {
+        None => default,
+        Some(Ok(value)) => value,
+        Some(Err(err)) => {
+            eprintln!("usage: chip8-fuzz [programs] [seed]: {}", err);
+            process::exit(2);
+        }
+    };
+    let programs = parse(args.next(), 1000);
+    let seed = parse(args.next(), 0x2545_f491_4f6c_dd1d);
+
+    let mut rng = XorShift(seed.max(1));
+    for index in 0..programs {
+        let program_seed = rng.next();
+        let program = random_program(&mut XorShift(program_seed.max(1)));
+
+        for &variant in Variant::ALL.iter() {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| run(&program, variant, program_seed)));
+            if result.is_err() {
+                let path = format!("chip8-fuzz-{}.ch8", program_seed);
+                let _ = fs::write(&path, &program);
+                eprintln!("program {} panicked on {:?}, saved to {}", index, variant, path);
+                process::exit(1);
+            }
+        }
+    }
+
+    println!("{} programs ran on {} variants without a panic", programs, Variant::ALL.len());
+}
+
+// Exercises execution together with the host facing calls a frontend makes every frame.
+fn run(program: &[u8], variant: Variant, seed: u64) {
+    let mut emulator = Emulator::headless();
+    emulator.set_variant(variant);
+    emulator.set_random_seed(seed as u32);
+    // At most 514 bytes, which fits into every variant.
+    let _ = emulator.load(program);
+    // Keeps the frames cheap, other scales are rendered once at the end.
+    emulator.set_display_scale(1);
+
+    let mut keys = XorShift(seed.max(1));
+    for _ in 0..FRAMES {
+        emulator.set_key((keys.next() % 18) as u8, keys.next() & 1 == 0);
+        emulator.tick_frame();
+        emulator.render_rgba();
+        let mut samples = [0.0; 64];
+        emulator.fill_audio(&mut samples);
+    }
+
+    render_random(&mut emulator, &mut keys);
+    let _ = emulator.load_input_script(0, &random_script(&mut keys));
+
+    let state = emulator.save_state();
+    if let Err(err) = emulator.load_state(&state) {
+        panic!("state saved on {:?} does not load: {:?}", variant, err);
+    }
+    let _ = emulator.load_state(&corrupt(&state, &mut keys));
+    emulator.tick_frame();
+    emulator.render_rgba();
+}
+
+// Renders with a random scale, style and overlay, including coordinates and sizes at the limits.
+fn render_random(emulator: &mut Emulator, rng: &mut XorShift) {
+    const CURVATURES: [f32; 6] = [0.0, 0.05, 0.5, 1e9, f32::INFINITY, f32::NAN];
+    emulator.set_display_scale((rng.next() % 40) as u32);
+    let mut style = DisplayStyle::new(rng.next() & 1 == 0, rng.next() & 1 == 0, 0.0);
+    style.curvature = CURVATURES[(rng.next() % CURVATURES.len() as u64) as usize];
+    emulator.set_display_style(style);
+    for _ in 0..rng.next() % 4 {
+        emulator.set_overlay_color(rng.next() as u32);
+        emulator.overlay_rect(rng.next() as i32, rng.next() as i32, rng.next() as u32, rng.next() as u32);
+        emulator.overlay_text(rng.next() as i32, rng.next() as i32, "FUZZ 0123");
+    }
+    emulator.render_rgba();
+    emulator.clear_overlay();
+    emulator.set_display_scale(1);
+}
+
+// An input script with random frames, keys and frame count, or random text.
+fn random_script(rng: &mut XorShift) -> String {
+    if rng.next() & 3 == 0 {
+        return (0..rng.next() % 64).map(|_| (rng.next() % 128) as u8 as char).collect();
+    }
+    let events: Vec<String> = (0..rng.next() % 8)
+        .map(|_| {
+            let (frame, key, pressed) = (random_frame(rng), rng.next() % 20, rng.next() & 1 == 0);
+            format!(r#"{{ "frame": {}, "key": {}, "pressed": {} }}"#, frame, key, pressed)
+        })
+        .collect();
+    format!(r#"{{ "frames": {}, "events": [{}] }}"#, random_frame(rng), events.join(", "))
+}
+
+// A frame number, now and then one at the limits.
+fn random_frame(rng: &mut XorShift) -> u64 {
+    const LIMITS: [u64; 5] = [0, 1000, u32::MAX as u64, usize::MAX as u64, u64::MAX];
+    match rng.next() % 4 {
+        0 => LIMITS[(rng.next() % LIMITS.len() as u64) as usize],
+        _ => rng.next() % 100,
+    }
+}
+
+// The state with a few random bytes changed and maybe cut short.
+fn corrupt(state: &[u8], rng: &mut XorShift) -> Vec<u8> {
+    let mut state = state.to_vec();
+    for _ in 0..1 + rng.next() % 8 {
+        let index = (rng.next() % state.len().max(1) as u64) as usize;
+        if let Some(byte) = state.get_mut(index) {
+            *byte = rng.next() as u8;
+        }
+    }
+    if rng.next() & 1 == 0 {
+        state.truncate((rng.next() % (state.len() as u64 + 1)) as usize);
+    }
+    state
+}
+
+// Random bytes. Almost every opcode is defined by some variant, so they exercise nearly everything.
+fn random_program(rng: &mut XorShift) -> Vec<u8> {
+    let len = 2 + (rng.next() % 512) as usize;
+    (0..len).map(|_| rng.next() as u8).collect()
+}
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -0,0 +1,48 @@
+//! Plays a ROM in the terminal: `chip8-tui <rom> [ticks-per-frame]`.
+
+use std::{env, fs, process};
+
+use wasm_chip8::{tui, Emulator};
+
+fn main() {
+    #[cfg(feature = "env_logger")]
+    env_logger::init();
+
+    let mut args = env::args().skip(1);
+
+    let path = match args.next() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: chip8-tui <rom> [ticks-per-frame]");
+            process::exit(2);
+        }
+    };
+
+    let ticks_per_frame = match args.next().map(|value| value.parse::<usize>()) {
+        None => 10,
+        Some(Ok(value)) => value,
+        Some(Err(err)) => {
+            eprintln!("invalid ticks-per-frame: {}", err);
+            process::exit(2);
+        }
+    };
+
+    let program = match fs::read(&path) {
+        Ok(program) => program,
+        Err(err) => {
+            eprintln!("failed to read {}: {}", path, err);
+            process::exit(1);
+        }
+    };
+
+    let mut emulator = Emulator::headless();
+    if let Err(err) = emulator.load(&program) {
+        eprintln!("failed to load {}: {}", path, err);
+        process::exit(1);
+    }
+
+    if let Err(err) = tui::run(&mut emulator, ticks_per_frame) {
+        eprintln!("terminal error: {}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,312 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Variant};
+
+/// Diagnostic programs of `generate_test_rom`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Test {
+    /// Shows the digit of every held key where it sits on the keypad, using EXA1.
+    Keypad,
+    /// Draws a border along the edges of the display and the digits 0 to F in two rows, to check
+    /// orientation, scaling and clipping.
+    Display,
+    /// Counts seconds from 00 to 99 with the delay timer and beeps briefly every second.
+    Timing,
+}
+
+/// Programs that ship with the emulator.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Builtin {
+    /// Draws the CHIP-8 logo and a "LOAD A ROM" prompt, then halts. Frontends show it until the
+    /// user picks a program.
+    Splash,
+}
+
+// 4 by 5 letters of the splash texts, indexed by the splash text table.
+const SPLASH_GLYPHS: [[u8; 5]; 13] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0xf0, 0x80, 0x80, 0x80, 0xf0], // C
+    [0x90, 0x90, 0xf0, 0x90, 0x90], // H
+    [0xe0, 0x40, 0x40, 0x40, 0xe0], // I
+    [0xf0, 0x90, 0xf0, 0x80, 0x80], // P
+    [0x00, 0x00, 0xf0, 0x00, 0x00], // -
+    [0xf0, 0x90, 0xf0, 0x90, 0xf0], // 8
+    [0x80, 0x80, 0x80, 0x80, 0xf0], // L
+    [0xf0, 0x90, 0x90, 0x90, 0xf0], // O
+    [0xf0, 0x90, 0xf0, 0x90, 0x90], // A
+    [0xe0, 0x90, 0x90, 0x90, 0xe0], // D
+    [0xe0, 0x90, 0xe0, 0x90, 0x90], // R
+    [0x90, 0xf0, 0xf0, 0x90, 0x90], // M
+];
+
+// Glyphs of "CHIP-8" and "LOAD A ROM", each text ending with END_OF_TEXT. The program stores them
+// as offsets into the glyph data.
+const SPLASH_TEXT: [u8; 18] = [1, 2, 3, 4, 5, 6, END_OF_TEXT, 7, 8, 9, 10, 0, 9, 0, 11, 8, 12, END_OF_TEXT];
+const END_OF_TEXT: u8 = 0xff;
+
+// Assembles the splash program for the given load address.
+fn splash(base: u16) -> Vec<u8> {
+    let (underline, halt, text, table) = (base + 0x14, base + 0x1c, base + 0x1e, base + 0x34);
+    let (bar, glyphs) = (table + SPLASH_TEXT.len() as u16, table + SPLASH_TEXT.len() as u16 + 1);
+    let code = [
+        // v1 := 17, v2 := 8, v3 := 0, text
+        0x6111,
+        0x6208,
+        0x6300,
+        0x2000 | text,
+        // v1 := 7, v2 := 20, text
+        0x6107,
+        0x6214,
+        0x2000 | text,
+        // Underline the logo: v1 := 15, v2 := 14, i := bar, loop: sprite v1 v2 1, v1 += 8,
+        // if v1 != 47 then jump loop
+        0x610f,
+        0x620e,
+        0xa000 | bar,
+        0xd121,
+        0x7108,
+        0x312f,
+        0x1000 | underline,
+        // halt: jump halt
+        0x1000 | halt,
+        // text: i := table, i += v3, load v0, v3 += 1, if v0 == END_OF_TEXT then return,
+        // i := glyphs, i += v0, sprite v1 v2 5, v1 += 5, jump text
+        0xa000 | table,
+        0xf31e,
+        0xf065,
+        0x7301,
+        0x4000 | u16::from(END_OF_TEXT),
+        0x00ee,
+        0xa000 | glyphs,
+        0xf01e,
+        0xd125,
+        0x7105,
+        0x1000 | text,
+    ];
+
+    let mut program = assemble(&code);
+    program.extend(SPLASH_TEXT.iter().map(|&glyph| if glyph == END_OF_TEXT { glyph } else { glyph * 5 }));
+    program.push(0xff);
+    program.extend(SPLASH_GLYPHS.iter().flatten());
+    program
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Loads a program that ships with the emulator, e.g. the splash screen to show before the
+    /// user picked a ROM.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Builtin, Emulator};
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_builtin(Builtin::Splash);
+    /// emulator.run_cycles(1000);
+    /// assert!(emulator.pixels().any(|pixel| pixel.is_lit()));
+    /// assert!(emulator.is_idle());
+    /// ```
+    pub fn load_builtin(&mut self, builtin: Builtin) {
+        // The builtin programs are a few hundred bytes, they fit into every variant.
+        let _ = self.load(&program(builtin, self.variant));
+    }
+}
+
+// Column and row of every key on the hex keypad, indexed by key:
+//   1 2 3 C
+//   4 5 6 D
+//   7 8 9 E
+//   A 0 B F
+const KEYPAD_LAYOUT: [(u8, u8); 16] = [
+    (1, 3),
+    (0, 0),
+    (1, 0),
+    (2, 0),
+    (0, 1),
+    (1, 1),
+    (2, 1),
+    (0, 2),
+    (1, 2),
+    (2, 2),
+    (0, 3),
+    (2, 3),
+    (3, 0),
+    (3, 1),
+    (3, 2),
+    (3, 3),
+];
+
+fn assemble(code: &[u16]) -> Vec<u8> { code.iter().flat_map(|word| word.to_be_bytes()).collect() }
+
+fn keypad_test(base: u16) -> Vec<u8> {
+    let (next, toggle) = (base + 0x02, base + 0x1a);
+    let (positions, state) = (base + 0x30, base + 0x50);
+    let code = [
+        // v5 := 0
+        0x6500,
+        // next: v4 := key v5 held, i := state + v5, load v0, if v0 != v4 then toggle
+        0x6400,
+        0xe5a1,
+        0x6401,
+        0xa000 | state,
+        0xf51e,
+        0xf065,
+        0x5040,
+        0x2000 | toggle,
+        // v5 += 1, if v5 != 16 then jump next, jump start
+        0x7501,
+        0x3510,
+        0x1000 | next,
+        0x1000 | base,
+        // toggle: save v4 at state + v5, i := positions + 2 * v5, load v0 - v1, i := hex v5,
+        // sprite v0 v1 5, return
+        0xa000 | state,
+        0xf51e,
+        0x8040,
+        0xf055,
+        0xa000 | positions,
+        0xf51e,
+        0xf51e,
+        0xf165,
+        0xf529,
+        0xd015,
+        0x00ee,
+    ];
+
+    let mut program = assemble(&code);
+    program.extend(KEYPAD_LAYOUT.iter().flat_map(|&(column, row)| [14 + column * 10, 2 + row * 8]));
+    program.extend_from_slice(&[0; 16]);
+    program
+}
+
+fn display_test(base: u16) -> Vec<u8> {
+    let (row, digit, halt) = (base + 0x08, base + 0x2a, base + 0x3e);
+    let (line, column) = (base + 0x40, base + 0x41);
+    let code = [
+        // i := line, v0 := 0, v1 := 0, v2 := 31, row: sprite v0 v1 1, sprite v0 v2 1, v0 += 8,
+        // if v0 != 64 then jump row
+        0xa000 | line,
+        0x6000,
+        0x6100,
+        0x621f,
+        0xd011,
+        0xd021,
+        0x7008,
+        0x3040,
+        0x1000 | row,
+        // i := column, v0 := 0, v1 := 63, draw both columns from y 1 and from y 16
+        0xa000 | column,
+        0x6000,
+        0x613f,
+        0x6201,
+        0xd02f,
+        0xd12f,
+        0x6210,
+        0xd02f,
+        0xd12f,
+        // v3 := 0, v0 := 4, v1 := 6, digit: i := hex v3, sprite v0 v1 5, v3 += 1, v0 += 7,
+        // if v3 == 8 then v0 := 4 and v1 := 20, if v3 != 16 then jump digit
+        0x6300,
+        0x6004,
+        0x6106,
+        0xf329,
+        0xd015,
+        0x7301,
+        0x7007,
+        0x4308,
+        0x6004,
+        0x4308,
+        0x6114,
+        0x3310,
+        0x1000 | digit,
+        // halt: jump halt
+        0x1000 | halt,
+    ];
+
+    let mut program = assemble(&code);
+    program.push(0xff);
+    program.extend_from_slice(&[0x80; 15]);
+    program
+}
+
+fn timing_test(base: u16) -> Vec<u8> {
+    let (second, wait, bcd) = (base + 0x02, base + 0x22, base + 0x32);
+    let code = [
+        // v3 := 0, second: delay := 60 and buzzer := 2 first, so drawing takes none of the second
+        0x6300,
+        0x6a3c,
+        0xfa15,
+        0x6a02,
+        0xfa18,
+        // clear, bcd v3, load v0 - v2, show the tens and ones at (26, 13)
+        0x00e0,
+        0xa000 | bcd,
+        0xf333,
+        0xa000 | bcd,
+        0xf265,
+        0x651a,
+        0x660d,
+        0xf129,
+        0xd565,
+        0x7506,
+        0xf229,
+        0xd565,
+        // wait: if delay != 0 then jump wait
+        0xfa07,
+        0x3a00,
+        0x1000 | wait,
+        // v3 += 1, if v3 != 100 then jump second, v3 := 0, jump second
+        0x7301,
+        0x3364,
+        0x1000 | second,
+        0x6300,
+        0x1000 | second,
+    ];
+
+    let mut program = assemble(&code);
+    program.extend_from_slice(&[0; 3]);
+    program
+}
+
+/// Assembles a diagnostic program for the load address of the default variant, so frontends can
+/// check their input, rendering and timer plumbing without a ROM file.
+///
+/// # Example
+///
+/// ```
+/// use wasm_chip8::{generate_test_rom, Emulator, Test};
+/// let mut emulator = Emulator::new();
+/// emulator.load(&generate_test_rom(Test::Keypad)).unwrap();
+/// emulator.run_cycles(1000);
+/// assert!(emulator.pixels().all(|pixel| !pixel.is_lit()));
+/// emulator.set_key(0x5, true);
+/// emulator.tick_frame();
+/// emulator.run_cycles(1000);
+/// // The 5 glyph in the second column of the second row.
+/// assert!(emulator.pixels().any(|pixel| pixel.is_lit() && (24..28).contains(&pixel.x) && pixel.y == 10));
+///
+/// emulator.reset();
+/// emulator.load(&generate_test_rom(Test::Timing)).unwrap();
+/// for _ in 0..61 {
+///     emulator.tick_frame();
+/// }
+/// assert_eq!(emulator.v(3), 1);
+/// ```
+#[wasm_bindgen]
+pub fn generate_test_rom(test: Test) -> Vec<u8> {
+    let base = Variant::default().load_address() as u16;
+    match test {
+        Test::Keypad => keypad_test(base),
+        Test::Display => display_test(base),
+        Test::Timing => timing_test(base),
+    }
+}
+
+/// Assembles a built-in program for a variant.
+pub(crate) fn program(builtin: Builtin, variant: Variant) -> Vec<u8> {
+    match builtin {
+        Builtin::Splash => splash(variant.load_address() as u16),
+    }
+}
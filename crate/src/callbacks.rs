@@ -0,0 +1,67 @@
+use js_sys::Function;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Object passed to frame listeners.
+#[derive(Serialize)]
+struct FrameStats {
+    // Index of the frame that just ended and how many instructions it executed.
+    frame: u64,
+    cycles: u32,
+    // Whether the display changed during the frame and the beep is sounding.
+    drawn: bool,
+    sound: bool,
+    // Display after the frame, packed like `pixels_packed`.
+    pixels: Vec<u8>,
+}
+
+/// JS functions called after every frame.
+#[derive(Default)]
+pub struct FrameListeners {
+    next_id: u32,
+    listeners: Vec<(u32, Function)>,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Registers a function called at the end of every frame with
+    /// `{ frame, cycles, drawn, sound, pixels }`. Returns an id for `off_frame`.
+    ///
+    /// Listeners run while the emulator is busy, so they must not call back into it. Everything a
+    /// renderer needs is part of the object. Exceptions thrown by a listener are ignored.
+    pub fn on_frame(&mut self, callback: Function) -> u32 {
+        let id = self.frame_listeners.next_id;
+        self.frame_listeners.next_id += 1;
+        self.frame_listeners.listeners.push((id, callback));
+        id
+    }
+
+    /// Removes a listener registered with `on_frame`. Returns `false` for unknown ids.
+    pub fn off_frame(&mut self, id: u32) -> bool {
+        let listeners = &mut self.frame_listeners.listeners;
+        let count = listeners.len();
+        listeners.retain(|(listener, _)| *listener != id);
+        listeners.len() != count
+    }
+}
+
+impl Emulator {
+    pub(crate) fn notify_frame(&self) {
+        if self.frame_listeners.listeners.is_empty() {
+            return;
+        }
+
+        let stats = crate::utils::to_js(&FrameStats {
+            frame: self.frame,
+            cycles: self.frame_cycle,
+            drawn: self.draw_flag,
+            sound: self.sound_timer > 0,
+            pixels: self.pixels_packed(),
+        });
+        for (_, listener) in &self.frame_listeners.listeners {
+            let _ = listener.call1(&JsValue::NULL, &stats);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Instruction};
+
+/// Straight-line run of instructions that is only entered at its first one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BasicBlock {
+    /// Address of the first instruction and one past the last one.
+    pub start: u16,
+    pub end: u16,
+    /// Disassembly of every instruction.
+    pub instructions: Vec<String>,
+    /// Whether coverage saw any of the instructions execute.
+    pub executed: bool,
+}
+
+/// How control gets from one block to another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    /// To the next instruction, also from a call to the instruction it returns to.
+    Fallthrough,
+    Jump,
+    Call,
+    /// Over the next instruction of a skip.
+    Skip,
+    /// From a return to where it returned, only known from coverage.
+    Return,
+    /// From BNNN to where it jumped, only known from coverage.
+    Indirect,
+}
+
+/// Edge between the blocks starting at `from` and `to`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Edge {
+    pub from: u16,
+    pub to: u16,
+    pub kind: EdgeKind,
+    /// Whether the edge was taken from coverage instead of the program text.
+    pub dynamic: bool,
+}
+
+/// Basic blocks of the loaded program and the edges between them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ControlFlowGraph {
+    /// Blocks in address order.
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<Edge>,
+}
+
+// Static successors of an instruction at `address` that is `size` bytes long.
+fn successors(
+    emulator: &Emulator,
+    instruction: Instruction,
+    address: usize,
+    size: usize,
+) -> Vec<(usize, EdgeKind)> {
+    let next = address + size;
+    match instruction {
+        Instruction::Jump(nnn) => vec![(usize::from(nnn), EdgeKind::Jump)],
+        Instruction::Call(nnn) => vec![(usize::from(nnn), EdgeKind::Call), (next, EdgeKind::Fallthrough)],
+        Instruction::Return | Instruction::JumpOffset(_) | Instruction::Unknown(_) => vec![],
+        Instruction::SkipEqImm { .. }
+        | Instruction::SkipNeImm { .. }
+        | Instruction::SkipEq { .. }
+        | Instruction::SkipNe { .. }
+        | Instruction::SkipKey { .. }
+        | Instruction::SkipNotKey { .. }
+        | Instruction::SkipKey2 { .. }
+        | Instruction::SkipNotKey2 { .. } => {
+            vec![(next, EdgeKind::Fallthrough), (next + emulator.instruction_size_at(next), EdgeKind::Skip)]
+        }
+        _ => vec![(next, EdgeKind::Fallthrough)],
+    }
+}
+
+// Returns `true` if no instruction follows `instruction` in the same block.
+fn ends_block(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Jump(_)
+            | Instruction::Call(_)
+            | Instruction::Return
+            | Instruction::JumpOffset(_)
+            | Instruction::Unknown(_)
+            | Instruction::SkipEqImm { .. }
+            | Instruction::SkipNeImm { .. }
+            | Instruction::SkipEq { .. }
+            | Instruction::SkipNe { .. }
+            | Instruction::SkipKey { .. }
+            | Instruction::SkipNotKey { .. }
+            | Instruction::SkipKey2 { .. }
+            | Instruction::SkipNotKey2 { .. }
+    )
+}
+
+impl Emulator {
+    /// Splits the loaded program into basic blocks connected by jumps, calls and skips.
+    ///
+    /// Code is found like `export_listing` finds it. With coverage enabled (see
+    /// `set_coverage_enabled`) blocks tell whether they ran, and returns and BNNN jumps the
+    /// program took become dynamic edges.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{EdgeKind, Emulator};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_coverage_enabled(true);
+    /// // call sub, loop: jump loop, sub: v0 := 1, return
+    /// emulator.load(&[0x22, 0x04, 0x12, 0x02, 0x60, 0x01, 0x00, 0xee]).unwrap();
+    /// emulator.tick_frame();
+    /// let graph = emulator.control_flow_graph();
+    /// let starts: Vec<u16> = graph.blocks.iter().map(|block| block.start).collect();
+    /// assert_eq!(starts, [0x200, 0x202, 0x204]);
+    /// assert_eq!(graph.blocks[2].instructions, ["LD V0, 0x01", "RET"]);
+    /// let returns = graph.edges.iter().find(|edge| edge.kind == EdgeKind::Return).unwrap();
+    /// assert_eq!((returns.from, returns.to, returns.dynamic), (0x204, 0x202, true));
+    /// ```
+    pub fn control_flow_graph(&self) -> ControlFlowGraph {
+        let analysis = self.analyze();
+        let (start, end) = (analysis.start, analysis.end);
+        let in_program = |address: usize| analysis.is_code(address);
+        let decode = |address: usize| Instruction::decode(self.word_at(address), self.variant);
+
+        // Targets of the program text and of coverage start blocks.
+        let dynamic: Vec<(usize, usize)> =
+            self.coverage.transfers().filter(|&(from, to)| in_program(from) && in_program(to)).collect();
+        let mut leaders: BTreeSet<usize> = dynamic.iter().map(|&(_, to)| to).collect();
+        leaders.insert(start);
+        for address in (start..end).filter(|&address| in_program(address)) {
+            let instruction = decode(address);
+            if ends_block(instruction) {
+                let targets = successors(self, instruction, address, self.instruction_size_at(address));
+                leaders.extend(targets.into_iter().map(|(target, _)| target));
+            }
+        }
+
+        // Block start and the address of its last instruction.
+        let mut blocks: Vec<(BasicBlock, usize)> = vec![];
+        let mut open = false;
+        for address in (start..end).filter(|&address| in_program(address)) {
+            let instruction = decode(address);
+            let size = self.instruction_size_at(address).min(end - address);
+            let continues = open
+                && !leaders.contains(&address)
+                && blocks.last().is_some_and(|(block, _)| usize::from(block.end) == address);
+            if !continues {
+                let block = BasicBlock {
+                    start: address as u16,
+                    end: address as u16,
+                    instructions: vec![],
+                    executed: false,
+                };
+                blocks.push((block, address));
+            }
+            if let Some((block, last)) = blocks.last_mut() {
+                block.end = (address + size) as u16;
+                block.instructions.push(instruction.to_string());
+                block.executed |= self.coverage.was_executed(address);
+                *last = address;
+            }
+            open = !ends_block(instruction);
+        }
+
+        let block_at = |address: usize| blocks.iter().any(|(block, _)| usize::from(block.start) == address);
+        let mut edges = BTreeSet::new();
+        for (block, last) in blocks.iter() {
+            let instruction = decode(*last);
+            for (target, kind) in successors(self, instruction, *last, self.instruction_size_at(*last)) {
+                if block_at(target) {
+                    edges.insert(Edge { from: block.start, to: target as u16, kind, dynamic: false });
+                }
+            }
+        }
+        for &(from, to) in dynamic.iter() {
+            let kind = match decode(from) {
+                Instruction::Return => EdgeKind::Return,
+                Instruction::JumpOffset(_) => EdgeKind::Indirect,
+                _ => continue,
+            };
+            let block = blocks.iter().find(|&&(_, last)| last == from);
+            if let Some((block, _)) = block.filter(|_| block_at(to)) {
+                edges.insert(Edge { from: block.start, to: to as u16, kind, dynamic: true });
+            }
+        }
+
+        ControlFlowGraph {
+            blocks: blocks.into_iter().map(|(block, _)| block).collect(),
+            edges: edges.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `control_flow_graph` as a plain JS object for graph libraries such as d3.
+    #[wasm_bindgen(js_name = control_flow_graph)]
+    pub fn control_flow_graph_js(&self) -> JsValue { crate::utils::to_js(&self.control_flow_graph()) }
+}
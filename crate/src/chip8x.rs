@@ -0,0 +1,229 @@
+use wasm_bindgen::prelude::*;
+
+use crate::utils::Fnv1a;
+use crate::{Emulator, CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH};
+
+/// Colors of the VP-590 color board in 0xRRGGBB, indexed by the three bit color value of BXYN.
+pub const CHIP8X_COLORS: [u32; 8] = [
+    0x000000, // black
+    0xff0000, // red
+    0x0000ff, // blue
+    0xff00ff, // violet
+    0x00ff00, // green
+    0xffff00, // yellow
+    0x00ffff, // aqua
+    0xffffff, // white
+];
+
+/// Background colors 02A0 cycles through, starting with blue.
+pub const CHIP8X_BACKGROUNDS: [u32; 4] = [0x000080, 0x000000, 0x008000, 0x800000];
+
+// Width of a color zone in pixels and the height of a zone selected by BXY0.
+const ZONE_WIDTH: usize = 8;
+const ZONE_HEIGHT: usize = 4;
+const ZONES: usize = CHIP8_DISPLAY_WIDTH / ZONE_WIDTH;
+
+/// Size of the color board in a savestate: the background followed by the zones row by row.
+pub(crate) const COLOR_BOARD_BYTES: usize = 1 + ZONES * CHIP8_DISPLAY_HEIGHT;
+
+// Color the zones have after a reset.
+const DEFAULT_ZONE_COLOR: u8 = 1;
+
+/// State of the CHIP-8X color board and of the second keypad.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ColorBoard {
+    // Index into `CHIP8X_BACKGROUNDS`.
+    background: usize,
+    // Foreground color of every 8 pixel wide zone of every display row.
+    zones: [[u8; ZONES]; CHIP8_DISPLAY_HEIGHT],
+    // Keys held on the second keypad, bit N for key N. They are not part of savestates.
+    keypad2: u16,
+}
+
+impl Default for ColorBoard {
+    fn default() -> ColorBoard {
+        ColorBoard { background: 0, zones: [[DEFAULT_ZONE_COLOR; ZONES]; CHIP8_DISPLAY_HEIGHT], keypad2: 0 }
+    }
+}
+
+impl ColorBoard {
+    /// Restores the background and the zone colors, keys stay held.
+    pub(crate) fn clear(&mut self) { *self = ColorBoard { keypad2: self.keypad2, ..ColorBoard::default() }; }
+
+    pub(crate) fn cycle_background(&mut self) {
+        self.background = (self.background + 1) % CHIP8X_BACKGROUNDS.len();
+    }
+
+    /// Feeds the bytes of `to_bytes` to the hasher without allocating them.
+    pub(crate) fn hash(&self, hasher: &mut Fnv1a) {
+        hasher.write(&[self.background as u8]);
+        for row in self.zones.iter() {
+            hasher.write(row);
+        }
+    }
+
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(COLOR_BOARD_BYTES);
+        bytes.push(self.background as u8);
+        for row in self.zones.iter() {
+            bytes.extend_from_slice(row);
+        }
+        bytes
+    }
+
+    /// Takes the colors of a restored savestate, keys stay held.
+    pub(crate) fn restore(&mut self, saved: ColorBoard) { *self = ColorBoard { keypad2: self.keypad2, ..saved }; }
+
+    /// Reads what `to_bytes` wrote, nothing if a value is out of range.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<ColorBoard> {
+        let (&background, zones) = bytes.split_first()?;
+        if usize::from(background) >= CHIP8X_BACKGROUNDS.len() || zones.len() != ZONES * CHIP8_DISPLAY_HEIGHT {
+            return None;
+        }
+        if zones.iter().any(|&color| color > 0x7) {
+            return None;
+        }
+
+        let mut board = ColorBoard { background: usize::from(background), ..ColorBoard::default() };
+        for (row, colors) in board.zones.iter_mut().zip(zones.chunks(ZONES)) {
+            row.copy_from_slice(colors);
+        }
+        Some(board)
+    }
+
+    pub(crate) fn background(&self) -> u32 { CHIP8X_BACKGROUNDS[self.background] }
+
+    /// Colors the zones selected by the BXYN operands `vx` and `vy`.
+    ///
+    /// The low nibble of `vx` is the first zone column and the high nibble the number of further
+    /// columns. With `n` 0 `vy` selects 4 pixel high zone rows the same way, otherwise it is the
+    /// first pixel row of `n` rows.
+    pub(crate) fn color(&mut self, vx: u8, vy: u8, n: u8, color: u8) {
+        let columns = zone_range(vx, 1, ZONES);
+        let rows = if n == 0 {
+            zone_range(vy, ZONE_HEIGHT, CHIP8_DISPLAY_HEIGHT)
+        } else {
+            let first = usize::from(vy) % CHIP8_DISPLAY_HEIGHT;
+            first..(first + usize::from(n)).min(CHIP8_DISPLAY_HEIGHT)
+        };
+
+        for row in rows {
+            for zone in columns.clone() {
+                self.zones[row][zone] = color & 0x7;
+            }
+        }
+    }
+
+    /// 0xRRGGBB color of a display pixel.
+    pub(crate) fn pixel_color(&self, x: usize, y: usize, lit: bool) -> u32 {
+        if lit {
+            CHIP8X_COLORS[usize::from(self.zones[y][x / ZONE_WIDTH])]
+        } else {
+            self.background()
+        }
+    }
+
+    pub(crate) fn set_key(&mut self, key: u8, pressed: bool) {
+        if key <= 0xf {
+            if pressed {
+                self.keypad2 |= 1 << key;
+            } else {
+                self.keypad2 &= !(1 << key);
+            }
+        }
+    }
+
+    pub(crate) fn is_key_pressed(&self, key: u8) -> bool { key <= 0xf && self.keypad2 & (1 << key) != 0 }
+}
+
+// Zones `value & 0xf` to `value & 0xf + value >> 4`, each `size` units high, clipped to `limit`.
+fn zone_range(value: u8, size: usize, limit: usize) -> std::ops::Range<usize> {
+    let first = usize::from(value & 0xf) * size;
+    let last = first + (usize::from(value >> 4) + 1) * size;
+    first.min(limit)..last.min(limit)
+}
+
+/// Adds VY to VX nibble by nibble, each nibble wrapping at 8 like the color values of the VP-590.
+pub(crate) fn add_octal(vx: u8, vy: u8) -> u8 {
+    ((vx & 0x70) + (vy & 0x70)) & 0x70 | ((vx & 0x07) + (vy & 0x07)) & 0x07
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Presses or releases a key of the second CHIP-8X keypad, read by EXF2 and EXF5.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Variant};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_variant(Variant::Chip8X);
+    /// emulator.set_key2(0x3, true);
+    /// // v0 := 3, if key2 v0 then skip, v1 := 1
+    /// emulator.load(&[0x60, 0x03, 0xe0, 0xf2, 0x61, 0x01]).unwrap();
+    /// emulator.tick();
+    /// emulator.tick();
+    /// assert_eq!(emulator.pc(), 0x306);
+    /// ```
+    pub fn set_key2(&mut self, key: u8, pressed: bool) { self.color_board.set_key(key, pressed); }
+
+    /// Returns `true` if the key of the second CHIP-8X keypad is held.
+    pub fn is_key2_pressed(&self, key: u8) -> bool { self.color_board.is_key_pressed(key) }
+
+    /// Current 0xRRGGBB CHIP-8X background color.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Variant};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_variant(Variant::Chip8X);
+    /// // cycle background
+    /// emulator.load(&[0x02, 0xa0]).unwrap();
+    /// let (background, snapshot, hash) = (emulator.chip8x_background(), emulator.snapshot(), emulator.state_hash());
+    /// emulator.tick();
+    /// assert_ne!(emulator.chip8x_background(), background);
+    /// emulator.restore_snapshot(&snapshot);
+    /// assert_eq!(emulator.chip8x_background(), background);
+    /// assert_eq!(emulator.state_hash(), hash);
+    /// ```
+    pub fn chip8x_background(&self) -> u32 { self.color_board.background() }
+}
+
+impl Emulator {
+    /// 0xRRGGBB color of every display pixel as the CHIP-8X color board shows it, in row-major order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Variant, CHIP8X_COLORS};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_variant(Variant::Chip8X);
+    /// // v0 := 0, v1 := 4 (green), v2 := 0, col v0, v2, 0, i := font 0, drw v0, v2, 5
+    /// emulator.load(&[0x60, 0x00, 0x61, 0x04, 0x62, 0x00, 0xb0, 0x20, 0xf0, 0x29, 0xd0, 0x25]).unwrap();
+    /// for _ in 0..6 {
+    ///     emulator.tick();
+    /// }
+    /// assert_eq!(emulator.chip8x_colors()[0], CHIP8X_COLORS[4]);
+    /// ```
+    pub fn chip8x_colors(&self) -> Vec<u32> {
+        self.pixels().map(|pixel| self.color_board.pixel_color(pixel.x, pixel.y, pixel.is_lit())).collect()
+    }
+
+    pub(crate) fn cycle_background(&mut self) {
+        self.color_board.cycle_background();
+        self.draw_flag = true;
+        self.next_opcode();
+    }
+
+    pub(crate) fn set_zone_color(&mut self, x: usize, y: usize, n: u8) {
+        let color = self.v[(x + 1) % 16];
+        self.color_board.color(self.v[x], self.v[y], n, color);
+        self.draw_flag = true;
+        self.next_opcode();
+    }
+
+    pub(crate) fn skip_key2(&mut self, key: u8, pressed: bool) {
+        self.skip_if(self.color_board.is_key_pressed(key) == pressed);
+    }
+}
@@ -0,0 +1,128 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Variant, CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH};
+
+/// Reason a reference image could not be compared with the display.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareError {
+    /// The data is neither a PNG file nor a raw frame.
+    UnknownFormat,
+    /// The PNG file ends early or its contents are invalid.
+    Corrupted,
+    /// The PNG file uses an encoding that is not supported, or the `png` feature is disabled.
+    Unsupported,
+    /// The image is not the display scaled by a whole number.
+    SizeMismatch,
+}
+
+/// Differences between the display and a reference image.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DiffReport {
+    /// Display pixels that differ.
+    pub differing: usize,
+    /// 1 for every display pixel that differs and 0 for every other one, in row-major order.
+    pub diff: Vec<u8>,
+}
+
+impl Emulator {
+    /// Compares the display with a reference image, for golden image tests of frontends and of
+    /// programs under different quirks.
+    ///
+    /// The image is either a raw frame in the format of `pixels_packed`, compared plane by plane,
+    /// or a PNG screenshot of the display scaled by a whole number (with the `png` feature),
+    /// compared with the colors `render_rgba` would use. Every scaled pixel is sampled at its
+    /// center, so grids and scanlines in the screenshot do not count as differences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{CompareError, Emulator};
+    /// let mut emulator = Emulator::new();
+    /// let golden = emulator.pixels_packed();
+    /// // i := font 0, sprite v0 v0 5
+    /// emulator.load(&[0xf0, 0x29, 0xd0, 0x05]).unwrap();
+    /// emulator.tick();
+    /// emulator.tick();
+    /// let report = emulator.compare_frame(&golden).unwrap();
+    /// // The 4 by 5 outline of the 0 glyph.
+    /// assert_eq!(report.differing, 14);
+    /// assert_eq!(report.diff[0..5], [1, 1, 1, 1, 0]);
+    /// assert_eq!(emulator.compare_frame(&[0; 10]), Err(CompareError::UnknownFormat));
+    /// ```
+    ///
+    /// Comparing with a screenshot:
+    ///
+    /// ```
+    /// # #[cfg(feature = "png")] {
+    /// use wasm_chip8::{DisplayStyle, Emulator};
+    /// # fn png(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    /// #     let rows: Vec<u8> = rgba.chunks(width * 4).flat_map(|row| [0].iter().chain(row)).copied().collect();
+    /// #     let chunk = |kind: &[u8], body: &[u8]| {
+    /// #         [&(body.len() as u32).to_be_bytes()[..], kind, body, &[0; 4]].concat()
+    /// #     };
+    /// #     let header = [&(width as u32).to_be_bytes()[..], &(height as u32).to_be_bytes(), &[8, 6, 0, 0, 0]];
+    /// #     let data = miniz_oxide::deflate::compress_to_vec_zlib(&rows, 6);
+    /// #     let chunks = [chunk(b"IHDR", &header.concat()), chunk(b"IDAT", &data), chunk(b"IEND", &[])];
+    /// #     [b"\x89PNG\r\n\x1a\n".to_vec(), chunks.concat()].concat()
+    /// # }
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_display_scale(4);
+    /// emulator.set_display_style(DisplayStyle::new(true, false, 0.0));
+    /// emulator.render_rgba();
+    /// let golden = png(emulator.rgba_width(), emulator.rgba_height(), emulator.rgba());
+    /// assert_eq!(emulator.compare_frame(&golden).unwrap().differing, 0);
+    /// // i := font 0, sprite v0 v0 5
+    /// emulator.load(&[0xf0, 0x29, 0xd0, 0x05]).unwrap();
+    /// emulator.tick();
+    /// emulator.tick();
+    /// assert_eq!(emulator.compare_frame(&golden).unwrap().differing, 14);
+    /// # }
+    /// ```
+    pub fn compare_frame(&self, png_or_raw: &[u8]) -> Result<DiffReport, CompareError> {
+        let size = CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT;
+        let diff: Vec<u8> = if png_or_raw.len() == size {
+            self.pixels().zip(png_or_raw).map(|(pixel, &planes)| u8::from(pixel.planes != planes)).collect()
+        } else {
+            let expected: Vec<u32> = if self.variant == Variant::Chip8X {
+                self.chip8x_colors()
+            } else {
+                self.pixels().map(|pixel| self.renderer.color(pixel.planes)).collect()
+            };
+            let colors = self.decode_reference(png_or_raw)?;
+            expected.iter().zip(colors).map(|(&expected, color)| u8::from(expected != color)).collect()
+        };
+
+        Ok(DiffReport { differing: diff.iter().filter(|&&differs| differs != 0).count(), diff })
+    }
+
+    // Color of every display pixel in a PNG screenshot.
+    #[cfg(feature = "png")]
+    fn decode_reference(&self, png: &[u8]) -> Result<Vec<u32>, CompareError> {
+        let (width, height, colors) = crate::png::decode(png)?;
+        let scale = width / CHIP8_DISPLAY_WIDTH;
+        if scale == 0 || width != CHIP8_DISPLAY_WIDTH * scale || height != CHIP8_DISPLAY_HEIGHT * scale {
+            return Err(CompareError::SizeMismatch);
+        }
+
+        let sample = |x: usize, y: usize| colors[(y * scale + scale / 2) * width + x * scale + scale / 2];
+        Ok(self.pixels().map(|pixel| sample(pixel.x, pixel.y)).collect())
+    }
+
+    #[cfg(not(feature = "png"))]
+    fn decode_reference(&self, png: &[u8]) -> Result<Vec<u32>, CompareError> {
+        const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+        Err(if png.starts_with(SIGNATURE) { CompareError::Unsupported } else { CompareError::UnknownFormat })
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `compare_frame` as a plain JS object.
+    #[wasm_bindgen(js_name = compare_frame)]
+    pub fn compare_frame_js(&self, png_or_raw: &[u8]) -> Result<JsValue, CompareError> {
+        self.compare_frame(png_or_raw).map(|report| crate::utils::to_js(&report))
+    }
+}
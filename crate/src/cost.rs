@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::instruction::PATTERNS;
+use crate::{Emulator, Instruction, Variant};
+
+/// Clock of the COSMAC VIP in Hz. A machine cycle of its CDP1802 takes 8 clock periods.
+pub const VIP_CLOCK_HZ: u32 = 1_760_900;
+const CLOCKS_PER_MACHINE_CYCLE: f64 = 8.0;
+
+/// What the extra cycles of an opcode are counted for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CostUnit {
+    /// The opcode always costs the same.
+    None,
+    /// Once if the next instruction is skipped.
+    Skip,
+    /// For every sprite row drawn.
+    Row,
+    /// For every register stored or loaded.
+    Register,
+}
+
+/// Emulated cost of an opcode on the VIP interpreter in CDP1802 machine cycles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct OpcodeCost {
+    pub pattern: &'static str,
+    pub mnemonic: &'static str,
+    pub cycles: u32,
+    /// Cycles added for every `unit`.
+    pub extra_cycles: u32,
+    pub unit: CostUnit,
+}
+
+/// Cycles spent on one mnemonic during a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct CostEntry {
+    pub mnemonic: &'static str,
+    pub count: u64,
+    pub cycles: u64,
+}
+
+/// Where the emulated VIP time of a run went.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct CostBreakdown {
+    pub cycles: u64,
+    /// How long the VIP would have taken for the cycles, in milliseconds.
+    pub vip_ms: f64,
+    /// Mnemonics by descending cycles.
+    pub entries: Vec<CostEntry>,
+}
+
+// Base cycles, extra cycles and their unit. The numbers follow published measurements of the
+// VIP interpreter. They leave out the wait for the display interrupt, which dominates DXYN and
+// varies with the timing of the program. Opcodes the VIP interpreter lacks cost nothing.
+fn model(instruction: Instruction) -> (u32, u32, CostUnit) {
+    match instruction {
+        Instruction::ClearScreen | Instruction::CycleBackground => (24, 0, CostUnit::None),
+        Instruction::Return | Instruction::Jump(_) | Instruction::Call(_) | Instruction::JumpOffset(_) => {
+            (23, 0, CostUnit::None)
+        }
+        Instruction::SkipEqImm { .. } | Instruction::SkipNeImm { .. } => (12, 2, CostUnit::Skip),
+        Instruction::SkipEq { .. }
+        | Instruction::SkipNe { .. }
+        | Instruction::SkipKey { .. }
+        | Instruction::SkipNotKey { .. }
+        | Instruction::SkipKey2 { .. }
+        | Instruction::SkipNotKey2 { .. } => (16, 2, CostUnit::Skip),
+        Instruction::SetImm { .. } => (6, 0, CostUnit::None),
+        Instruction::AddImm { .. }
+        | Instruction::GetDelay { .. }
+        | Instruction::WaitKey { .. }
+        | Instruction::SetDelay { .. }
+        | Instruction::SetSound { .. } => (10, 0, CostUnit::None),
+        Instruction::Set { .. }
+        | Instruction::Or { .. }
+        | Instruction::And { .. }
+        | Instruction::Xor { .. }
+        | Instruction::Add { .. }
+        | Instruction::Sub { .. }
+        | Instruction::ShiftRight { .. }
+        | Instruction::SubReverse { .. }
+        | Instruction::ShiftLeft { .. }
+        | Instruction::AddOctal { .. } => (44, 0, CostUnit::None),
+        Instruction::SetI(_) => (12, 0, CostUnit::None),
+        Instruction::Random { .. } | Instruction::Color { .. } => (36, 0, CostUnit::None),
+        Instruction::Draw { .. } => (36, 11, CostUnit::Row),
+        Instruction::AddI { .. } => (19, 0, CostUnit::None),
+        Instruction::Font { .. } => (20, 0, CostUnit::None),
+        Instruction::Bcd { .. } => (204, 0, CostUnit::None),
+        Instruction::Store { .. } | Instruction::Load { .. } => (10, 8, CostUnit::Register),
+        Instruction::MachineCall(_)
+        | Instruction::ScrollDown(_)
+        | Instruction::ScrollRight
+        | Instruction::ScrollLeft
+        | Instruction::LongI
+        | Instruction::SelectPlanes(_)
+        | Instruction::Unknown(_) => (0, 0, CostUnit::None),
+    }
+}
+
+/// Cycles an executed instruction cost, `skipped` telling whether a skip was taken.
+pub(crate) fn cost(instruction: Instruction, skipped: bool) -> u32 {
+    let (cycles, extra, unit) = model(instruction);
+    let units = match (unit, instruction) {
+        (CostUnit::Skip, _) => u32::from(skipped),
+        (CostUnit::Row, Instruction::Draw { n, .. }) => u32::from(if n == 0 { 16 } else { n }),
+        (CostUnit::Register, Instruction::Store { x } | Instruction::Load { x }) => x as u32 + 1,
+        _ => 0,
+    };
+    cycles + extra * units
+}
+
+/// Cost of every opcode the VIP interpreter runs, so teachers can show why some games are slow
+/// on real hardware.
+///
+/// # Example
+///
+/// ```
+/// use wasm_chip8::{opcode_costs, CostUnit};
+/// let draw = opcode_costs().into_iter().find(|cost| cost.pattern == "DXYN").unwrap();
+/// assert_eq!(draw.unit, CostUnit::Row);
+/// assert!(opcode_costs().iter().all(|cost| cost.cycles > 0));
+/// ```
+pub fn opcode_costs() -> Vec<OpcodeCost> {
+    PATTERNS
+        .iter()
+        .filter_map(|&(pattern, sample, _)| {
+            let variant = Variant::ALL.iter().copied().find(|&variant| {
+                Instruction::decode(sample, variant).pattern() == pattern
+            })?;
+            let instruction = Instruction::decode(sample, variant);
+            let (cycles, extra_cycles, unit) = model(instruction);
+            Some(OpcodeCost { pattern, mnemonic: instruction.mnemonic(), cycles, extra_cycles, unit })
+        })
+        .filter(|cost| cost.cycles > 0)
+        .collect()
+}
+
+/// Returns `opcode_costs` as an array of plain JS objects.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = opcode_costs)]
+pub fn opcode_costs_js() -> JsValue { crate::utils::to_js(&opcode_costs()) }
+
+/// Cycles and counts per mnemonic of the instructions executed while tracking is on.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CostTracker {
+    enabled: bool,
+    totals: BTreeMap<&'static str, (u64, u64)>,
+}
+
+impl CostTracker {
+    pub(crate) fn is_enabled(&self) -> bool { self.enabled }
+
+    pub(crate) fn record(&mut self, instruction: Instruction, skipped: bool) {
+        let total = self.totals.entry(instruction.mnemonic()).or_insert((0, 0));
+        total.0 += 1;
+        total.1 += u64::from(cost(instruction, skipped));
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Starts or stops adding up the VIP cost of the executed instructions (see `opcode_costs`).
+    /// Starting forgets the previous run.
+    pub fn set_cost_tracking(&mut self, enabled: bool) {
+        self.costs = CostTracker { enabled, ..CostTracker::default() };
+    }
+}
+
+impl Emulator {
+    /// Breaks the emulated VIP time of the run down by mnemonic.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_cost_tracking(true);
+    /// // v0 := 1, i := font 0, sprite v0 v0 5
+    /// emulator.load(&[0x60, 0x01, 0xf0, 0x29, 0xd0, 0x05]).unwrap();
+    /// for _ in 0..3 {
+    ///     emulator.tick();
+    /// }
+    /// let breakdown = emulator.cost_breakdown();
+    /// assert_eq!(breakdown.entries[0].mnemonic, "DRW");
+    /// assert_eq!(breakdown.cycles, 6 + 20 + 36 + 5 * 11);
+    /// ```
+    pub fn cost_breakdown(&self) -> CostBreakdown {
+        let mut entries: Vec<CostEntry> = self
+            .costs
+            .totals
+            .iter()
+            .map(|(&mnemonic, &(count, cycles))| CostEntry { mnemonic, count, cycles })
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.cycles));
+        let cycles = entries.iter().map(|entry| entry.cycles).sum::<u64>();
+
+        CostBreakdown {
+            cycles,
+            vip_ms: cycles as f64 * CLOCKS_PER_MACHINE_CYCLE * 1000.0 / f64::from(VIP_CLOCK_HZ),
+            entries,
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `cost_breakdown` as a plain JS object.
+    #[wasm_bindgen(js_name = cost_breakdown)]
+    pub fn cost_breakdown_js(&self) -> JsValue { crate::utils::to_js(&self.cost_breakdown()) }
+}
@@ -0,0 +1,78 @@
+use std::collections::BTreeSet;
+
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Addresses instructions were executed from.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Coverage {
+    enabled: bool,
+    // One flag per memory address, allocated once coverage is enabled.
+    executed: Vec<bool>,
+    // Jumps, calls and returns seen, as the address of the instruction and where it went.
+    transfers: BTreeSet<(usize, usize)>,
+}
+
+impl Coverage {
+    pub(crate) fn clear(&mut self) {
+        self.executed.clear();
+        self.transfers.clear();
+    }
+
+    pub(crate) fn record(&mut self, address: usize, memory_size: usize) {
+        if !self.enabled || address >= memory_size {
+            return;
+        }
+        if self.executed.len() != memory_size {
+            self.executed.resize(memory_size, false);
+        }
+        self.executed[address] = true;
+    }
+
+    /// Records where the instruction at `from` went unless it continued with the next or the
+    /// instruction after that.
+    pub(crate) fn record_transfer(&mut self, from: usize, to: usize) {
+        if self.enabled && to != from && to != from + 2 && to != from + 4 {
+            self.transfers.insert((from, to));
+        }
+    }
+
+    pub(crate) fn transfers(&self) -> impl Iterator<Item = (usize, usize)> + '_ { self.transfers.iter().copied() }
+
+    /// Returns `true` if an instruction started at `address`.
+    pub(crate) fn was_executed(&self, address: usize) -> bool {
+        self.executed.get(address).copied().unwrap_or(false)
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Records which addresses instructions are executed from, so analysis tools can tell code
+    /// from data. Coverage survives `reset` and is cleared by `load` and by this call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_coverage_enabled(true);
+    /// // v0 := 0x10, jump0 0x1f4 (lands on 0x204), loop: jump loop
+    /// emulator.load(&[0x60, 0x10, 0xb1, 0xf4, 0x12, 0x04]).unwrap();
+    /// emulator.tick_frame();
+    /// assert!(emulator.was_executed(0x204));
+    /// assert!(!emulator.was_executed(0x201));
+    /// ```
+    pub fn set_coverage_enabled(&mut self, enabled: bool) {
+        self.coverage.enabled = enabled;
+        self.coverage.clear();
+    }
+
+    pub fn is_coverage_enabled(&self) -> bool { self.coverage.enabled }
+
+    /// Forgets the recorded coverage.
+    pub fn clear_coverage(&mut self) { self.coverage.clear(); }
+
+    /// Returns `true` if coverage is enabled and an instruction was executed from `address`.
+    pub fn was_executed(&self, address: u16) -> bool { self.coverage.was_executed(usize::from(address)) }
+}
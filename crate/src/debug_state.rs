@@ -0,0 +1,75 @@
+use serde::Serialize;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Bits of `DebugState::changed` besides bit N (0 to 15) that stands for VN.
+pub const CHANGED_I: u32 = 1 << 16;
+pub const CHANGED_PC: u32 = 1 << 17;
+pub const CHANGED_DELAY_TIMER: u32 = 1 << 18;
+pub const CHANGED_SOUND_TIMER: u32 = 1 << 19;
+
+/// Registers shown by a debugger together with the ones that changed since the previous snapshot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DebugState {
+    pub pc: u16,
+    pub i: u16,
+    pub v: [u8; 16],
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    /// Depth of the call stack.
+    pub stack_depth: u8,
+    /// Registers that differ from the previous `debug_state` call, see `CHANGED_I` and friends.
+    /// Nothing is marked in the first snapshot after the emulator was created or reset.
+    pub changed: u32,
+}
+
+impl Emulator {
+    /// Takes a register snapshot for debugger UIs and marks what changed since the last one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, CHANGED_PC};
+    /// let mut emulator = Emulator::new();
+    /// // v3 := 1
+    /// emulator.load(&[0x63, 0x01]).unwrap();
+    /// assert_eq!(emulator.debug_state().changed, 0);
+    /// emulator.step();
+    /// assert_eq!(emulator.debug_state().changed, 1 << 3 | CHANGED_PC);
+    /// ```
+    pub fn debug_state(&mut self) -> DebugState {
+        let mut state = DebugState {
+            pc: self.pc as u16,
+            i: self.i as u16,
+            v: self.v,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            stack_depth: self.stack.len() as u8,
+            changed: 0,
+        };
+
+        if let Some(previous) = self.last_debug_state {
+            let v = (0..16).filter(|&x| state.v[x] != previous.v[x]).fold(0, |bits, x| bits | 1 << x);
+            let others = [
+                (CHANGED_I, state.i != previous.i),
+                (CHANGED_PC, state.pc != previous.pc),
+                (CHANGED_DELAY_TIMER, state.delay_timer != previous.delay_timer),
+                (CHANGED_SOUND_TIMER, state.sound_timer != previous.sound_timer),
+            ];
+            state.changed = others.iter().filter(|&&(_, changed)| changed).fold(v, |bits, &(bit, _)| bits | bit);
+        }
+
+        self.last_debug_state = Some(state);
+        state
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `debug_state` as a plain JS object.
+    #[wasm_bindgen(js_name = debug_state)]
+    pub fn debug_state_js(&mut self) -> JsValue { crate::utils::to_js(&self.debug_state()) }
+}
@@ -0,0 +1,219 @@
+use std::collections::BTreeSet;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Instruction, RomTooLarge};
+
+/// Upper bound of instructions `step_over` and `step_out` run before giving up.
+pub const MAX_STEP_CYCLES: u32 = 1_000_000;
+
+/// Why a debugger command stopped executing.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The command finished.
+    Stepped,
+    /// PC reached a breakpoint.
+    Breakpoint,
+    /// The program faulted.
+    Fault,
+    /// The instruction limit was reached.
+    CycleLimit,
+}
+
+/// Breakpoints set by the user.
+#[derive(Clone, Debug, Default)]
+pub struct Breakpoints {
+    addresses: BTreeSet<u16>,
+}
+
+impl Breakpoints {
+    pub fn contains(&self, address: usize) -> bool {
+        address <= 0xffff && self.addresses.contains(&(address as u16))
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Stops debugger commands before the instruction at the address executes.
+    pub fn add_breakpoint(&mut self, address: u16) { self.breakpoints.addresses.insert(address); }
+
+    pub fn remove_breakpoint(&mut self, address: u16) { self.breakpoints.addresses.remove(&address); }
+
+    pub fn clear_breakpoints(&mut self) { self.breakpoints.addresses.clear(); }
+
+    /// Returns breakpoint addresses in ascending order.
+    pub fn breakpoints(&self) -> Vec<u16> { self.breakpoints.addresses.iter().copied().collect() }
+
+    /// Executes a single instruction. Frames start and end as if the program ran with `tick_frame`,
+    /// so the keypad and the timers keep working while stepping.
+    pub fn step(&mut self) -> StopReason { self.run_until(1, |_| true) }
+
+    /// Executes the next instruction, running a whole subroutine if it is a call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, StopReason};
+    /// let mut emulator = Emulator::new();
+    /// // call 0x206, v1 := 1, halt, sub: v0 := 1, return
+    /// emulator.load(&[0x22, 0x06, 0x61, 0x01, 0x12, 0x04, 0x60, 0x01, 0x00, 0xee]).unwrap();
+    /// assert_eq!(emulator.step_over(), StopReason::Stepped);
+    /// assert_eq!(emulator.pc(), 0x202);
+    /// ```
+    pub fn step_over(&mut self) -> StopReason {
+        match Instruction::decode(self.get_opcode(), self.variant) {
+            Instruction::Call(_) => {
+                let (address, depth) = (self.pc + 2, self.stack.len());
+                self.run_until(MAX_STEP_CYCLES, |emulator| emulator.pc == address && emulator.stack.len() == depth)
+            }
+            _ => self.step(),
+        }
+    }
+
+    /// Runs until the current subroutine returns. Outside of a subroutine it is the same as `step`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, StopReason};
+    /// let mut emulator = Emulator::new();
+    /// // call 0x206, v1 := 1, halt, sub: v0 := 1, return
+    /// emulator.load(&[0x22, 0x06, 0x61, 0x01, 0x12, 0x04, 0x60, 0x01, 0x00, 0xee]).unwrap();
+    /// emulator.step();
+    /// assert_eq!(emulator.step_out(), StopReason::Stepped);
+    /// assert_eq!(emulator.pc(), 0x202);
+    /// ```
+    pub fn step_out(&mut self) -> StopReason {
+        let depth = self.stack.len();
+        if depth == 0 {
+            return self.step();
+        }
+
+        self.run_until(MAX_STEP_CYCLES, |emulator| emulator.stack.len() < depth)
+    }
+
+    /// Runs until PC reaches the address, as if a breakpoint was set there for this command only.
+    /// Other breakpoints still stop it. At least one instruction is executed, so running to the
+    /// current address waits until the program comes back to it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, StopReason};
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 1, v1 := 2, v2 := 3, halt
+    /// emulator.load(&[0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0x12, 0x06]).unwrap();
+    /// assert_eq!(emulator.run_to(0x204, 100), StopReason::Stepped);
+    /// assert_eq!((emulator.pc(), emulator.v(1)), (0x204, 2));
+    /// assert_eq!(emulator.run_to(0x300, 100), StopReason::CycleLimit);
+    /// assert!(emulator.breakpoints().is_empty());
+    /// ```
+    pub fn run_to(&mut self, address: u16, max_cycles: u32) -> StopReason {
+        self.run_until(max_cycles, |emulator| emulator.pc == address as usize)
+    }
+
+    /// Address of the next instruction.
+    pub fn pc(&self) -> u16 { self.pc as u16 }
+
+    /// Value of the I register.
+    pub fn i(&self) -> u16 { self.i as u16 }
+
+    pub fn delay_timer(&self) -> u8 { self.delay_timer }
+
+    pub fn sound_timer(&self) -> u8 { self.sound_timer }
+
+    /// Value of register VX, 0 for indices above 0xF.
+    pub fn v(&self, x: u8) -> u8 { self.v.get(x as usize).copied().unwrap_or(0) }
+
+    /// Replaces the program and restarts it for the next edit-assemble-test iteration.
+    ///
+    /// The machine is reset like a fresh boot, the boot state is dropped as it holds the old
+    /// program. Everything configuring the session survives: breakpoints, variant and quirks,
+    /// strict mode, the machine call policy, cycles per frame, input mode and display settings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.load(&[0x12, 0x00]).unwrap();
+    /// emulator.add_breakpoint(0x202);
+    /// emulator.reload(&[0x60, 0x01, 0x61, 0x02]).unwrap();
+    /// emulator.step();
+    /// assert_eq!(emulator.breakpoints(), [0x202]);
+    /// assert_eq!(emulator.pc(), 0x202);
+    /// ```
+    pub fn reload(&mut self, program: &[u8]) -> Result<(), RomTooLarge> {
+        self.boot_state = None;
+        self.reset();
+        self.load(program)
+    }
+}
+
+/// Direct register writes for test fixtures and debuggers, enabled by the `debug` feature. They
+/// bypass the program, so the machine can end up in states no ROM could reach.
+#[cfg(feature = "debug")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Moves PC to the address. Returns `false` and leaves PC alone if the address is not in memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // At 0x204: v0 := v0 + v1
+    /// emulator.load(&[0x00, 0x00, 0x00, 0x00, 0x80, 0x14]).unwrap();
+    /// assert!(emulator.set_pc(0x204));
+    /// emulator.set_v(0x0, 2);
+    /// emulator.set_v(0x1, 3);
+    /// emulator.step();
+    /// assert_eq!(emulator.v(0x0), 5);
+    /// ```
+    pub fn set_pc(&mut self, address: u16) -> bool {
+        if address as usize + 1 >= self.memory.len() {
+            return false;
+        }
+
+        self.pc = address as usize;
+        true
+    }
+
+    /// Sets I, wrapping it into memory like ANNN does.
+    pub fn set_i(&mut self, value: u16) { self.i = self.address(value as usize); }
+
+    /// Sets register VX, indices above 0xF are ignored.
+    pub fn set_v(&mut self, x: u8, value: u8) {
+        if let Some(register) = self.v.get_mut(x as usize) {
+            *register = value;
+        }
+    }
+}
+
+impl Emulator {
+    /// Executes instructions until `done` returns `true` after one of them, a breakpoint is
+    /// reached or `max_cycles` instructions ran. The breakpoint at the starting PC is ignored, so
+    /// commands can continue from a breakpoint.
+    pub(crate) fn run_until<F: FnMut(&Emulator) -> bool>(&mut self, max_cycles: u32, mut done: F) -> StopReason {
+        for executed in 0..max_cycles {
+            if self.fault.is_some() {
+                return StopReason::Fault;
+            }
+            if executed > 0 && self.breakpoints.contains(self.pc) {
+                return StopReason::Breakpoint;
+            }
+
+            self.cycle();
+
+            if self.fault.is_some() {
+                return StopReason::Fault;
+            }
+            if done(self) {
+                return StopReason::Stepped;
+            }
+        }
+
+        StopReason::CycleLimit
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH};
+
+/// Raw framebuffer layout used by the emulator. Every cell holds a bitmask of the planes that
+/// are lit at that position (bit 0 is the first plane).
+pub type Gfx = [[u8; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT];
+
+/// Single display cell together with the planes that are lit at its position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pixel {
+    pub x: usize,
+    pub y: usize,
+    // Bit N is set when plane N is lit.
+    pub planes: u8,
+}
+
+impl Pixel {
+    /// Returns `true` if any plane is lit at this position.
+    pub fn is_lit(&self) -> bool { self.planes != 0 }
+
+    /// Returns `true` if the given plane (0-based) is lit at this position.
+    pub fn in_plane(&self, plane: u8) -> bool { plane < 8 && self.planes & (1 << plane) != 0 }
+}
+
+/// Iterator over every display cell in row-major order.
+pub struct PixelIter<'a> {
+    gfx: &'a Gfx,
+    index: usize,
+}
+
+impl<'a> PixelIter<'a> {
+    pub(crate) fn new(gfx: &'a Gfx) -> PixelIter<'a> { PixelIter { gfx, index: 0 } }
+}
+
+impl<'a> Iterator for PixelIter<'a> {
+    type Item = Pixel;
+
+    fn next(&mut self) -> Option<Pixel> {
+        if self.index >= CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT {
+            return None;
+        }
+
+        let x = self.index % CHIP8_DISPLAY_WIDTH;
+        let y = self.index / CHIP8_DISPLAY_WIDTH;
+        self.index += 1;
+
+        Some(Pixel { x, y, planes: self.gfx[y][x] })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for PixelIter<'a> {}
@@ -0,0 +1,129 @@
+use std::collections::{HashSet, VecDeque};
+use std::mem::{self, Discriminant};
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Variant};
+
+/// How many events are kept until the host takes them, older ones are dropped first.
+pub const MAX_EVENTS: usize = 256;
+
+/// Nesting depth of subroutine calls the COSMAC VIP interpreter has room for.
+pub const VIP_STACK_DEPTH: usize = 12;
+
+/// Something the program did that the host may want to tell the user about.
+///
+/// Compatibility warnings (see `is_warning`) are raised once per instruction address and kind,
+/// for a badge telling the user the game may not work as intended while it keeps running.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventKind {
+    /// 0NNN called machine code of the original interpreter host, which cannot be emulated.
+    /// Raised with `MachineCallPolicy::Warn`.
+    MachineCall { address: u16 },
+    /// I was moved past the end of memory and wrapped around, `value` being the address before.
+    IOutOfRange { value: u32 },
+    /// The program overwrote the font at `address`.
+    FontWrite { address: u16 },
+    /// A call nested subroutines deeper than `VIP_STACK_DEPTH`.
+    DeepStack { depth: u8 },
+    /// A 2NNN called its own address, which nests calls until the stack overflows.
+    Recursion { address: u16 },
+    /// The beep started, because the sound timer was set or the emulator resumed.
+    SoundStart,
+    /// The beep stopped, because the sound timer ran out or the emulator paused.
+    SoundStop,
+    /// The program used an opcode only `variant` defines and the emulator switched to it, see
+    /// `Emulator::set_auto_variant`.
+    VariantUpgrade { variant: Variant },
+}
+
+/// Event together with the frame and the address of the instruction that raised it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Event {
+    pub frame: u64,
+    pub pc: u16,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+/// Bounded queue of events waiting for the host.
+#[derive(Clone, Debug, Default)]
+pub struct Events {
+    queue: VecDeque<Event>,
+    // Address and kind of the warnings already raised.
+    warned: HashSet<(u16, Discriminant<EventKind>)>,
+}
+
+impl EventKind {
+    /// Returns `true` for compatibility warnings about suspicious program behavior.
+    pub fn is_warning(&self) -> bool {
+        !matches!(self, EventKind::SoundStart | EventKind::SoundStop | EventKind::VariantUpgrade { .. })
+    }
+}
+
+impl Events {
+    pub fn push(&mut self, event: Event) {
+        if self.queue.len() == MAX_EVENTS {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(event);
+    }
+
+    pub fn len(&self) -> usize { self.queue.len() }
+
+    pub fn is_empty(&self) -> bool { self.queue.is_empty() }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.warned.clear();
+    }
+}
+
+impl Emulator {
+    /// Queues an event raised by the instruction that is executing.
+    pub(crate) fn emit(&mut self, kind: EventKind) {
+        self.events.push(Event { frame: self.frame, pc: self.pc as u16, kind });
+    }
+
+    /// Queues a compatibility warning unless the instruction that is executing raised one of the
+    /// same kind before.
+    pub(crate) fn warn(&mut self, kind: EventKind) {
+        if self.events.warned.insert((self.pc as u16, mem::discriminant(&kind))) {
+            self.emit(kind);
+        }
+    }
+
+    /// Removes and returns the queued events, oldest first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, EventKind};
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 0xff, i := 0xf20, loop: i += v0, save v0, jump loop
+    /// emulator.load(&[0x60, 0xff, 0xaf, 0x20, 0xf0, 0x1e, 0xf0, 0x55, 0x12, 0x04]).unwrap();
+    /// emulator.tick_frame();
+    /// let events = emulator.take_events();
+    /// assert_eq!(events[0].kind, EventKind::IOutOfRange { value: 0x101f });
+    /// assert_eq!(events[1].kind, EventKind::FontWrite { address: 0x01f });
+    /// assert_eq!(events.len(), 2);
+    /// assert!(events.iter().all(|event| event.kind.is_warning()));
+    /// ```
+    pub fn take_events(&mut self) -> Vec<Event> { self.events.queue.drain(..).collect() }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `take_events` as an array of plain JS objects with a `type` field.
+    #[wasm_bindgen(js_name = take_events)]
+    pub fn take_events_js(&mut self) -> JsValue { crate::utils::to_js(&self.take_events()) }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Number of events waiting to be taken with `take_events`.
+    pub fn pending_events(&self) -> usize { self.events.len() }
+}
@@ -0,0 +1,154 @@
+use serde::{Serialize, Serializer};
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// How many executed instructions are kept for crash dumps.
+pub const HISTORY_LEN: usize = 32;
+
+/// Reason the emulator stopped executing the program.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum FaultKind {
+    /// 00EE was executed with an empty stack.
+    StackUnderflow,
+    /// 2NNN was executed with `STACK_DEPTH` calls already on the stack.
+    StackOverflow,
+    /// PC points past the end of memory.
+    PcOutOfBounds,
+    /// 0NNN was executed while the machine call policy is `Fault`.
+    MachineCall,
+    /// Strict mode: an instruction would read or write past the end of memory.
+    MemoryOutOfBounds,
+    /// Strict mode: I would be moved past the end of memory.
+    IOutOfBounds,
+    /// Strict mode: the opcode is not defined by the variant.
+    UnknownOpcode,
+}
+
+/// Instruction that was executed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Executed {
+    pub pc: u16,
+    pub opcode: u16,
+}
+
+/// Fixed-size ring of the last `HISTORY_LEN` executed instructions. Recording never allocates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct History {
+    entries: [Executed; HISTORY_LEN],
+    // Index the next entry is written to and how many entries are filled.
+    next: usize,
+    len: usize,
+}
+
+impl Default for History {
+    fn default() -> History { History { entries: [Executed::default(); HISTORY_LEN], next: 0, len: 0 } }
+}
+
+impl History {
+    pub fn push(&mut self, pc: u16, opcode: u16) {
+        self.entries[self.next] = Executed { pc, opcode };
+        self.next = (self.next + 1) % HISTORY_LEN;
+        self.len = (self.len + 1).min(HISTORY_LEN);
+    }
+
+    /// Iterates from the oldest to the most recent instruction.
+    pub fn iter(&self) -> impl Iterator<Item = Executed> + '_ {
+        let start = (self.next + HISTORY_LEN - self.len) % HISTORY_LEN;
+        (0..self.len).map(move |offset| self.entries[(start + offset) % HISTORY_LEN])
+    }
+
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    pub fn clear(&mut self) { *self = History::default(); }
+}
+
+impl Serialize for History {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Description of the failure that stopped the emulator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Fault {
+    pub kind: FaultKind,
+    // Address and opcode of the failed instruction.
+    pub pc: u16,
+    pub opcode: u16,
+    // Instructions executed before the fault, the failed one included.
+    pub history: History,
+}
+
+/// Machine state at the moment of a crash, meant to be pasted into bug reports.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CrashDump {
+    pub fault: Option<Fault>,
+    pub rom_hash: u32,
+    pub frame: u64,
+    pub pc: u16,
+    pub i: u16,
+    pub v: [u8; 16],
+    pub stack: Vec<u16>,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub history: History,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `true` if a fault stopped the program. Only `reset` clears it.
+    pub fn has_fault(&self) -> bool { self.fault.is_some() }
+
+    /// Returns the kind of fault that stopped the program.
+    pub fn fault_kind(&self) -> Option<FaultKind> { self.fault.map(|fault| fault.kind) }
+}
+
+impl Emulator {
+    /// Returns the fault that stopped the program.
+    pub fn fault(&self) -> Option<&Fault> { self.fault.as_ref() }
+
+    /// Instructions executed most recently, oldest first.
+    pub fn history(&self) -> &History { &self.history }
+
+    /// Collects the machine state for a crash report.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, FaultKind};
+    /// let mut emulator = Emulator::new();
+    /// // Returning from a subroutine that was never called.
+    /// emulator.load(&[0x60, 0x01, 0x00, 0xee]).unwrap();
+    /// emulator.tick_frame();
+    /// let dump = emulator.crash_dump();
+    /// let fault = dump.fault.unwrap();
+    /// assert_eq!(fault.kind, FaultKind::StackUnderflow);
+    /// assert_eq!(fault.pc, 0x202);
+    /// assert_eq!(fault.history.iter().map(|executed| executed.opcode).collect::<Vec<_>>(), [0x6001, 0x00ee]);
+    /// ```
+    pub fn crash_dump(&self) -> CrashDump {
+        CrashDump {
+            fault: self.fault,
+            rom_hash: self.rom_hash,
+            frame: self.frame,
+            pc: self.pc as u16,
+            i: self.i as u16,
+            v: self.v,
+            stack: self.stack.iter().map(|&address| address as u16).collect(),
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            history: self.history,
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `crash_dump` as a plain JS object.
+    pub fn dump_crash(&self) -> JsValue { crate::utils::to_js(&self.crash_dump()) }
+}
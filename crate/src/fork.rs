@@ -0,0 +1,100 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns a deep copy of the emulator for what-if exploration, e.g. to see what happens when
+    /// a key is pressed at this point without disturbing the session.
+    ///
+    /// The copy has everything the emulator has, including its configuration, debugger state and
+    /// random number generator, so both run the same from here on given the same input. Its IO is
+    /// detached: it is headless (see `Emulator::headless`), measures time with the host clock, has
+    /// no frame listeners, no save storage and its own stop handle, and it continues neither a
+    /// movie recording nor a trace.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // loop: v0 := key, v1 := v0, jump loop
+    /// emulator.load(&[0xf0, 0x0a, 0x81, 0x00, 0x12, 0x00]).unwrap();
+    /// emulator.tick_frame();
+    ///
+    /// let mut what_if = emulator.fork();
+    /// what_if.set_key(0x5, true);
+    /// what_if.tick_frame();
+    /// what_if.set_key(0x5, false);
+    /// what_if.tick_frame();
+    /// what_if.tick_frame();
+    /// emulator.tick_frame();
+    /// emulator.tick_frame();
+    /// assert_eq!((what_if.v(1), emulator.v(1)), (5, 0));
+    /// ```
+    pub fn fork(&self) -> Emulator {
+        Emulator {
+            opcode: self.opcode,
+            i: self.i,
+            memory: self.memory.clone(),
+            memory_init: self.memory_init,
+            v: self.v,
+            stack: self.stack.clone(),
+            pc: self.pc,
+            gfx: self.gfx,
+            planes: self.planes,
+            draw_flag: self.draw_flag,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            keypad: self.keypad.clone(),
+            variant: self.variant,
+            quirks: self.quirks,
+            frame: self.frame,
+            cycles_per_frame: self.cycles_per_frame,
+            timer_hz: self.timer_hz,
+            frame_cycle: self.frame_cycle,
+            rom_hash: self.rom_hash,
+            slots: self.slots.clone(),
+            input: self.input.clone(),
+            drawn_sprites: self.drawn_sprites.clone(),
+            synth: self.synth.clone(),
+            renderer: self.renderer.clone(),
+            overlay: self.overlay.clone(),
+            history: self.history,
+            fault: self.fault,
+            boot_state: self.boot_state.clone(),
+            breakpoints: self.breakpoints.clone(),
+            events: self.events.clone(),
+            machine_calls: self.machine_calls.clone(),
+            machine_call_policy: self.machine_call_policy,
+            players: self.players.clone(),
+            strict: self.strict,
+            auto_variant: self.auto_variant,
+            pause_on_recursion: self.pause_on_recursion,
+            macros: self.macros.clone(),
+            #[cfg(feature = "debug")]
+            mutation: self.mutation,
+            heatmap: self.heatmap.clone(),
+            coverage: self.coverage.clone(),
+            rng: self.rng.clone(),
+            paused: self.paused,
+            muted: self.muted,
+            key_polling: self.key_polling,
+            color_board: self.color_board.clone(),
+            banks: self.banks.clone(),
+            current_bank: self.current_bank,
+            skip_idle: self.skip_idle,
+            low_power: self.low_power,
+            clock: self.clock.clone(),
+            last_debug_state: self.last_debug_state,
+            auto_speed: self.auto_speed.clone(),
+            total_cycles: self.total_cycles,
+            costs: self.costs.clone(),
+            latency: self.latency.clone(),
+            journal: self.journal.clone(),
+            last_executed: self.last_executed,
+            midi_base_note: self.midi_base_note,
+            ..Emulator::headless()
+        }
+    }
+}
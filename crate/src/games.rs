@@ -0,0 +1,76 @@
+//! Programs bundled with the emulator, enabled by the `games` feature, so demos and tutorials
+//! work without users finding ROM files first. The sources are in the `roms` directory.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Description of a bundled program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct BuiltinRom {
+    /// Name `load_builtin_rom` takes.
+    pub name: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub size: usize,
+}
+
+// Name, title, description and contents of every bundled program.
+pub(crate) const ROMS: [(&str, &str, &str, &[u8]); 2] = [
+    (
+        "pong",
+        "Pong",
+        "Pong for two players. The left paddle moves with 1 and 4, the right one with C and D.",
+        include_bytes!("../roms/pong.ch8"),
+    ),
+    (
+        "keypad-test",
+        "Keypad test",
+        "Shows every held key where it sits on the keypad, to check the key mapping.",
+        include_bytes!("../roms/keypad-test.ch8"),
+    ),
+];
+
+/// Lists the bundled programs.
+///
+/// # Example
+///
+/// ```
+/// use wasm_chip8::builtin_roms;
+/// assert!(builtin_roms().iter().any(|rom| rom.name == "pong"));
+/// ```
+pub fn builtin_roms() -> Vec<BuiltinRom> {
+    ROMS.iter()
+        .map(|&(name, title, description, rom)| BuiltinRom { name, title, description, size: rom.len() })
+        .collect()
+}
+
+/// Returns `builtin_roms` as an array of plain JS objects.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = builtin_roms)]
+pub fn builtin_roms_js() -> JsValue { crate::utils::to_js(&builtin_roms()) }
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Loads the bundled program with the given name (see `builtin_roms`). Returns `false` if no
+    /// program has the name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// assert!(emulator.load_builtin_rom("pong"));
+    /// emulator.run_cycles(100);
+    /// assert!(emulator.pixels().any(|pixel| pixel.is_lit()));
+    /// assert!(!emulator.load_builtin_rom("tetris"));
+    /// ```
+    pub fn load_builtin_rom(&mut self, name: &str) -> bool {
+        match ROMS.iter().find(|&&(rom_name, ..)| rom_name == name) {
+            // Every bundled program fits into the memory of every variant.
+            Some(&(.., rom)) => self.load(rom).is_ok(),
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Width and height of the heatmap. Every cell covers `memory_size / 4096` addresses.
+pub const HEATMAP_SIZE: usize = 64;
+
+const CELLS: usize = HEATMAP_SIZE * HEATMAP_SIZE;
+// Heat added by one access, heat is kept in 0..=255.
+const HEAT_STEP: u8 = 64;
+// Every frame the heat of a cell is multiplied by DECAY / 16.
+const DECAY: u16 = 14;
+
+/// Kind of memory access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+/// Decaying read, write and execute counters of every memory cell.
+#[derive(Clone, Debug, Default)]
+pub struct Heatmap {
+    enabled: bool,
+    // CELLS counters per access kind, allocated once the heatmap is enabled.
+    read: Vec<u8>,
+    write: Vec<u8>,
+    execute: Vec<u8>,
+    rgba: Vec<u8>,
+}
+
+impl Heatmap {
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.clear();
+    }
+
+    pub fn is_enabled(&self) -> bool { self.enabled }
+
+    pub fn clear(&mut self) {
+        let cells = if self.enabled { CELLS } else { 0 };
+        for counters in [&mut self.read, &mut self.write, &mut self.execute] {
+            counters.clear();
+            counters.resize(cells, 0);
+        }
+    }
+
+    /// Records an access of `address` in a memory of `memory_size` bytes.
+    pub fn touch(&mut self, access: Access, address: usize, memory_size: usize) {
+        if !self.enabled || address >= memory_size {
+            return;
+        }
+
+        let cell = address * CELLS / memory_size;
+        let counters = match access {
+            Access::Read => &mut self.read,
+            Access::Write => &mut self.write,
+            Access::Execute => &mut self.execute,
+        };
+        counters[cell] = counters[cell].saturating_add(HEAT_STEP);
+    }
+
+    /// Cools every cell down, called once per frame.
+    pub fn decay(&mut self) {
+        for counters in [&mut self.read, &mut self.write, &mut self.execute] {
+            counters.iter_mut().for_each(|heat| *heat = (u16::from(*heat) * DECAY / 16) as u8);
+        }
+    }
+
+    /// Renders writes to red, reads to green and execution to blue.
+    pub fn render(&mut self) -> &[u8] {
+        self.rgba.resize(CELLS * 4, 0);
+        for cell in 0..CELLS {
+            let heat = |counters: &[u8]| counters.get(cell).copied().unwrap_or(0);
+            self.rgba[cell * 4..cell * 4 + 4].copy_from_slice(&[
+                heat(&self.write),
+                heat(&self.read),
+                heat(&self.execute),
+                0xff,
+            ]);
+        }
+        &self.rgba
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Turns the memory heatmap on or off. It is off by default as it slows every memory access.
+    pub fn set_heatmap_enabled(&mut self, enabled: bool) { self.heatmap.set_enabled(enabled); }
+
+    pub fn is_heatmap_enabled(&self) -> bool { self.heatmap.is_enabled() }
+
+    /// Renders the heatmap and returns pointer to its `HEATMAP_SIZE * HEATMAP_SIZE * 4` RGBA bytes.
+    ///
+    /// Cells are addresses in row-major order, red shows writes, green reads and blue executed
+    /// instructions. Heat fades over a few frames, so the image shows what the program is touching
+    /// right now.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, HEATMAP_SIZE};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_heatmap_enabled(true);
+    /// // Loop forever at 0x200.
+    /// emulator.load(&[0x12, 0x00]).unwrap();
+    /// emulator.tick_frame();
+    /// emulator.memory_heatmap();
+    /// let cell = 0x200 * 4;
+    /// assert!(emulator.heatmap()[cell + 2] > 0);
+    /// assert_eq!(emulator.heatmap().len(), HEATMAP_SIZE * HEATMAP_SIZE * 4);
+    /// ```
+    pub fn memory_heatmap(&mut self) -> *const u8 { self.heatmap.render().as_ptr() }
+}
+
+impl Emulator {
+    /// Image produced by the last `memory_heatmap` call.
+    pub fn heatmap(&self) -> &[u8] { &self.heatmap.rgba }
+
+    pub(crate) fn touch(&mut self, access: Access, address: usize) {
+        self.heatmap.touch(access, address, self.memory.len());
+    }
+}
@@ -0,0 +1,96 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Instruction};
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Lets `tick_frame` end a frame early while the program busy-waits on the delay timer in the
+    /// common `vx := delay` / `if vx != 0 then` / jump back loop. The timer still counts down once
+    /// per frame, so the program sees the same timing while the host spends almost no time on it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_skip_idle(true);
+    /// // v0 := 3, delay := v0, loop: v0 := delay, if v0 != 0 then jump loop, v1 := 1
+    /// emulator.load(&[0x60, 0x03, 0xf0, 0x15, 0xf0, 0x07, 0x30, 0x00, 0x12, 0x04, 0x61, 0x01]).unwrap();
+    /// for _ in 0..4 {
+    ///     emulator.tick_frame();
+    /// }
+    /// assert_eq!(emulator.v(1), 1);
+    /// ```
+    pub fn set_skip_idle(&mut self, skip: bool) { self.skip_idle = skip; }
+
+    pub fn skips_idle(&self) -> bool { self.skip_idle }
+
+    /// Lets `advance_ms` and `advance` return immediately without running frames while the
+    /// program is idle (see `is_idle`). The idle time is forgotten like time spent paused, so
+    /// frame counters stop, which no program can observe. Mobile frontends check `is_idle` after
+    /// advancing (or `LoopStep::idle` of `run_loop`) to lower their animation frame rate until the
+    /// next key change.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_low_power(true);
+    /// // v0 := key, v1 := 1, halt: jump halt
+    /// emulator.load(&[0xf0, 0x0a, 0x61, 0x01, 0x12, 0x04]).unwrap();
+    /// assert!(emulator.is_idle());
+    /// assert_eq!(emulator.advance_ms(1000.0), 0);
+    /// emulator.set_key(0x5, true);
+    /// emulator.advance_ms(1000.0 / 60.0);
+    /// emulator.set_key(0x5, false);
+    /// emulator.advance_ms(1000.0 / 30.0);
+    /// assert_eq!((emulator.v(0), emulator.v(1)), (0x5, 1));
+    /// assert!(emulator.is_idle());
+    /// ```
+    pub fn set_low_power(&mut self, enabled: bool) { self.low_power = enabled; }
+
+    pub fn is_low_power(&self) -> bool { self.low_power }
+
+    /// Returns `true` if running frames cannot change anything until a key changes: the program
+    /// halts in a jump to itself or waits in FX0A, both timers are stopped and no key change is
+    /// waiting to reach the program.
+    pub fn is_idle(&self) -> bool {
+        if self.delay_timer != 0 || self.sound_timer != 0 || self.is_playing_macro() {
+            return false;
+        }
+        if !self.keypad.is_settled(self.live_keys()) {
+            return false;
+        }
+
+        match self.decode_at(self.pc) {
+            Instruction::Jump(nnn) => usize::from(nnn) == self.pc,
+            Instruction::WaitKey { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+impl Emulator {
+    /// Returns `true` if PC is at the start of a loop that only waits for the delay timer to
+    /// reach 0 and the timer is still running.
+    pub(crate) fn is_waiting_on_delay(&self) -> bool {
+        if self.delay_timer == 0 || self.fault.is_some() {
+            return false;
+        }
+
+        let decode = |address: usize| self.decode_at(address);
+        match (decode(self.pc), decode(self.pc + 2), decode(self.pc + 4)) {
+            (Instruction::GetDelay { x }, Instruction::SkipEqImm { x: tested, nn: 0 }, Instruction::Jump(nnn)) => {
+                x == tested && nnn as usize == self.pc
+            }
+            _ => false,
+        }
+    }
+
+    /// Instruction at an address, reading past the end of memory as zeros.
+    pub(crate) fn decode_at(&self, address: usize) -> Instruction {
+        let byte = |at: usize| u16::from(self.memory.get(at).copied().unwrap_or(0));
+        Instruction::decode(byte(address) << 8 | byte(address + 1), self.variant)
+    }
+}
@@ -0,0 +1,121 @@
+//! Input recordings written as JSON, so recordings made with other tools can be played back.
+//!
+//! A script lists key changes and the emulated frame they happen in:
+//!
+//! ```json
+//! {
+//!     "frames": 120,
+//!     "events": [
+//!         { "frame": 0, "key": 5, "pressed": true },
+//!         { "frame": 30, "key": "a", "pressed": true },
+//!         { "frame": 60, "key": 5, "pressed": false }
+//!     ]
+//! }
+//! ```
+//!
+//! Keys are keypad keys, a number from 0 to 15 or a hex digit. A key stays pressed until an event
+//! releases it. `frames` is optional and makes the recording last longer than its last event.
+
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, MAX_MACRO_FRAMES};
+
+/// Reason an input script could not be loaded.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputScriptError {
+    /// The script is not valid JSON or a field has the wrong type.
+    InvalidJson,
+    /// A key is not a keypad key.
+    InvalidKey,
+    /// The recording is longer than `MAX_MACRO_FRAMES`.
+    TooLong,
+}
+
+#[derive(Debug, Deserialize)]
+struct Script {
+    #[serde(default)]
+    frames: usize,
+    events: Vec<Event>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    frame: usize,
+    key: Key,
+    pressed: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Key {
+    Index(u8),
+    Digit(String),
+}
+
+impl Key {
+    fn index(&self) -> Result<u8, InputScriptError> {
+        let index = match self {
+            Key::Index(index) => Some(*index),
+            Key::Digit(digit) if digit.len() == 1 => u8::from_str_radix(digit, 16).ok(),
+            Key::Digit(_) => None,
+        };
+        index.filter(|&index| index < 16).ok_or(InputScriptError::InvalidKey)
+    }
+}
+
+// Keypad bitmask of every frame of the script.
+fn masks(script: &str) -> Result<Vec<u16>, InputScriptError> {
+    let mut script: Script = serde_json::from_str(script).map_err(|_| InputScriptError::InvalidJson)?;
+    script.events.sort_by_key(|event| event.frame);
+    let last = script.events.last().map_or(Some(0), |event| event.frame.checked_add(1));
+    let frames = last.map(|last| script.frames.max(last)).filter(|&frames| frames <= MAX_MACRO_FRAMES);
+    let frames = frames.ok_or(InputScriptError::TooLong)?;
+
+    let mut masks = Vec::with_capacity(frames);
+    let mut mask = 0u16;
+    let mut events = script.events.iter().peekable();
+    for frame in 0..frames {
+        while let Some(event) = events.next_if(|event| event.frame == frame) {
+            let bit = 1 << event.key.index()?;
+            mask = if event.pressed { mask | bit } else { mask & !bit };
+        }
+        masks.push(mask);
+    }
+    Ok(masks)
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Stores an input script (see the module documentation) as the macro with the given id, to
+    /// be played back with `play_macro`. Returns how many frames it spans.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, InputScriptError};
+    /// let mut emulator = Emulator::new();
+    /// let script = r#"{ "events": [{ "frame": 1, "key": "5", "pressed": true }] }"#;
+    /// assert_eq!(emulator.load_input_script(1, script), Ok(2));
+    /// // v0 := 5, wait until key 5 is held, v1 := 1
+    /// emulator.load(&[0x60, 0x05, 0xe0, 0x9e, 0x12, 0x02, 0x61, 0x01, 0x12, 0x08]).unwrap();
+    /// emulator.play_macro(1);
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.v(1), 0);
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.v(1), 1);
+    ///
+    /// let script = r#"{ "events": [{ "frame": 0, "key": 16, "pressed": true }] }"#;
+    /// assert_eq!(emulator.load_input_script(2, script), Err(InputScriptError::InvalidKey));
+    ///
+    /// let script = format!(r#"{{ "events": [{{ "frame": {}, "key": 0, "pressed": true }}] }}"#, usize::MAX);
+    /// assert_eq!(emulator.load_input_script(3, &script), Err(InputScriptError::TooLong));
+    /// ```
+    pub fn load_input_script(&mut self, id: u32, script: &str) -> Result<usize, InputScriptError> {
+        let masks = masks(script)?;
+        let frames = masks.len();
+        self.macros.insert(id, masks);
+        Ok(frames)
+    }
+}
@@ -0,0 +1,470 @@
+use std::fmt;
+
+use serde::Serialize;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::quirks::{
+    COLLISION_COUNTS_ROWS, JUMP_USES_VX, LARGE_SPRITES, LOAD_STORE_INCREMENTS_I, LORES_SCROLL_HALVED, SHIFT_USES_VY,
+    VF_RESET, WRAP_SPRITES,
+};
+use crate::Variant;
+
+/// Decoded CHIP-8 instruction. `x` and `y` are register indices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    /// 0NNN: call a machine code routine of the host computer.
+    MachineCall(u16),
+    /// 00E0
+    ClearScreen,
+    /// 00EE
+    Return,
+    /// 00CN (CHIP-8, XO-CHIP): scroll the display down by N rows.
+    ScrollDown(u8),
+    /// 00FB (CHIP-8, XO-CHIP): scroll the display right by 4 pixels.
+    ScrollRight,
+    /// 00FC (CHIP-8, XO-CHIP): scroll the display left by 4 pixels.
+    ScrollLeft,
+    /// 1NNN
+    Jump(u16),
+    /// 2NNN
+    Call(u16),
+    /// 3XNN
+    SkipEqImm { x: u8, nn: u8 },
+    /// 4XNN
+    SkipNeImm { x: u8, nn: u8 },
+    /// 5XY0
+    SkipEq { x: u8, y: u8 },
+    /// 6XNN
+    SetImm { x: u8, nn: u8 },
+    /// 7XNN
+    AddImm { x: u8, nn: u8 },
+    /// 8XY0
+    Set { x: u8, y: u8 },
+    /// 8XY1
+    Or { x: u8, y: u8 },
+    /// 8XY2
+    And { x: u8, y: u8 },
+    /// 8XY3
+    Xor { x: u8, y: u8 },
+    /// 8XY4
+    Add { x: u8, y: u8 },
+    /// 8XY5
+    Sub { x: u8, y: u8 },
+    /// 8XY6
+    ShiftRight { x: u8, y: u8 },
+    /// 8XY7
+    SubReverse { x: u8, y: u8 },
+    /// 8XYE
+    ShiftLeft { x: u8, y: u8 },
+    /// 9XY0
+    SkipNe { x: u8, y: u8 },
+    /// ANNN
+    SetI(u16),
+    /// BNNN
+    JumpOffset(u16),
+    /// CXNN
+    Random { x: u8, nn: u8 },
+    /// DXYN
+    Draw { x: u8, y: u8, n: u8 },
+    /// EX9E
+    SkipKey { x: u8 },
+    /// EXA1
+    SkipNotKey { x: u8 },
+    /// FX07
+    GetDelay { x: u8 },
+    /// FX0A
+    WaitKey { x: u8 },
+    /// FX15
+    SetDelay { x: u8 },
+    /// FX18
+    SetSound { x: u8 },
+    /// FX1E
+    AddI { x: u8 },
+    /// FX29
+    Font { x: u8 },
+    /// FX33
+    Bcd { x: u8 },
+    /// FX55
+    Store { x: u8 },
+    /// FX65
+    Load { x: u8 },
+    /// F000 NNNN (XO-CHIP): the address is stored in the word that follows the opcode.
+    LongI,
+    /// FN01 (XO-CHIP): select the display planes in the bitmask N for drawing, clearing and scrolling.
+    SelectPlanes(u8),
+    /// 02A0 (CHIP-8X): cycle the background color.
+    CycleBackground,
+    /// 5XY1 (CHIP-8X): add VY to VX, each nibble wrapping at 8.
+    AddOctal { x: u8, y: u8 },
+    /// BXYN (CHIP-8X): color the zones selected by VX and VY with V[X + 1].
+    Color { x: u8, y: u8, n: u8 },
+    /// EXF2 (CHIP-8X): skip if key VX of the second keypad is pressed.
+    SkipKey2 { x: u8 },
+    /// EXF5 (CHIP-8X): skip if key VX of the second keypad is not pressed.
+    SkipNotKey2 { x: u8 },
+    /// Opcode the variant does not define. It is executed as a no-op.
+    Unknown(u16),
+}
+
+impl Instruction {
+    /// Decodes an opcode as the given variant understands it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Instruction, Variant};
+    /// assert_eq!(Instruction::decode(0xd125, Variant::Chip8), Instruction::Draw { x: 1, y: 2, n: 5 });
+    /// assert_eq!(Instruction::decode(0xf000, Variant::Chip8), Instruction::Unknown(0xf000));
+    /// assert_eq!(Instruction::decode(0xf000, Variant::XoChip), Instruction::LongI);
+    /// assert_eq!(Instruction::decode(0xf301, Variant::XoChip), Instruction::SelectPlanes(3));
+    /// assert_eq!(Instruction::decode(0x8126, Variant::Dream6800), Instruction::Unknown(0x8126));
+    /// assert_eq!(Instruction::decode(0xb120, Variant::Chip8X), Instruction::Color { x: 1, y: 2, n: 0 });
+    /// ```
+    pub fn decode(opcode: u16, variant: Variant) -> Instruction {
+        let nnn = opcode & 0x0fff;
+        let nn = (opcode & 0x00ff) as u8;
+        let n = (opcode & 0x000f) as u8;
+        let x = ((opcode & 0x0f00) >> 8) as u8;
+        let y = ((opcode & 0x00f0) >> 4) as u8;
+
+        match opcode >> 12 {
+            0x0 => match opcode {
+                0x00e0 => Instruction::ClearScreen,
+                0x00ee => Instruction::Return,
+                0x02a0 if variant == Variant::Chip8X => Instruction::CycleBackground,
+                0x00c0..=0x00cf if variant.has_scrolling() => Instruction::ScrollDown(n),
+                0x00fb if variant.has_scrolling() => Instruction::ScrollRight,
+                0x00fc if variant.has_scrolling() => Instruction::ScrollLeft,
+                _ => Instruction::MachineCall(nnn),
+            },
+            0x1 => Instruction::Jump(nnn),
+            0x2 => Instruction::Call(nnn),
+            0x3 => Instruction::SkipEqImm { x, nn },
+            0x4 => Instruction::SkipNeImm { x, nn },
+            0x5 if n == 0 => Instruction::SkipEq { x, y },
+            0x5 if n == 1 && variant == Variant::Chip8X => Instruction::AddOctal { x, y },
+            0x6 => Instruction::SetImm { x, nn },
+            0x7 => Instruction::AddImm { x, nn },
+            // CHIPOS only implements the first few arithmetic instructions.
+            0x8 if variant == Variant::Dream6800 && matches!(n, 0x3 | 0x6 | 0x7 | 0xe) => {
+                Instruction::Unknown(opcode)
+            }
+            0x8 => match n {
+                0x0 => Instruction::Set { x, y },
+                0x1 => Instruction::Or { x, y },
+                0x2 => Instruction::And { x, y },
+                0x3 => Instruction::Xor { x, y },
+                0x4 => Instruction::Add { x, y },
+                0x5 => Instruction::Sub { x, y },
+                0x6 => Instruction::ShiftRight { x, y },
+                0x7 => Instruction::SubReverse { x, y },
+                0xe => Instruction::ShiftLeft { x, y },
+                _ => Instruction::Unknown(opcode),
+            },
+            0x9 if n == 0 => Instruction::SkipNe { x, y },
+            0xa => Instruction::SetI(nnn),
+            0xb if variant == Variant::Chip8X => Instruction::Color { x, y, n },
+            0xb => Instruction::JumpOffset(nnn),
+            0xc => Instruction::Random { x, nn },
+            0xd => Instruction::Draw { x, y, n },
+            0xe => match nn {
+                0x9e => Instruction::SkipKey { x },
+                0xa1 => Instruction::SkipNotKey { x },
+                0xf2 if variant == Variant::Chip8X => Instruction::SkipKey2 { x },
+                0xf5 if variant == Variant::Chip8X => Instruction::SkipNotKey2 { x },
+                _ => Instruction::Unknown(opcode),
+            },
+            0xf => match nn {
+                0x00 if x == 0 && variant.has_long_addresses() => Instruction::LongI,
+                0x01 if variant.has_planes() => Instruction::SelectPlanes(x),
+                0x07 => Instruction::GetDelay { x },
+                0x0a => Instruction::WaitKey { x },
+                0x15 => Instruction::SetDelay { x },
+                0x18 => Instruction::SetSound { x },
+                0x1e => Instruction::AddI { x },
+                0x29 => Instruction::Font { x },
+                0x33 => Instruction::Bcd { x },
+                0x55 => Instruction::Store { x },
+                0x65 => Instruction::Load { x },
+                _ => Instruction::Unknown(opcode),
+            },
+            _ => Instruction::Unknown(opcode),
+        }
+    }
+
+    /// Short mnemonic of the instruction, e.g. `"DRW"`.
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::MachineCall(_) => "SYS",
+            Instruction::ClearScreen => "CLS",
+            Instruction::Return => "RET",
+            Instruction::ScrollDown(_) => "SCD",
+            Instruction::ScrollRight => "SCR",
+            Instruction::ScrollLeft => "SCL",
+            Instruction::Jump(_) | Instruction::JumpOffset(_) => "JP",
+            Instruction::Call(_) => "CALL",
+            Instruction::SkipEqImm { .. } | Instruction::SkipEq { .. } => "SE",
+            Instruction::SkipNeImm { .. } | Instruction::SkipNe { .. } => "SNE",
+            Instruction::AddImm { .. }
+            | Instruction::Add { .. }
+            | Instruction::AddI { .. }
+            | Instruction::AddOctal { .. } => "ADD",
+            Instruction::Or { .. } => "OR",
+            Instruction::And { .. } => "AND",
+            Instruction::Xor { .. } => "XOR",
+            Instruction::Sub { .. } => "SUB",
+            Instruction::ShiftRight { .. } => "SHR",
+            Instruction::SubReverse { .. } => "SUBN",
+            Instruction::ShiftLeft { .. } => "SHL",
+            Instruction::Random { .. } => "RND",
+            Instruction::Draw { .. } => "DRW",
+            Instruction::SkipKey { .. } => "SKP",
+            Instruction::SkipNotKey { .. } => "SKNP",
+            Instruction::CycleBackground => "BGC",
+            Instruction::Color { .. } => "COL",
+            Instruction::SkipKey2 { .. } => "SKP2",
+            Instruction::SkipNotKey2 { .. } => "SKNP2",
+            Instruction::SelectPlanes(_) => "PLANE",
+            Instruction::SetImm { .. }
+            | Instruction::Set { .. }
+            | Instruction::SetI(_)
+            | Instruction::GetDelay { .. }
+            | Instruction::WaitKey { .. }
+            | Instruction::SetDelay { .. }
+            | Instruction::SetSound { .. }
+            | Instruction::Font { .. }
+            | Instruction::Bcd { .. }
+            | Instruction::Store { .. }
+            | Instruction::Load { .. }
+            | Instruction::LongI => "LD",
+            Instruction::Unknown(_) => "DW",
+        }
+    }
+
+    /// Opcode pattern the instruction was decoded from, e.g. `"DXYN"`. Empty for unknown opcodes.
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            Instruction::MachineCall(_) => "0NNN",
+            Instruction::ClearScreen => "00E0",
+            Instruction::Return => "00EE",
+            Instruction::ScrollDown(_) => "00CN",
+            Instruction::ScrollRight => "00FB",
+            Instruction::ScrollLeft => "00FC",
+            Instruction::CycleBackground => "02A0",
+            Instruction::Jump(_) => "1NNN",
+            Instruction::Call(_) => "2NNN",
+            Instruction::SkipEqImm { .. } => "3XNN",
+            Instruction::SkipNeImm { .. } => "4XNN",
+            Instruction::SkipEq { .. } => "5XY0",
+            Instruction::AddOctal { .. } => "5XY1",
+            Instruction::SetImm { .. } => "6XNN",
+            Instruction::AddImm { .. } => "7XNN",
+            Instruction::Set { .. } => "8XY0",
+            Instruction::Or { .. } => "8XY1",
+            Instruction::And { .. } => "8XY2",
+            Instruction::Xor { .. } => "8XY3",
+            Instruction::Add { .. } => "8XY4",
+            Instruction::Sub { .. } => "8XY5",
+            Instruction::ShiftRight { .. } => "8XY6",
+            Instruction::SubReverse { .. } => "8XY7",
+            Instruction::ShiftLeft { .. } => "8XYE",
+            Instruction::SkipNe { .. } => "9XY0",
+            Instruction::SetI(_) => "ANNN",
+            Instruction::JumpOffset(_) => "BNNN",
+            Instruction::Color { .. } => "BXYN",
+            Instruction::Random { .. } => "CXNN",
+            Instruction::Draw { .. } => "DXYN",
+            Instruction::SkipKey { .. } => "EX9E",
+            Instruction::SkipNotKey { .. } => "EXA1",
+            Instruction::SkipKey2 { .. } => "EXF2",
+            Instruction::SkipNotKey2 { .. } => "EXF5",
+            Instruction::GetDelay { .. } => "FX07",
+            Instruction::WaitKey { .. } => "FX0A",
+            Instruction::SetDelay { .. } => "FX15",
+            Instruction::SetSound { .. } => "FX18",
+            Instruction::AddI { .. } => "FX1E",
+            Instruction::Font { .. } => "FX29",
+            Instruction::Bcd { .. } => "FX33",
+            Instruction::Store { .. } => "FX55",
+            Instruction::Load { .. } => "FX65",
+            Instruction::LongI => "F000 NNNN",
+            Instruction::SelectPlanes(_) => "FN01",
+            Instruction::Unknown(_) => "",
+        }
+    }
+
+    /// Names of the `Quirks` flags that change how the instruction behaves.
+    pub fn quirks(&self) -> &'static [&'static str] {
+        match self {
+            Instruction::ShiftRight { .. } | Instruction::ShiftLeft { .. } => &[SHIFT_USES_VY],
+            Instruction::Store { .. } | Instruction::Load { .. } => &[LOAD_STORE_INCREMENTS_I],
+            Instruction::JumpOffset(_) => &[JUMP_USES_VX],
+            Instruction::Or { .. } | Instruction::And { .. } | Instruction::Xor { .. } => &[VF_RESET],
+            Instruction::Draw { .. } => &[WRAP_SPRITES, COLLISION_COUNTS_ROWS, LARGE_SPRITES],
+            Instruction::ScrollDown(_) | Instruction::ScrollRight | Instruction::ScrollLeft => {
+                &[LORES_SCROLL_HALVED]
+            }
+            _ => &[],
+        }
+    }
+
+    /// Returns `true` for opcodes the variant does not define.
+    pub fn is_unknown(&self) -> bool { matches!(self, Instruction::Unknown(_)) }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let m = self.mnemonic();
+        match *self {
+            Instruction::ClearScreen
+            | Instruction::Return
+            | Instruction::CycleBackground
+            | Instruction::ScrollRight
+            | Instruction::ScrollLeft => {
+                write!(f, "{}", m)
+            }
+            Instruction::MachineCall(nnn) | Instruction::Jump(nnn) | Instruction::Call(nnn) => {
+                write!(f, "{} 0x{:03X}", m, nnn)
+            }
+            Instruction::SkipEqImm { x, nn }
+            | Instruction::SkipNeImm { x, nn }
+            | Instruction::SetImm { x, nn }
+            | Instruction::AddImm { x, nn }
+            | Instruction::Random { x, nn } => write!(f, "{} V{:X}, 0x{:02X}", m, x, nn),
+            Instruction::SkipEq { x, y }
+            | Instruction::Set { x, y }
+            | Instruction::Or { x, y }
+            | Instruction::And { x, y }
+            | Instruction::Xor { x, y }
+            | Instruction::Add { x, y }
+            | Instruction::Sub { x, y }
+            | Instruction::ShiftRight { x, y }
+            | Instruction::SubReverse { x, y }
+            | Instruction::ShiftLeft { x, y }
+            | Instruction::SkipNe { x, y }
+            | Instruction::AddOctal { x, y } => write!(f, "{} V{:X}, V{:X}", m, x, y),
+            Instruction::ScrollDown(n) | Instruction::SelectPlanes(n) => write!(f, "{} {}", m, n),
+            Instruction::SetI(nnn) => write!(f, "{} I, 0x{:03X}", m, nnn),
+            Instruction::JumpOffset(nnn) => write!(f, "{} V0, 0x{:03X}", m, nnn),
+            Instruction::Draw { x, y, n } | Instruction::Color { x, y, n } => {
+                write!(f, "{} V{:X}, V{:X}, {}", m, x, y, n)
+            }
+            Instruction::SkipKey { x }
+            | Instruction::SkipNotKey { x }
+            | Instruction::SkipKey2 { x }
+            | Instruction::SkipNotKey2 { x } => write!(f, "{} V{:X}", m, x),
+            Instruction::GetDelay { x } => write!(f, "{} V{:X}, DT", m, x),
+            Instruction::WaitKey { x } => write!(f, "{} V{:X}, K", m, x),
+            Instruction::SetDelay { x } => write!(f, "{} DT, V{:X}", m, x),
+            Instruction::SetSound { x } => write!(f, "{} ST, V{:X}", m, x),
+            Instruction::AddI { x } => write!(f, "{} I, V{:X}", m, x),
+            Instruction::Font { x } => write!(f, "{} F, V{:X}", m, x),
+            Instruction::Bcd { x } => write!(f, "{} B, V{:X}", m, x),
+            Instruction::Store { x } => write!(f, "{} [I], V{:X}", m, x),
+            Instruction::Load { x } => write!(f, "{} V{:X}, [I]", m, x),
+            Instruction::LongI => write!(f, "{} I, long", m),
+            Instruction::Unknown(opcode) => write!(f, "{} 0x{:04X}", m, opcode),
+        }
+    }
+}
+
+/// Opcode pattern, a sample opcode matching it and what it does.
+pub(crate) const PATTERNS: &[(&str, u16, &str)] = &[
+    ("0NNN", 0x0123, "Call machine code routine at NNN (skipped, see MachineCallPolicy)"),
+    ("02A0", 0x02a0, "Cycle the background color (CHIP-8X)"),
+    ("00E0", 0x00e0, "Clear the display"),
+    ("00EE", 0x00ee, "Return from subroutine"),
+    ("00CN", 0x00c3, "Scroll the display down by N rows"),
+    ("00FB", 0x00fb, "Scroll the display right by 4 pixels"),
+    ("00FC", 0x00fc, "Scroll the display left by 4 pixels"),
+    ("1NNN", 0x1234, "Jump to NNN"),
+    ("2NNN", 0x2234, "Call subroutine at NNN"),
+    ("3XNN", 0x3123, "Skip next instruction if VX == NN"),
+    ("4XNN", 0x4123, "Skip next instruction if VX != NN"),
+    ("5XY0", 0x5120, "Skip next instruction if VX == VY"),
+    ("5XY1", 0x5121, "VX += VY, each nibble wrapping at 8 (CHIP-8X)"),
+    ("6XNN", 0x6123, "VX = NN"),
+    ("7XNN", 0x7123, "VX += NN without carry"),
+    ("8XY0", 0x8120, "VX = VY"),
+    ("8XY1", 0x8121, "VX |= VY"),
+    ("8XY2", 0x8122, "VX &= VY"),
+    ("8XY3", 0x8123, "VX ^= VY"),
+    ("8XY4", 0x8124, "VX += VY, VF = carry"),
+    ("8XY5", 0x8125, "VX -= VY, VF = not borrow"),
+    ("8XY6", 0x8126, "VX >>= 1, VF = shifted out bit"),
+    ("8XY7", 0x8127, "VX = VY - VX, VF = not borrow"),
+    ("8XYE", 0x812e, "VX <<= 1, VF = shifted out bit"),
+    ("9XY0", 0x9120, "Skip next instruction if VX != VY"),
+    ("ANNN", 0xa123, "I = NNN"),
+    ("BNNN", 0xb123, "Jump to NNN + V0"),
+    ("BXYN", 0xb123, "Color the zones selected by VX and VY with V[X + 1] (CHIP-8X)"),
+    ("CXNN", 0xc123, "VX = random byte & NN"),
+    ("DXYN", 0xd123, "Draw N rows of sprite at I to (VX, VY), VF = collision (DXY0: 16x16 with the large_sprites quirk)"),
+    ("EX9E", 0xe19e, "Skip next instruction if key VX is pressed"),
+    ("EXA1", 0xe1a1, "Skip next instruction if key VX is not pressed"),
+    ("EXF2", 0xe1f2, "Skip next instruction if key VX of the second keypad is pressed (CHIP-8X)"),
+    ("EXF5", 0xe1f5, "Skip next instruction if key VX of the second keypad is not pressed (CHIP-8X)"),
+    ("FX07", 0xf107, "VX = delay timer"),
+    ("FX0A", 0xf10a, "Wait for a key, VX = key (see KeyWait)"),
+    ("FX15", 0xf115, "Delay timer = VX"),
+    ("FX18", 0xf118, "Sound timer = VX"),
+    ("FX1E", 0xf11e, "I += VX"),
+    ("FX29", 0xf129, "I = address of the font character VX"),
+    ("FX33", 0xf133, "Store BCD of VX at I, I + 1 and I + 2"),
+    ("FX55", 0xf155, "Store V0 to VX at I"),
+    ("FX65", 0xf165, "Load V0 to VX from I"),
+    ("F000 NNNN", 0xf000, "I = NNNN (16-bit)"),
+    ("FN01", 0xf301, "Select the display planes in the bitmask N for drawing, clearing and scrolling"),
+];
+
+/// Description of a supported opcode for reference panels and documentation tools.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct OpcodeInfo {
+    pub pattern: &'static str,
+    pub mnemonic: &'static str,
+    pub description: &'static str,
+    /// Variants that decode the pattern.
+    pub variants: Vec<Variant>,
+    /// Names of the `Quirks` flags that change its behavior.
+    pub quirks: Vec<&'static str>,
+}
+
+/// Lists every opcode the decoder supports. Availability and quirks come from the decoder itself,
+/// so the reference cannot go out of sync with the emulator.
+///
+/// # Example
+///
+/// ```
+/// use wasm_chip8::{opcode_reference, Variant};
+/// let long_i = opcode_reference().into_iter().find(|info| info.pattern == "F000 NNNN").unwrap();
+/// assert_eq!(long_i.variants, [Variant::XoChip]);
+/// let jump = opcode_reference().into_iter().find(|info| info.pattern == "BNNN").unwrap();
+/// assert!(!jump.variants.contains(&Variant::Chip8X));
+/// ```
+pub fn opcode_reference() -> Vec<OpcodeInfo> {
+    PATTERNS
+        .iter()
+        .map(|&(pattern, sample, description)| {
+            let variants: Vec<Variant> = Variant::ALL
+                .iter()
+                .copied()
+                .filter(|&variant| Instruction::decode(sample, variant).pattern() == pattern)
+                .collect();
+            let instruction = Instruction::decode(sample, variants.first().copied().unwrap_or_default());
+
+            OpcodeInfo {
+                pattern,
+                mnemonic: instruction.mnemonic(),
+                description,
+                variants,
+                quirks: instruction.quirks().to_vec(),
+            }
+        })
+        .collect()
+}
+
+/// Returns `opcode_reference` as an array of plain JS objects.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = opcode_reference)]
+pub fn opcode_reference_js() -> JsValue { crate::utils::to_js(&opcode_reference()) }
@@ -0,0 +1,205 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::utils;
+
+/// Sound output that is switched on and off by the sound timer.
+pub trait AudioBackend {
+    fn start(&mut self);
+    fn stop(&mut self);
+    fn is_active(&self) -> bool;
+
+    /// Changes the pitch of the beep. Backends with a fixed tone can ignore it.
+    fn set_frequency(&mut self, _hz: f32) {}
+
+    /// Changes the loudness of the beep, 0 to 1. Backends with a fixed volume can ignore it.
+    fn set_volume(&mut self, _volume: f32) {}
+}
+
+/// Source of the hex keypad state polled by the key opcodes.
+pub trait KeyboardBackend {
+    fn is_key_pressed(&self, key: u8) -> bool;
+}
+
+/// Monotonic clock every host time measurement goes through, so timing can be tested without
+/// real time passing.
+pub trait TimeSource {
+    /// Milliseconds since an arbitrary point that stays fixed for the life of the source.
+    fn now_ms(&self) -> f64;
+}
+
+/// Clock of the host: `performance.now()` in the browser and `std::time::Instant` elsewhere.
+#[derive(Debug, Default)]
+pub struct HostClock;
+
+impl TimeSource for HostClock {
+    fn now_ms(&self) -> f64 { utils::monotonic_ms() }
+}
+
+/// Clock that only moves when it is told to. Clones share the time, so a test keeps one clone
+/// and hands the other to the emulator.
+///
+/// # Example
+///
+/// ```
+/// use wasm_chip8::{MockClock, TimeSource};
+/// let clock = MockClock::default();
+/// let handle = clock.clone();
+/// handle.advance(16.0);
+/// assert_eq!(clock.now_ms(), 16.0);
+/// // Every reading moves the clock by the step afterwards.
+/// handle.set_step(1.0);
+/// assert_eq!((clock.now_ms(), clock.now_ms()), (16.0, 17.0));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MockClock {
+    now: Rc<Cell<f64>>,
+    step: Rc<Cell<f64>>,
+}
+
+impl MockClock {
+    /// Moves the clock forward by `ms`.
+    pub fn advance(&self, ms: f64) { self.now.set(self.now.get() + ms); }
+
+    /// Sets how far the clock moves after every reading, 0 by default.
+    pub fn set_step(&self, ms: f64) { self.step.set(ms); }
+}
+
+impl TimeSource for MockClock {
+    fn now_ms(&self) -> f64 {
+        let now = self.now.get();
+        self.now.set(now + self.step.get());
+        now
+    }
+}
+
+/// Persistent storage of the save window (see `SAVE_WINDOW_START`), one save per ROM hash.
+pub trait SaveStorage {
+    /// Returns the save stored for the ROM, if any.
+    fn load(&self, rom_hash: u32) -> Option<Vec<u8>>;
+
+    /// Replaces the save stored for the ROM.
+    fn store(&mut self, rom_hash: u32, data: &[u8]);
+}
+
+/// Save storage that keeps saves in memory for the life of the process. Clones share the saves,
+/// so a test or a host that persists them itself keeps one clone and hands the other to the
+/// emulator.
+#[derive(Clone, Debug, Default)]
+pub struct MemorySaveStorage {
+    saves: Rc<RefCell<HashMap<u32, Vec<u8>>>>,
+}
+
+impl MemorySaveStorage {
+    /// Returns the save stored for the ROM, if any.
+    pub fn get(&self, rom_hash: u32) -> Option<Vec<u8>> { self.saves.borrow().get(&rom_hash).cloned() }
+}
+
+impl SaveStorage for MemorySaveStorage {
+    fn load(&self, rom_hash: u32) -> Option<Vec<u8>> { self.get(rom_hash) }
+
+    fn store(&mut self, rom_hash: u32, data: &[u8]) { self.saves.borrow_mut().insert(rom_hash, data.to_vec()); }
+}
+
+/// Audio backend for headless use. It plays nothing and only remembers whether it was started.
+#[derive(Debug, Default)]
+pub struct SilentAudio {
+    active: bool,
+}
+
+impl AudioBackend for SilentAudio {
+    fn start(&mut self) { self.active = true; }
+
+    fn stop(&mut self) { self.active = false; }
+
+    fn is_active(&self) -> bool { self.active }
+}
+
+/// Keyboard backend that never reports a pressed key. Headless hosts drive input through
+/// `Emulator::set_key` instead.
+#[derive(Debug, Default)]
+pub struct NoKeyboard;
+
+impl KeyboardBackend for NoKeyboard {
+    fn is_key_pressed(&self, _key: u8) -> bool { false }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(raw_module = "../../js/io-interfaces/audio.js")]
+extern "C" {
+    pub type Audio;
+
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Audio;
+
+    #[wasm_bindgen(method, js_name = start)]
+    fn js_start(this: &Audio);
+
+    #[wasm_bindgen(method, js_name = stop)]
+    fn js_stop(this: &Audio);
+
+    #[wasm_bindgen(method, js_name = is_active)]
+    fn js_is_active(this: &Audio) -> bool;
+
+    #[wasm_bindgen(method, js_name = set_frequency)]
+    fn js_set_frequency(this: &Audio, hz: f32);
+
+    #[wasm_bindgen(method, js_name = set_volume)]
+    fn js_set_volume(this: &Audio, volume: f32);
+}
+
+#[cfg(target_arch = "wasm32")]
+impl AudioBackend for Audio {
+    fn start(&mut self) { self.js_start(); }
+
+    fn stop(&mut self) { self.js_stop(); }
+
+    fn is_active(&self) -> bool { self.js_is_active() }
+
+    fn set_frequency(&mut self, hz: f32) { self.js_set_frequency(hz); }
+
+    fn set_volume(&mut self, volume: f32) { self.js_set_volume(volume); }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(raw_module = "../../js/io-interfaces/keyboard.js")]
+extern "C" {
+    pub type Keyboard;
+
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Keyboard;
+
+    #[wasm_bindgen(method, js_name = is_key_pressed)]
+    fn js_is_key_pressed(this: &Keyboard, key: u8) -> bool;
+}
+
+#[cfg(target_arch = "wasm32")]
+impl KeyboardBackend for Keyboard {
+    fn is_key_pressed(&self, key: u8) -> bool { self.js_is_key_pressed(key) }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(raw_module = "../../js/io-interfaces/storage.js")]
+extern "C" {
+    pub type LocalSaveStorage;
+
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> LocalSaveStorage;
+
+    #[wasm_bindgen(method, js_name = load)]
+    fn js_load(this: &LocalSaveStorage, rom_hash: u32) -> Option<Vec<u8>>;
+
+    #[wasm_bindgen(method, js_name = store)]
+    fn js_store(this: &LocalSaveStorage, rom_hash: u32, data: &[u8]);
+}
+
+#[cfg(target_arch = "wasm32")]
+impl SaveStorage for LocalSaveStorage {
+    fn load(&self, rom_hash: u32) -> Option<Vec<u8>> { self.js_load(rom_hash) }
+
+    fn store(&mut self, rom_hash: u32, data: &[u8]) { self.js_store(rom_hash, data); }
+}
@@ -0,0 +1,111 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+// Magic bytes an IPS patch starts and ends with.
+const HEADER: &[u8] = b"PATCH";
+const FOOTER: &[u8] = b"EOF";
+
+/// Reason an IPS patch was rejected. A rejected patch leaves memory untouched.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatchError {
+    /// The patch does not start with `PATCH`.
+    InvalidHeader,
+    /// The patch ends in the middle of a record or has no `EOF` marker.
+    Truncated,
+    /// A record writes past the end of memory.
+    OutOfRange,
+}
+
+// Bytes a record writes, relative to the start of the program.
+struct Record<'a> {
+    offset: usize,
+    data: RecordData<'a>,
+}
+
+enum RecordData<'a> {
+    Bytes(&'a [u8]),
+    Run { length: usize, value: u8 },
+}
+
+impl<'a> Record<'a> {
+    fn len(&self) -> usize {
+        match self.data {
+            RecordData::Bytes(bytes) => bytes.len(),
+            RecordData::Run { length, .. } => length,
+        }
+    }
+}
+
+// Splits a patch into its records.
+fn parse(patch: &[u8]) -> Result<Vec<Record<'_>>, PatchError> {
+    let mut rest = patch.strip_prefix(HEADER).ok_or(PatchError::InvalidHeader)?;
+    let mut records = vec![];
+
+    loop {
+        if rest.starts_with(FOOTER) && (rest.len() == FOOTER.len() || rest.len() == FOOTER.len() + 3) {
+            // An optional 3 byte size after `EOF` truncates the patched file, which does not
+            // apply to memory.
+            return Ok(records);
+        }
+
+        let (header, body) = split(rest, 5)?;
+        let offset = usize::from(header[0]) << 16 | usize::from(header[1]) << 8 | usize::from(header[2]);
+        let size = usize::from(header[3]) << 8 | usize::from(header[4]);
+        if size == 0 {
+            let (run, body) = split(body, 3)?;
+            let length = usize::from(run[0]) << 8 | usize::from(run[1]);
+            records.push(Record { offset, data: RecordData::Run { length, value: run[2] } });
+            rest = body;
+        } else {
+            let (bytes, body) = split(body, size)?;
+            records.push(Record { offset, data: RecordData::Bytes(bytes) });
+            rest = body;
+        }
+    }
+}
+
+fn split(bytes: &[u8], at: usize) -> Result<(&[u8], &[u8]), PatchError> {
+    if bytes.len() < at {
+        return Err(PatchError::Truncated);
+    }
+    Ok(bytes.split_at(at))
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Applies an IPS patch to the loaded program, so translations and bug fixes distributed as
+    /// patches can be used without patching the ROM file first. Record offsets are relative to
+    /// the load address of the variant. Apply it after `load`, which overwrites the patched
+    /// bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 1
+    /// emulator.load(&[0x60, 0x01]).unwrap();
+    /// // Patch the second byte to 0x2a.
+    /// emulator.apply_ips_patch(b"PATCH\x00\x00\x01\x00\x01\x2aEOF").unwrap();
+    /// emulator.tick();
+    /// assert_eq!(emulator.v(0), 0x2a);
+    /// ```
+    pub fn apply_ips_patch(&mut self, patch: &[u8]) -> Result<(), PatchError> {
+        let start = self.variant.load_address();
+        let records = parse(patch)?;
+        if records.iter().any(|record| start + record.offset + record.len() > self.memory.len()) {
+            return Err(PatchError::OutOfRange);
+        }
+
+        for record in records {
+            let target = &mut self.memory[start + record.offset..start + record.offset + record.len()];
+            match record.data {
+                RecordData::Bytes(bytes) => target.copy_from_slice(bytes),
+                RecordData::Run { value, .. } => target.fill(value),
+            }
+        }
+        Ok(())
+    }
+}
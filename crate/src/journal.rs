@@ -0,0 +1,194 @@
+//! Journal of the effects of every executed instruction, so execution can be reversed one
+//! instruction at a time.
+//!
+//! Instead of a copy of the machine an entry holds the registers before the instruction, the old
+//! values of the memory bytes it wrote and the pixels it flipped. Only instructions that change
+//! the whole display keep a copy of it. An entry of a typical instruction is well under 100
+//! bytes, where a snapshot holds all of memory.
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::*;
+
+use crate::chip8x::ColorBoard;
+use crate::utils::XorShift;
+use crate::{Emulator, Gfx, Instruction};
+
+/// Machine state before an instruction and what the instruction overwrote.
+#[derive(Clone, Debug)]
+struct Entry {
+    pc: usize,
+    i: usize,
+    opcode: u16,
+    v: [u8; 16],
+    delay_timer: u8,
+    sound_timer: u8,
+    draw_flag: bool,
+    planes: u8,
+    frame: u64,
+    frame_cycle: u32,
+    total_cycles: u64,
+    rng: u32,
+    // Depth of the stack and the address on top of it, which a return pops.
+    stack_len: usize,
+    stack_top: Option<usize>,
+    // Addresses the instruction wrote and their old values.
+    writes: Vec<(usize, u8)>,
+    // Pixels DXYN flipped and the plane of each. Flipping them again undoes the draw.
+    flips: Vec<(u8, u8, u8)>,
+    // Display before an instruction that clears or scrolls it.
+    gfx: Option<Box<Gfx>>,
+    // Color board before a CHIP-8X color instruction.
+    color_board: Option<Box<ColorBoard>>,
+}
+
+/// Entries of the most recent instructions, at most `capacity` of them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Journal {
+    capacity: usize,
+    entries: VecDeque<Entry>,
+}
+
+impl Journal {
+    pub(crate) fn is_enabled(&self) -> bool { self.capacity > 0 }
+
+    pub(crate) fn clear(&mut self) { self.entries.clear(); }
+
+    fn push(&mut self, entry: Entry) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Records the old value of a memory byte the current instruction writes.
+    pub(crate) fn write(&mut self, address: usize, old: u8) {
+        if let Some(entry) = self.entries.back_mut() {
+            entry.writes.push((address, old));
+        }
+    }
+
+    /// Records a pixel the current instruction flips in the given plane.
+    pub(crate) fn flip(&mut self, x: usize, y: usize, plane: u8) {
+        if let Some(entry) = self.entries.back_mut() {
+            entry.flips.push((x as u8, y as u8, plane));
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Starts journaling the effects of the executed instructions, keeping the entries of the
+    /// last `capacity` of them, so `step_back` can reverse them. 0 stops journaling. The
+    /// journal is cleared when the machine state is replaced: by `load`, `reset`, `load_state`
+    /// and `restore_snapshot`.
+    ///
+    /// Stepping back restores the machine: registers, stack, timers, memory, the display, the
+    /// CHIP-8X color board, the frame counters and the random number generator. The keypad
+    /// follows the host and the records kept for debugging (history, coverage, heatmap, events)
+    /// are not rewound.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_journal_capacity(1000);
+    /// // v0 := 7, i := 0x300, save v0, i := font 0, sprite v0 v0 5
+    /// emulator.load(&[0x60, 0x07, 0xa3, 0x00, 0xf0, 0x55, 0xa0, 0x00, 0xd0, 0x05]).unwrap();
+    /// let start = emulator.state_hash();
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.journal_len(), 10);
+    /// assert_eq!(emulator.step_back_instructions(10), 10);
+    /// assert_eq!(emulator.state_hash(), start);
+    /// assert!(!emulator.step_back());
+    /// ```
+    pub fn set_journal_capacity(&mut self, capacity: usize) {
+        self.journal.capacity = capacity;
+        while self.journal.entries.len() > capacity {
+            self.journal.entries.pop_front();
+        }
+    }
+
+    /// Instructions `step_back` can reverse.
+    pub fn journal_len(&self) -> usize { self.journal.entries.len() }
+
+    /// Reverses the last executed instruction. Returns `false` if the journal is empty.
+    pub fn step_back(&mut self) -> bool {
+        let entry = match self.journal.entries.pop_back() {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        for &(address, old) in entry.writes.iter().rev() {
+            self.memory[address] = old;
+        }
+        for &(x, y, plane) in &entry.flips {
+            self.gfx[usize::from(y)][usize::from(x)] ^= plane;
+        }
+        if let Some(gfx) = entry.gfx {
+            self.gfx = *gfx;
+        }
+        if let Some(board) = entry.color_board {
+            self.color_board.restore(*board);
+        }
+        self.stack.truncate(entry.stack_len);
+        if self.stack.len() < entry.stack_len {
+            self.stack.extend(entry.stack_top);
+        }
+
+        self.pc = entry.pc;
+        self.i = entry.i;
+        self.opcode = entry.opcode;
+        self.v = entry.v;
+        self.delay_timer = entry.delay_timer;
+        self.sound_timer = entry.sound_timer;
+        self.draw_flag = entry.draw_flag;
+        self.planes = entry.planes;
+        self.frame = entry.frame;
+        self.frame_cycle = entry.frame_cycle;
+        self.total_cycles = entry.total_cycles;
+        self.rng = XorShift::new(entry.rng);
+        self.fault = None;
+        self.sync_audio();
+        true
+    }
+
+    /// Reverses up to `count` instructions and returns how many were reversed.
+    pub fn step_back_instructions(&mut self, count: usize) -> usize {
+        (0..count).take_while(|_| self.step_back()).count()
+    }
+}
+
+impl Emulator {
+    /// Opens the journal entry of the instruction about to execute.
+    pub(crate) fn journal_before(&mut self) {
+        let instruction = Instruction::decode(self.get_opcode(), self.variant);
+        let replaces_display = matches!(
+            instruction,
+            Instruction::ClearScreen | Instruction::ScrollDown(_) | Instruction::ScrollRight | Instruction::ScrollLeft
+        );
+        let colors = matches!(instruction, Instruction::CycleBackground | Instruction::Color { .. });
+        let entry = Entry {
+            pc: self.pc,
+            i: self.i,
+            opcode: self.opcode,
+            v: self.v,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            draw_flag: self.draw_flag,
+            planes: self.planes,
+            frame: self.frame,
+            frame_cycle: self.frame_cycle,
+            total_cycles: self.total_cycles,
+            rng: self.rng.state(),
+            stack_len: self.stack.len(),
+            stack_top: self.stack.last().copied(),
+            writes: vec![],
+            flips: vec![],
+            gfx: if replaces_display { Some(Box::new(self.gfx)) } else { None },
+            color_board: if colors { Some(Box::new(self.color_board.clone())) } else { None },
+        };
+        self.journal.push(entry);
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::*;
+
+use crate::io::KeyboardBackend;
+use crate::{Emulator, Instruction, Variant};
+
+/// When EX9E and EXA1 look at the keypad.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyPolling {
+    /// A snapshot taken at the start of every frame. Every instruction of a frame sees the same
+    /// keys, so replays and netplay stay deterministic.
+    #[default]
+    Frame,
+    /// The keypad as it is when the instruction executes, for the lowest input latency. Netplay
+    /// input and macros still change once per frame.
+    Instruction,
+}
+
+/// How many held keys the program sees at once.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyRollover {
+    /// Every held key.
+    #[default]
+    All,
+    /// Only the held key that was pressed last, like the COSMAC VIP keypad matrix that reliably
+    /// reports a single key. Of keys pressed in the same frame the highest one counts as last.
+    Latest,
+    /// Only the held key with the lowest index.
+    Lowest,
+}
+
+/// Which key event ends the wait of FX0A.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyWait {
+    /// The release of any key, like Octo. A key held when FX0A starts counts once it is released.
+    #[default]
+    Release,
+    /// The release of the key that was held first, like the COSMAC VIP interpreter that waits for
+    /// a key to be pressed and then for that key to be released.
+    SameKey,
+    /// The press of any key, like interpreters that return as soon as a key goes down.
+    Press,
+}
+
+impl KeyWait {
+    /// Returns how FX0A usually waits on the given variant.
+    pub fn for_variant(variant: Variant) -> KeyWait {
+        match variant {
+            Variant::Chip8 | Variant::XoChip => KeyWait::Release,
+            Variant::Chip8X | Variant::Vip2K => KeyWait::SameKey,
+            // CHIPOS returns from its key routine on the press.
+            Variant::Dream6800 => KeyWait::Press,
+        }
+    }
+}
+
+/// Key state change scheduled for the start of a frame, used for replays and tests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: u8,
+    pub down: bool,
+    pub frame: u64,
+}
+
+/// State of the 16-key hex keypad.
+///
+/// The host state is latched into a snapshot once per frame, so every instruction executed during
+/// a frame sees the same keys and key releases can be detected as edges between two snapshots.
+#[derive(Clone, Debug, Default)]
+pub struct Keypad {
+    // Bit N is set while key N is held down by the host.
+    pressed: u16,
+    // Keys pressed for the whole frame by frame-based sources (netplay input, macros).
+    scheduled: u16,
+    // Snapshots taken at the start of the current and the previous frame.
+    current: u16,
+    previous: u16,
+    // Release edges of the current frame that were already consumed by FX0A.
+    consumed: u16,
+    // Keys held at the current snapshot before `rollover` hid some of them, and the key pressed
+    // last.
+    held: u16,
+    latest: u8,
+    rollover: KeyRollover,
+    // Event ending FX0A, and the key whose release it waits for with `KeyWait::SameKey`.
+    wait: KeyWait,
+    awaited: Option<u8>,
+    // Injected events ordered by frame.
+    queue: VecDeque<KeyEvent>,
+}
+
+impl Keypad {
+    pub fn set(&mut self, key: u8, pressed: bool) {
+        if key > 0xf {
+            return;
+        }
+
+        if pressed {
+            self.pressed |= 1 << key;
+        } else {
+            self.pressed &= !(1 << key);
+        }
+    }
+
+    /// Releases every host key and drops the scheduled events.
+    pub fn release_all(&mut self) {
+        self.pressed = 0;
+        self.queue.clear();
+    }
+
+    /// Schedules an event, keeping events of the same frame in injection order.
+    pub fn inject(&mut self, event: KeyEvent) {
+        let position = self.queue.iter().position(|queued| queued.frame > event.frame).unwrap_or(self.queue.len());
+        self.queue.insert(position, event);
+    }
+
+    /// Applies the events due at `frame` and takes a new snapshot. Keys in `scheduled` and `live`
+    /// are reported as pressed in addition to the host state, the `scheduled` ones stay pressed
+    /// until the next snapshot even for `live_state`.
+    pub fn latch(&mut self, frame: u64, scheduled: u16, live: u16) {
+        while self.queue.front().is_some_and(|event| event.frame <= frame) {
+            if let Some(event) = self.queue.pop_front() {
+                self.set(event.key, event.down);
+            }
+        }
+
+        let held = self.pressed | scheduled | live;
+        let pressed = held & !self.held;
+        if pressed != 0 {
+            self.latest = 15 - pressed.leading_zeros() as u8;
+        }
+        self.held = held;
+
+        self.previous = self.current;
+        self.scheduled = scheduled;
+        self.current = self.visible(held);
+        self.consumed = 0;
+    }
+
+    /// Keys held down right now as a bitmask, `live` being the current state of live sources.
+    pub fn live_state(&self, live: u16) -> u16 { self.visible(self.pressed | self.scheduled | live) }
+
+    pub fn set_rollover(&mut self, rollover: KeyRollover) { self.rollover = rollover; }
+
+    pub fn rollover(&self) -> KeyRollover { self.rollover }
+
+    // The held keys the program sees.
+    fn visible(&self, held: u16) -> u16 {
+        match self.rollover {
+            KeyRollover::All => held,
+            KeyRollover::Latest if held & (1 << self.latest) != 0 => 1 << self.latest,
+            KeyRollover::Latest | KeyRollover::Lowest => held & held.wrapping_neg(),
+        }
+    }
+
+    /// Returns `true` if the next snapshot would equal the current one and no release edge is
+    /// pending, `live` being the current state of live sources.
+    pub fn is_settled(&self, live: u16) -> bool {
+        self.queue.is_empty() && self.previous == self.current && self.live_state(live) == self.current
+    }
+
+    /// Keys held down in the current snapshot as a bitmask.
+    pub fn snapshot(&self) -> u16 { self.current }
+
+    /// Returns `true` if the key was held down in the current snapshot.
+    pub fn is_pressed(&self, key: u8) -> bool { key <= 0xf && self.current & (1 << key) != 0 }
+
+    pub fn set_wait(&mut self, wait: KeyWait) {
+        self.wait = wait;
+        self.awaited = None;
+    }
+
+    pub fn wait(&self) -> KeyWait { self.wait }
+
+    /// Key held down while FX0A waits for its release with `KeyWait::SameKey`.
+    pub fn awaited(&self) -> Option<u8> { self.awaited }
+
+    /// Forgets the key FX0A was waiting on, e.g. when the program is reset.
+    pub fn cancel_wait(&mut self) { self.awaited = None; }
+
+    /// Returns the key that ends the wait of FX0A, checked once per executed FX0A.
+    pub fn take_wait_key(&mut self) -> Option<u8> {
+        match (self.wait, self.awaited) {
+            (KeyWait::Release, _) => self.take_edge(self.previous & !self.current),
+            (KeyWait::Press, _) => self.take_edge(self.current & !self.previous),
+            (KeyWait::SameKey, None) => {
+                self.awaited = (self.current != 0).then(|| self.current.trailing_zeros() as u8);
+                None
+            }
+            (KeyWait::SameKey, Some(key)) if !self.is_pressed(key) => self.awaited.take(),
+            (KeyWait::SameKey, Some(_)) => None,
+        }
+    }
+
+    // Returns the lowest key of the edges between the previous and the current snapshot that has
+    // not been taken yet during this frame.
+    fn take_edge(&mut self, edges: u16) -> Option<u8> {
+        let edges = edges & !self.consumed;
+        if edges == 0 {
+            return None;
+        }
+
+        let key = edges.trailing_zeros() as u8;
+        self.consumed |= 1 << key;
+        Some(key)
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Chooses the keypad state EX9E and EXA1 test. FX0A always compares frame snapshots, see
+    /// `set_key_wait`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, KeyPolling};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_key_polling(KeyPolling::Instruction);
+    /// // v1 := 1 once key 0 is held down, seen without waiting for the next frame
+    /// emulator.load(&[0xe0, 0x9e, 0x12, 0x00, 0x61, 0x01]).unwrap();
+    /// emulator.tick();
+    /// emulator.set_key(0x0, true);
+    /// for _ in 0..3 {
+    ///     emulator.tick();
+    /// }
+    /// assert_eq!(emulator.crash_dump().v[1], 1);
+    /// ```
+    pub fn set_key_polling(&mut self, polling: KeyPolling) { self.key_polling = polling; }
+
+    pub fn key_polling(&self) -> KeyPolling { self.key_polling }
+
+    /// Limits how many held keys EX9E, EXA1 and FX0A see, for games that malfunction when several
+    /// keys are reported at once as they could not be on the original hardware. Applies from the
+    /// next keypad snapshot.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, KeyRollover};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_key_rollover(KeyRollover::Latest);
+    /// // v0 := 2, v3 := 5, loop: v1 := key v0 held, v2 := key v3 held, jump loop
+    /// let held = [0x61, 0x00, 0xe0, 0xa1, 0x61, 0x01, 0x62, 0x00, 0xe3, 0xa1, 0x62, 0x01, 0x12, 0x04];
+    /// emulator.load(&[&[0x60, 0x02, 0x63, 0x05][..], &held].concat()).unwrap();
+    /// let seen = |emulator: &mut Emulator| {
+    ///     emulator.tick_frame();
+    ///     emulator.run_to(0x210, 100);
+    ///     emulator.run_to(0x210, 100);
+    ///     (emulator.v(1), emulator.v(2))
+    /// };
+    /// emulator.set_key(0x5, true);
+    /// assert_eq!(seen(&mut emulator), (0, 1));
+    /// emulator.set_key(0x2, true);
+    /// assert_eq!(seen(&mut emulator), (1, 0));
+    /// emulator.set_key(0x2, false);
+    /// assert_eq!(seen(&mut emulator), (0, 1));
+    ///
+    /// emulator.set_key_rollover(KeyRollover::Lowest);
+    /// emulator.set_key(0x2, true);
+    /// emulator.set_key(0x9, true);
+    /// assert_eq!(seen(&mut emulator), (1, 0));
+    /// emulator.set_key_rollover(KeyRollover::All);
+    /// assert_eq!(seen(&mut emulator), (1, 1));
+    /// ```
+    pub fn set_key_rollover(&mut self, rollover: KeyRollover) { self.keypad.set_rollover(rollover); }
+
+    pub fn key_rollover(&self) -> KeyRollover { self.keypad.rollover() }
+
+    /// Chooses the key event that ends the wait of FX0A. `set_variant` picks the one of the
+    /// variant, as programs written for the VIP may expect to see a key only once it is released.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, KeyWait, Variant};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_variant(Variant::Vip2K);
+    /// assert_eq!(emulator.key_wait(), KeyWait::SameKey);
+    /// // v0 := key, v1 := 1
+    /// emulator.load(&[0xf0, 0x0a, 0x61, 0x01]).unwrap();
+    /// emulator.tick_frame();
+    /// assert!(emulator.is_waiting_for_key());
+    /// emulator.set_key(0x7, true);
+    /// emulator.tick_frame();
+    /// emulator.set_key(0x3, true);
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.awaited_key(), Some(0x7));
+    /// // Releasing another key does not end the wait.
+    /// emulator.set_key(0x3, false);
+    /// emulator.tick_frame();
+    /// assert!(emulator.is_waiting_for_key());
+    /// emulator.set_key(0x7, false);
+    /// emulator.tick_frame();
+    /// assert_eq!((emulator.v(0), emulator.v(1)), (0x7, 1));
+    /// assert!(!emulator.is_waiting_for_key());
+    ///
+    /// emulator.reset();
+    /// emulator.set_key_wait(KeyWait::Press);
+    /// emulator.load(&[0xf0, 0x0a, 0x61, 0x01]).unwrap();
+    /// emulator.tick_frame();
+    /// emulator.set_key(0x2, true);
+    /// emulator.tick_frame();
+    /// assert_eq!((emulator.v(0), emulator.v(1)), (0x2, 1));
+    /// ```
+    pub fn set_key_wait(&mut self, wait: KeyWait) { self.keypad.set_wait(wait); }
+
+    pub fn key_wait(&self) -> KeyWait { self.keypad.wait() }
+
+    /// Returns `true` if the program is stopped in FX0A, e.g. to prompt the user for a key.
+    pub fn is_waiting_for_key(&self) -> bool {
+        self.fault.is_none() && matches!(self.decode_at(self.pc), Instruction::WaitKey { .. })
+    }
+
+    /// Key FX0A saw pressed and waits to be released with `KeyWait::SameKey`, e.g. to hint the
+    /// user to let go of it.
+    pub fn awaited_key(&self) -> Option<u8> { self.keypad.awaited() }
+
+    /// Keys the program sees held down as a bitmask, bit N for key N, so an on-screen keypad can
+    /// highlight what the emulator actually perceives. It includes scheduled input, macros and
+    /// the second player and applies the rollover. With `KeyPolling::Frame` it is the snapshot
+    /// taken at the start of the frame, otherwise the state right now.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, KeyRollover};
+    /// let mut emulator = Emulator::new();
+    /// emulator.load(&[0x12, 0x00]).unwrap();
+    /// emulator.set_key(0x1, true);
+    /// emulator.set_key(0xa, true);
+    /// assert_eq!(emulator.pressed_keys(), 0);
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.pressed_keys(), 0b0000_0100_0000_0010);
+    /// assert_eq!(emulator.pressed_key_list(), [0x1, 0xa]);
+    ///
+    /// emulator.set_key_rollover(KeyRollover::Lowest);
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.pressed_key_list(), [0x1]);
+    /// ```
+    pub fn pressed_keys(&self) -> u16 {
+        match self.key_polling {
+            KeyPolling::Frame => self.keypad.snapshot(),
+            KeyPolling::Instruction => self.keypad.live_state(self.live_keys()),
+        }
+    }
+
+    /// Keys of `pressed_keys` in ascending order.
+    pub fn pressed_key_list(&self) -> Vec<u8> {
+        let keys = self.pressed_keys();
+        (0..16).filter(|key| keys & (1 << key) != 0).collect()
+    }
+}
+
+impl Emulator {
+    /// Replaces the source of live key presses without touching the rest of the emulator, e.g. to
+    /// attach a real keyboard to an emulator that started headless. Keys set with `set_key` stay
+    /// pressed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, KeyboardBackend};
+    ///
+    /// struct Held(u8);
+    ///
+    /// impl KeyboardBackend for Held {
+    ///     fn is_key_pressed(&self, key: u8) -> bool { key == self.0 }
+    /// }
+    ///
+    /// let mut emulator = Emulator::headless();
+    /// emulator.load(&[0x12, 0x00]).unwrap();
+    /// emulator.set_keyboard_backend(Box::new(Held(0x5)));
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.pressed_keys(), 1 << 0x5);
+    /// ```
+    pub fn set_keyboard_backend(&mut self, keyboard: Box<dyn KeyboardBackend>) { self.keyboard = keyboard; }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Replaces the source of live key presses with a JS object that has the methods of the
+    /// `Keyboard` class in `js/io-interfaces/keyboard.js`, see `set_keyboard_backend`. `null` or
+    /// `undefined` disconnect it.
+    #[wasm_bindgen(js_name = set_keyboard_backend)]
+    pub fn set_keyboard_backend_js(&mut self, backend: JsValue) {
+        if backend.is_null() || backend.is_undefined() {
+            self.set_keyboard_backend(Box::new(crate::io::NoKeyboard));
+        } else {
+            self.set_keyboard_backend(Box::new(backend.unchecked_into::<crate::io::Keyboard>()));
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Latencies the probe keeps, older ones are dropped.
+pub const MAX_LATENCY_SAMPLES: usize = 1024;
+
+/// Summary of the measured input latencies. Times are host time measured with the time source
+/// (see `Emulator::set_time_source`), frames are emulated frames.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LatencyStats {
+    /// Key changes the program observed.
+    pub samples: u32,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    /// Latency 95% of the samples stay under.
+    pub p95_ms: f64,
+    pub mean_frames: f64,
+}
+
+// Key change that is waiting to be observed.
+#[derive(Clone, Copy, Debug)]
+struct Change {
+    pressed: bool,
+    at_ms: f64,
+    frame: u64,
+}
+
+/// Times every key change until a key instruction observes it.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LatencyProbe {
+    enabled: bool,
+    pending: [Option<Change>; 16],
+    // Host milliseconds and emulated frames of every observed change.
+    samples: VecDeque<(f64, u64)>,
+}
+
+impl LatencyProbe {
+    pub(crate) fn is_enabled(&self) -> bool { self.enabled }
+
+    pub(crate) fn changed(&mut self, key: u8, pressed: bool, at_ms: f64, frame: u64) {
+        if let Some(pending) = self.pending.get_mut(usize::from(key)) {
+            *pending = Some(Change { pressed, at_ms, frame });
+        }
+    }
+
+    /// Completes the pending change of `key` if the program saw the key in that state, `None`
+    /// matches any state.
+    pub(crate) fn observed(&mut self, key: u8, pressed: Option<bool>, at_ms: f64, frame: u64) {
+        let pending = match self.pending.get_mut(usize::from(key)) {
+            Some(pending) => pending,
+            None => return,
+        };
+        if let Some(change) = pending.filter(|change| pressed.is_none_or(|pressed| pressed == change.pressed)) {
+            *pending = None;
+            if self.samples.len() == MAX_LATENCY_SAMPLES {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(((at_ms - change.at_ms).max(0.0), frame.saturating_sub(change.frame)));
+        }
+    }
+
+    fn stats(&self) -> LatencyStats {
+        if self.samples.is_empty() {
+            return LatencyStats::default();
+        }
+
+        let mut times: Vec<f64> = self.samples.iter().map(|&(ms, _)| ms).collect();
+        times.sort_by(f64::total_cmp);
+        let count = times.len() as f64;
+        LatencyStats {
+            samples: times.len() as u32,
+            min_ms: times[0],
+            max_ms: times[times.len() - 1],
+            mean_ms: times.iter().sum::<f64>() / count,
+            p95_ms: times[((count * 0.95).ceil() as usize).clamp(1, times.len()) - 1],
+            mean_frames: self.samples.iter().map(|&(_, frames)| frames as f64).sum::<f64>() / count,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Starts or stops measuring how long key changes take to reach the program: from the
+    /// `set_key`, `set_player_key` or `set_host_key` call to the first EX9E, EXA1 or FX0A that
+    /// sees the new state. Frontends use it to quantify the latency of their input pipeline.
+    /// Enabling it forgets earlier measurements.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, MockClock};
+    /// let clock = MockClock::default();
+    /// let mut emulator = Emulator::headless();
+    /// emulator.set_time_source(Box::new(clock.clone()));
+    /// emulator.set_latency_probe(true);
+    /// // loop: if -key v0 then jump done, jump loop, done: jump done
+    /// emulator.load(&[0xe0, 0xa1, 0x12, 0x06, 0x12, 0x00, 0x12, 0x06]).unwrap();
+    /// emulator.tick_frame();
+    /// emulator.set_key(0x0, true);
+    /// clock.advance(12.0);
+    /// emulator.tick_frame();
+    /// let stats = emulator.input_latency();
+    /// assert_eq!((stats.samples, stats.mean_ms, stats.mean_frames), (1, 12.0, 0.0));
+    /// ```
+    pub fn set_latency_probe(&mut self, enabled: bool) {
+        self.latency = LatencyProbe { enabled, ..LatencyProbe::default() };
+    }
+
+    /// Statistics of the latencies measured since the probe was enabled.
+    pub fn input_latency(&self) -> LatencyStats { self.latency.stats() }
+}
+
+impl Emulator {
+    /// Tells the latency probe the host changed a key.
+    pub(crate) fn note_key_change(&mut self, key: u8, pressed: bool) {
+        if self.latency.is_enabled() {
+            let now = self.time.now_ms();
+            self.latency.changed(key, pressed, now, self.frame);
+        }
+    }
+
+    /// Tells the latency probe an instruction saw a key in the given state, `None` for FX0A.
+    pub(crate) fn note_key_observed(&mut self, key: u8, pressed: Option<bool>) {
+        if self.latency.is_enabled() {
+            let now = self.time.now_ms();
+            self.latency.observed(key, pressed, now, self.frame);
+        }
+    }
+}
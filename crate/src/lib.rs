@@ -1,7 +1,155 @@
+mod accessibility;
+mod analysis;
+#[cfg(feature = "zip")]
+mod archive;
+mod audio;
+mod auto_speed;
+mod auto_variant;
+mod banks;
+mod battery;
+mod builtin;
+#[cfg(target_arch = "wasm32")]
+mod callbacks;
+mod cfg;
+mod chip8x;
+mod cost;
+mod compare;
+mod coverage;
+mod debug_state;
+mod debugger;
+mod display;
+mod events;
+mod fault;
+mod fork;
+#[cfg(feature = "games")]
+mod games;
+mod heatmap;
+mod idle;
+mod input_script;
+mod instruction;
+mod io;
+mod ips;
+mod journal;
+mod keypad;
+mod latency;
+mod listing;
+mod loader;
+mod machine_code;
+mod macros;
+mod manifest;
+mod memory_init;
+mod midi;
+mod movie;
+#[cfg(feature = "debug")]
+mod mutation;
+mod netplay;
+mod octo;
+mod overlay;
+mod players;
+#[cfg(feature = "png")]
+mod png;
+mod preview;
+mod profile;
+mod quirk_test;
+mod quirks;
+mod render;
+mod reset;
+mod run;
+mod savestate;
+mod scheduler;
+#[cfg(feature = "share")]
+mod share;
+mod slots;
+mod sprites;
+mod state_json;
+mod stats;
+mod step_info;
+mod strict;
+mod svg;
+mod trace;
+#[cfg(feature = "tui")]
+pub mod tui;
 mod utils;
+mod variant;
+#[cfg(target_arch = "wasm32")]
+mod views;
+#[cfg(all(feature = "web", target_arch = "wasm32"))]
+mod web;
+
+use std::collections::BTreeMap;
 
 use wasm_bindgen::prelude::*;
-use rand::Rng;
+
+pub use crate::accessibility::{AccessibilityOptions, PalettePreset};
+#[cfg(feature = "zip")]
+pub use crate::archive::{extract_archive_entry, list_archive, ArchiveError};
+pub use crate::audio::{FrameAudio, Synth, DEFAULT_SAMPLE_RATE, DEFAULT_TONE_FREQUENCY};
+pub use crate::banks::{BankError, BANK_COUNT};
+pub use crate::battery::{SAVE_WINDOW_SIZE, SAVE_WINDOW_START};
+pub use crate::builtin::{generate_test_rom, Builtin, Test};
+pub use crate::cfg::{BasicBlock, ControlFlowGraph, Edge, EdgeKind};
+pub use crate::chip8x::{CHIP8X_BACKGROUNDS, CHIP8X_COLORS};
+pub use crate::compare::{CompareError, DiffReport};
+pub use crate::cost::{opcode_costs, CostBreakdown, CostEntry, CostUnit, OpcodeCost, VIP_CLOCK_HZ};
+pub use crate::debug_state::{
+    DebugState, CHANGED_DELAY_TIMER, CHANGED_I, CHANGED_PC, CHANGED_SOUND_TIMER,
+};
+pub use crate::debugger::{Breakpoints, StopReason, MAX_STEP_CYCLES};
+pub use crate::display::{Gfx, Pixel, PixelIter};
+pub use crate::events::{Event, EventKind, Events, MAX_EVENTS, VIP_STACK_DEPTH};
+pub use crate::keypad::{KeyEvent, KeyPolling, KeyRollover, KeyWait};
+pub use crate::latency::{LatencyStats, MAX_LATENCY_SAMPLES};
+pub use crate::listing::{ListingFormat, ListingOptions};
+pub use crate::loader::{LoadError, RomInfo, RomTooLarge};
+pub use crate::fault::{CrashDump, Executed, Fault, FaultKind, History, HISTORY_LEN};
+#[cfg(feature = "games")]
+pub use crate::games::{builtin_roms, BuiltinRom};
+pub use crate::heatmap::HEATMAP_SIZE;
+pub use crate::input_script::InputScriptError;
+pub use crate::instruction::{opcode_reference, Instruction, OpcodeInfo};
+pub use crate::io::{
+    AudioBackend, HostClock, KeyboardBackend, MemorySaveStorage, MockClock, NoKeyboard, SaveStorage, SilentAudio,
+    TimeSource,
+};
+pub use crate::ips::PatchError;
+pub use crate::machine_code::{MachineCall, MachineCallPolicy};
+pub use crate::macros::{Macros, MAX_MACRO_FRAMES};
+pub use crate::manifest::ManifestError;
+pub use crate::memory_init::MemoryInit;
+pub use crate::midi::DEFAULT_MIDI_BASE_NOTE;
+pub use crate::movie::{minimize_repro, render_movie, FrameStream, MovieError, MOVIE_VERSION};
+#[cfg(feature = "debug")]
+pub use crate::mutation::MutantResult;
+pub use crate::netplay::{InputBuffer, NetError, NetSession, MAX_PREDICTION_FRAMES};
+pub use crate::octo::{assemble_octo, AsmError, AsmErrorKind};
+pub use crate::overlay::DEFAULT_OVERLAY_COLOR;
+pub use crate::players::{InputMode, Player, Players};
+pub use crate::preview::PreviewRunner;
+pub use crate::profile::PerfCounters;
+pub use crate::quirks::Quirks;
+pub use crate::render::{
+    Compositing, DisplayStyle, Renderer, DEFAULT_BACKGROUND, DEFAULT_FOREGROUND, DEFAULT_SCALE,
+};
+pub use crate::reset::ResetOptions;
+pub use crate::run::StopHandle;
+pub use crate::savestate::{Snapshot, StateError, STATE_VERSION};
+#[cfg(feature = "share")]
+pub use crate::share::{shared_rom_hash, ShareError, SHARE_VERSION};
+pub use crate::scheduler::{CatchUpPolicy, LoopStep, Schedule, Scheduler, MAX_CATCH_UP_FRAMES};
+pub use crate::sprites::{Sprite, SpriteSource};
+pub use crate::state_json::STATE_SCHEMA;
+pub use crate::stats::{rom_stats, RomStats};
+pub use crate::step_info::{LastInstruction, MemoryWrite, Operand, StepInfo};
+pub use crate::trace::MAX_TRACE_ROWS;
+pub use crate::slots::{SlotInfo, SLOT_COUNT, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH};
+pub use crate::variant::Variant;
+#[cfg(all(feature = "web", target_arch = "wasm32"))]
+pub use crate::web::fetch_rom;
+
+use crate::heatmap::{Access, Heatmap};
+use crate::keypad::Keypad;
+use crate::profile::{Phase, Profiler};
+use crate::slots::Slot;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -9,37 +157,15 @@ use rand::Rng;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-#[wasm_bindgen(raw_module = "../../js/io-interfaces/audio.js")]
-extern "C" {
-    type Audio;
-
-    #[wasm_bindgen(constructor)]
-    fn new() -> Audio;
-
-    #[wasm_bindgen(method)]
-    fn start(this: &Audio);
-
-    #[wasm_bindgen(method)]
-    fn stop(this: &Audio);
-
-    #[wasm_bindgen(method)]
-    fn is_active(this: &Audio) -> bool;
-}
-
-#[wasm_bindgen(raw_module = "../../js/io-interfaces/keyboard.js")]
-extern "C" {
-    type Keyboard;
-
-    #[wasm_bindgen(constructor)]
-    fn new() -> Keyboard;
-
-    #[wasm_bindgen(method)]
-    fn is_key_pressed(this: &Keyboard, key: u8) -> bool;
-}
-
 pub const CHIP8_DISPLAY_WIDTH: usize = 64;
 pub const CHIP8_DISPLAY_HEIGHT: usize = 32;
 
+// Bitmask of every display plane, XO-CHIP has two.
+const PLANES: u8 = 0b11;
+
+/// Nested subroutine calls the stack holds, one more faults with `FaultKind::StackOverflow`.
+pub const STACK_DEPTH: usize = 16;
+
 const CHIP8_FONTSET: [u8; 80] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
     0x20, 0x60, 0x20, 0x20, 0x70, // 1
@@ -59,6 +185,26 @@ const CHIP8_FONTSET: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
+// CHIPOS draws its digits three pixels wide.
+const DREAM6800_FONTSET: [u8; 80] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x40, 0x40, 0x40, 0x40, 0x40, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0x80, 0xA0, 0xA0, 0xE0, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
 #[wasm_bindgen]
 /// Representation of the CHIP8 emulator.
 pub struct Emulator {
@@ -67,73 +213,419 @@ pub struct Emulator {
     opcode: u16,
     // I stands for index register, that usually has a pointer to the memory.
     i: usize,
-    // CHIP-8 has 4096 bytes of memory. Program is loaded to the 0x200 address. Lower addresses are
-    // used to store font used by the CHIP-8 interpreter.
-    memory: [u8; 4096],
+    // CHIP-8 has 4096 bytes of memory (XO-CHIP has 65536, the 2K VIP 2048). Program is loaded to the 0x200
+    // address (0x300 on CHIP-8X).
+    // Lower addresses are used to store font used by the CHIP-8 interpreter.
+    memory: Vec<u8>,
+    // What `reset` fills memory with.
+    memory_init: MemoryInit,
     // 16 one byte long registers. V0 to VE are used to store some data and VF is used to store
     // carry flag.
     v: [u8; 16],
-    // Stack to save current PC when jumping to another subroutine.
+    // Stack to save current PC when jumping to another subroutine, at most `STACK_DEPTH` deep.
     stack: Vec<usize>,
     // Program counter points to the current opcode position in memory.
     pc: usize,
-    // CHIP-8 display nested array.
-    gfx: [[u8; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT],
+    // CHIP-8 display nested array. Every cell is a bitmask of the lit planes.
+    gfx: Gfx,
+    // Bitmask of the planes drawing, clearing and scrolling affect, set by FN01 on XO-CHIP.
+    planes: u8,
+    // The display changed during the current frame.
     draw_flag: bool,
     delay_timer: u8,
     sound_timer: u8,
-    audio: Audio,
-    keyboard: Keyboard,
+    audio: Box<dyn AudioBackend>,
+    keyboard: Box<dyn KeyboardBackend>,
+    // Clock host time is measured with, by `advance` and auto-speed.
+    time: Box<dyn TimeSource>,
+    // Reading of `time` at the previous `advance` call.
+    last_advance_ms: Option<f64>,
+    // Keys set directly by the host. They are combined with the ones reported by `keyboard` and
+    // latched at the start of every frame.
+    keypad: Keypad,
+    variant: Variant,
+    quirks: Quirks,
+    // Rate the timers count down at instead of the one of the variant, see `set_timer_hz`.
+    timer_hz: Option<u8>,
+    // Index of the frame that runs next and how many instructions are executed per frame.
+    frame: u64,
+    cycles_per_frame: u32,
+    // Instructions of the current frame executed so far.
+    frame_cycle: u32,
+    // Hash of the loaded program, 0 when nothing is loaded.
+    rom_hash: u32,
+    slots: Vec<Option<Slot>>,
+    // Frame-indexed local and remote input for netplay.
+    input: InputBuffer,
+    // Tallest sprite drawn from every address.
+    drawn_sprites: BTreeMap<u16, u8>,
+    // Sample generator for hosts that mix the beep themselves.
+    synth: Synth,
+    renderer: Renderer,
+    // Debug text and shapes drawn over the `render_rgba` image.
+    overlay: overlay::Overlay,
+    // Last executed instructions and the fault that stopped the program.
+    history: History,
+    fault: Option<Fault>,
+    // Savestate `reset` restores instead of the pristine machine.
+    boot_state: Option<Vec<u8>>,
+    breakpoints: Breakpoints,
+    // Events waiting for the host.
+    events: Events,
+    // Executed 0NNN instructions by address and how they are handled.
+    machine_calls: BTreeMap<u16, u16>,
+    machine_call_policy: MachineCallPolicy,
+    // Keys held by the players sharing the keypad and the keys the program polls.
+    players: Players,
+    // Validate every instruction instead of tolerating out of bounds accesses.
+    strict: bool,
+    // Switch from CHIP-8 to XO-CHIP when the program uses an XO-CHIP opcode.
+    auto_variant: bool,
+    // Pause when a subroutine calls itself, see `set_pause_on_recursion`.
+    pause_on_recursion: bool,
+    // Recorded input sequences and the one being recorded or played.
+    macros: Macros,
+    // Movie being recorded, see `start_movie`.
+    movie: Option<movie::MovieRecorder>,
+    // Instruction trace being recorded, see `start_trace`.
+    trace: Option<trace::Trace>,
+    // Instruction handler perturbed for mutation testing, see `set_mutation`.
+    #[cfg(feature = "debug")]
+    mutation: Option<mutation::Mutation>,
+    // Recent memory accesses for visualizers.
+    heatmap: Heatmap,
+    // Addresses instructions were executed from, for code analysis.
+    coverage: coverage::Coverage,
+    // Source of CXNN random numbers.
+    rng: utils::XorShift,
+    #[cfg(target_arch = "wasm32")]
+    frame_listeners: callbacks::FrameListeners,
+    // Set by `pause`, the beep is silenced and `tick_frame` does nothing.
+    paused: bool,
+    // Silences the beep while the sound timer keeps counting.
+    muted: bool,
+    // Whether EX9E/EXA1 see the frame snapshot or the live keypad.
+    key_polling: KeyPolling,
+    // Set by `request_stop` to interrupt `run_cycles`.
+    stop: StopHandle,
+    // CHIP-8X background, zone colors and second keypad.
+    color_board: chip8x::ColorBoard,
+    // Programs that can be switched to and the bank whose program runs.
+    banks: Vec<Option<banks::Bank>>,
+    current_bank: Option<u8>,
+    // End frames early while the program busy-waits on the delay timer.
+    skip_idle: bool,
+    // Stop running frames in `advance_ms` while the program can only be woken by input.
+    low_power: bool,
+    // Turns the time passed to `advance_ms` into frames.
+    clock: Scheduler,
+    // Registers of the previous `debug_state` call.
+    last_debug_state: Option<DebugState>,
+    // Lowers the cycles per frame when frames take too long to run.
+    auto_speed: auto_speed::AutoSpeed,
+    // Instructions executed since the last reset.
+    total_cycles: u64,
+    // VIP cost of the executed instructions.
+    costs: cost::CostTracker,
+    // Measures how long key changes take to reach the program.
+    latency: latency::LatencyProbe,
+    // Storage of the save window, see `set_save_storage`.
+    battery: battery::Battery,
+    // Last executed instruction and the registers before it, see `last_instruction`.
+    last_executed: Option<step_info::Executed>,
+    // Effects of the last executed instructions for `step_back`.
+    journal: journal::Journal,
+    // Measures the host time spent in the parts of the core.
+    profiler: Profiler,
+    // MIDI note `set_midi_note_state` maps to key 0.
+    midi_base_note: u8,
 }
 
 #[wasm_bindgen]
 impl Emulator {
     /// Create new Emulator.
+    ///
+    /// In the browser sound and keyboard go through the JS IO interfaces, everywhere else the
+    /// emulator is headless (see `Emulator::headless`).
+    #[cfg(target_arch = "wasm32")]
     pub fn new() -> Emulator {
-        Emulator {
-            pc: 0x200,
-            i: 0x200,
-            opcode: 0,
-            stack: vec![],
-            v: [0; 16],
-            delay_timer: 0,
-            sound_timer: 0,
-            gfx: [[0; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT],
-            draw_flag: false,
-            audio: Audio::new(),
-            keyboard: Keyboard::new(),
-            memory: Emulator::prepare_memory(),
+        Emulator::with_backends(Box::new(io::Audio::new()), Box::new(io::Keyboard::new()))
+    }
+
+    /// Create new Emulator.
+    ///
+    /// In the browser sound and keyboard go through the JS IO interfaces, everywhere else the
+    /// emulator is headless (see `Emulator::headless`).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new() -> Emulator { Emulator::headless() }
+
+    /// Create new Emulator that is not connected to any sound or keyboard device.
+    ///
+    /// Input is provided with `set_key`.
+    pub fn headless() -> Emulator {
+        Emulator::with_backends(Box::new(SilentAudio::default()), Box::new(NoKeyboard))
+    }
+
+    /// Set state of the hex keypad key.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A key index from 0x0 to 0xF, other values are ignored.
+    /// * `pressed` - Whether the key is held down.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::headless();
+    /// // SKP V0: skips the next instruction while key 0 is held down.
+    /// emulator.load(&[0xe0, 0x9e]).unwrap();
+    /// emulator.set_key(0x0, true);
+    /// emulator.tick_frame();
+    /// ```
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        if self.input.is_exclusive() {
+            return;
         }
+        self.keypad.set(key, pressed);
+        self.note_key_change(key, pressed);
     }
 
-    /// Resets emulator properties to their initial values.
+    /// Schedule a key state change for the start of the given frame.
+    ///
+    /// Events for frames that already ran are applied at the start of the next frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A key index from 0x0 to 0xF, other values are ignored.
+    /// * `down` - Whether the key goes down or up.
+    /// * `frame` - Index of the frame the change becomes visible in (see `frame`).
     ///
     /// # Example
     ///
     /// ```
-    /// use wasm_chip8::{Emulator, CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH};
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::headless();
+    /// // v3 := key, i := 0x300, save v3
+    /// emulator.load(&[0xf3, 0x0a, 0xa3, 0x00, 0xf3, 0x55]).unwrap();
+    /// emulator.inject_key_event(0x5, true, 1);
+    /// emulator.inject_key_event(0x5, false, 2);
+    /// for _ in 0..3 {
+    ///     emulator.tick_frame();
+    /// }
+    /// assert_eq!(emulator.memory()[0x303], 0x5);
+    /// ```
+    pub fn inject_key_event(&mut self, key: u8, down: bool, frame: u64) {
+        if self.input.is_exclusive() {
+            return;
+        }
+        self.keypad.inject(KeyEvent { key, down, frame });
+    }
+
+    /// Index of the frame that runs on the next `tick_frame` call.
+    pub fn frame(&self) -> u64 { self.frame }
+
+    /// Frames completed since the last reset, the same as `frame`. Savestates and snapshots keep it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
     /// let mut emulator = Emulator::new();
+    /// emulator.set_cycles_per_frame(10);
+    /// // loop: jump loop
+    /// emulator.load(&[0x12, 0x00]).unwrap();
+    /// emulator.tick_frame();
+    /// emulator.tick_frame();
     /// emulator.tick();
+    /// assert_eq!((emulator.total_frames(), emulator.total_cycles()), (2, 21));
     /// emulator.reset();
-    /// assert_eq!(emulator.gfx(), [[0; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT]);
+    /// assert_eq!((emulator.total_frames(), emulator.total_cycles()), (0, 0));
     /// ```
-    pub fn reset(&mut self) {
-        self.pc = 0x200;
-        self.i = 0x200;
-        self.opcode = 0;
-        self.stack = vec![];
-        self.v = [0; 16];
-        self.delay_timer = 0;
-        self.sound_timer = 0;
-        self.gfx = [[0; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT];
-        self.draw_flag = false;
-        self.memory = Emulator::prepare_memory();
+    pub fn total_frames(&self) -> u64 { self.frame }
+
+    /// Instructions executed since the last reset. An instruction that faults is not counted.
+    pub fn total_cycles(&self) -> u64 { self.total_cycles }
+
+    /// Set how many instructions `tick_frame` executes. With auto-speed it is the most it executes.
+    pub fn set_cycles_per_frame(&mut self, cycles: u32) {
+        self.cycles_per_frame = cycles;
+        self.auto_speed.set_target(cycles);
+    }
+
+    /// Run one frame, `frame_rate` of them make up a second.
+    ///
+    /// Latches the keypad, executes `cycles_per_frame` instructions and decrements the timers once.
+    /// If the frame was partially executed by the debugger, only the rest of it is run. Listeners
+    /// registered with `on_frame` are called at the end of the frame.
+    pub fn tick_frame(&mut self) {
+        if self.paused {
+            return;
+        }
+
+        if self.auto_speed.is_enabled() {
+            let start = self.time.now_ms();
+            self.run_frame();
+            self.adapt_speed(self.time.now_ms() - start);
+        } else {
+            self.run_frame();
+        }
+    }
+
+    fn run_frame(&mut self) {
+        if self.cycles_per_frame == 0 {
+            self.begin_frame();
+            self.end_frame();
+            return;
+        }
+
+        loop {
+            if self.skip_idle && self.is_waiting_on_delay() {
+                if self.frame_cycle == 0 {
+                    self.begin_frame();
+                }
+                self.end_frame();
+                break;
+            }
+
+            self.cycle();
+            if self.frame_cycle == 0 || self.paused || self.stop.take() {
+                break;
+            }
+        }
+    }
+
+    /// Stops the program and silences the beep until `resume`. `tick_frame` does nothing while the
+    /// emulator is paused, the debugger commands still work.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, EventKind};
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 60, sound := v0, loop
+    /// emulator.load(&[0x60, 0x3c, 0xf0, 0x18, 0x12, 0x04]).unwrap();
+    /// emulator.tick_frame();
+    /// emulator.pause();
+    /// emulator.tick_frame();
+    /// emulator.resume();
+    /// let events: Vec<EventKind> = emulator.take_events().into_iter().map(|event| event.kind).collect();
+    /// assert_eq!(events, [EventKind::SoundStart, EventKind::SoundStop, EventKind::SoundStart]);
+    /// assert_eq!(emulator.frame(), 1);
+    /// ```
+    pub fn pause(&mut self) {
+        if self.paused {
+            return;
+        }
+
+        self.paused = true;
+        self.sync_audio();
+    }
+
+    /// Continues after `pause`, restarting the beep if the sound timer was still counting.
+    pub fn resume(&mut self) {
+        if !self.paused {
+            return;
+        }
+
+        self.paused = false;
+        self.sync_audio();
+    }
+
+    pub fn is_paused(&self) -> bool { self.paused }
+
+    /// Pauses the emulator right after a 2NNN calls its own address, the moment a runaway
+    /// recursion starts, instead of letting it run into a `StackOverflow` fault. The
+    /// `Recursion` event is raised either way. The frame stops where it is and continues on
+    /// `resume`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, EventKind};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_pause_on_recursion(true);
+    /// // v0 := 1, loop: call loop
+    /// emulator.load(&[0x60, 0x01, 0x22, 0x02]).unwrap();
+    /// emulator.tick_frame();
+    /// assert!(emulator.is_paused());
+    /// assert_eq!((emulator.pc(), emulator.debug_state().stack_depth), (0x202, 1));
+    /// assert_eq!(emulator.take_events()[0].kind, EventKind::Recursion { address: 0x202 });
+    /// ```
+    pub fn set_pause_on_recursion(&mut self, enabled: bool) { self.pause_on_recursion = enabled; }
+
+    pub fn pauses_on_recursion(&self) -> bool { self.pause_on_recursion }
+
+    /// Resets emulator properties to their initial values, or to the boot state if one was set
+    /// with `set_boot_state`. Use `reset_with` to reset only parts of the machine.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.tick();
+    /// emulator.reset();
+    /// assert!(emulator.pixels().all(|pixel| !pixel.is_lit()));
+    /// ```
+    pub fn reset(&mut self) { self.reset_with(ResetOptions::full()); }
+
+    /// Returns the CHIP-8 dialect the emulator executes.
+    pub fn variant(&self) -> Variant { self.variant }
+
+    /// Switches the CHIP-8 dialect and resets the emulator, so the program has to be loaded again.
+    /// The boot state is dropped as it belongs to the previous dialect, and the quirks, the
+    /// `key_wait` and the timer rate are set to the ones the dialect is usually run with.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Variant};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_variant(Variant::XoChip);
+    /// // i := long 0xfff0, v0 := 255, bcd v0
+    /// emulator.load(&[0xf0, 0x00, 0xff, 0xf0, 0x60, 0xff, 0xf0, 0x33]).unwrap();
+    /// for _ in 0..3 {
+    ///     emulator.tick();
+    /// }
+    /// assert_eq!(&emulator.memory()[0xfff0..0xfff3], &[2, 5, 5]);
+    /// ```
+    pub fn set_variant(&mut self, variant: Variant) {
+        log::debug!("switching to {:?}", variant);
+        self.variant = variant;
+        self.quirks = Quirks::for_variant(variant);
+        self.timer_hz = None;
+        self.keypad.set_wait(KeyWait::for_variant(variant));
+        self.boot_state = None;
+        self.reset();
     }
 
     /// Return pointer to the gfx array of 64 u8 elements.
     pub fn gfx(&self) -> *const [u8; 64] { self.gfx.as_ptr() }
 
-    /// Loads program to the emulator's memory.
+    /// Width of the display in pixels.
+    pub fn display_width(&self) -> usize { CHIP8_DISPLAY_WIDTH }
+
+    /// Height of the display in pixels.
+    pub fn display_height(&self) -> usize { CHIP8_DISPLAY_HEIGHT }
+
+    /// Returns a copy of the display in row-major order, one byte per pixel.
+    ///
+    /// Bit N of every byte is set when plane N is lit at that position, so a frontend can render
+    /// any variant without knowing how the emulator stores its framebuffer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let emulator = Emulator::new();
+    /// let packed = emulator.pixels_packed();
+    /// assert_eq!(packed.len(), emulator.display_width() * emulator.display_height());
+    /// ```
+    pub fn pixels_packed(&self) -> Vec<u8> { self.pixels().map(|pixel| pixel.planes).collect() }
+
+    /// Loads program to the emulator's memory. A program that does not fit into memory after the
+    /// load address is rejected and memory is left untouched.
     ///
     /// # Arguments
     ///
@@ -142,125 +634,302 @@ impl Emulator {
     /// # Example
     ///
     /// ```
-    /// use wasm_chip8::Emulator;
+    /// use wasm_chip8::{Emulator, RomTooLarge};
     /// let mut emulator = Emulator::new();
-    /// emulator.load(&[0xff, 0xf0, 0xfe]);
+    /// emulator.load(&[0xff, 0xf0, 0xfe]).unwrap();
+    /// assert_eq!(emulator.load(&[0; 4000]), Err(RomTooLarge { size: 4000, max_size: 3584 }));
     /// ```
-    pub fn load(&mut self, program: &[u8]) {
-        for (i, &byte) in program.iter().enumerate() {
-            self.memory[i + 0x200] = byte;
+    pub fn load(&mut self, program: &[u8]) -> Result<(), RomTooLarge> {
+        let max_size = self.max_rom_size();
+        if program.len() > max_size {
+            return Err(RomTooLarge { size: program.len(), max_size });
         }
+
+        let start = self.variant.load_address();
+        self.memory[start..start + program.len()].copy_from_slice(program);
+        self.rom_hash = utils::fnv1a(program);
+        self.coverage.clear();
+        self.journal.clear();
+        self.restore_save();
+        Ok(())
     }
 
+    /// Largest program `load` accepts for the current variant, in bytes.
+    pub fn max_rom_size(&self) -> usize { self.variant.max_rom_size() }
+
+    /// Seeds the generator behind CXNN, so runs with the same input produce the same numbers.
+    pub fn set_random_seed(&mut self, seed: u32) { self.rng = utils::XorShift::new(seed); }
+
+    /// Returns the FNV-1a hash of the loaded program or 0 if nothing is loaded.
+    pub fn rom_hash(&self) -> u32 { self.rom_hash }
+
     /// Run one step ("tick") of the program.
     ///
-    /// Loads opcode from memory, processes it and sets pointer to the next opcode.
+    /// Loads opcode from memory, processes it and sets pointer to the next opcode. Timers and the
+    /// keypad snapshot are only updated by `tick_frame`. Does nothing once a fault stopped the program.
     pub fn tick(&mut self) {
+        if self.fault.is_some() {
+            return;
+        }
+
+        if self.journal.is_enabled() {
+            self.journal_before();
+        }
+        let pc = self.pc;
+        let traced = self.trace_before();
+        #[cfg(feature = "debug")]
+        let before = self.mutation_before();
+        match self.execute() {
+            Ok(()) => {
+                #[cfg(feature = "debug")]
+                if let Some(before) = before {
+                    self.mutate(before);
+                }
+                self.total_cycles += 1;
+                if let Some(traced) = traced {
+                    self.record_trace(pc, traced);
+                }
+                self.coverage.record_transfer(pc, self.pc);
+                if self.costs.is_enabled() {
+                    self.costs.record(Instruction::decode(self.opcode, self.variant), self.pc != pc + 2);
+                }
+            }
+            Err(kind) => {
+                log::warn!("{:?} at {:#05x} executing {:04X}", kind, self.pc, self.opcode);
+                self.fault = Some(Fault { kind, pc: self.pc as u16, opcode: self.opcode, history: self.history });
+            }
+        }
+    }
+
+    fn execute(&mut self) -> Result<(), FaultKind> {
+        if self.variant.mirrors_memory() {
+            self.pc = self.address(self.pc);
+        }
+        if self.pc + 1 >= self.memory.len() {
+            self.opcode = 0;
+            return Err(FaultKind::PcOutOfBounds);
+        }
+
+        let started = self.profile_start();
         self.opcode = self.get_opcode();
+        self.history.push(self.pc as u16, self.opcode);
+        self.touch(Access::Execute, self.pc);
+        self.touch(Access::Execute, self.pc + 1);
+        self.coverage.record(self.pc, self.memory.len());
+
+        let instruction = self.detect_variant(Instruction::decode(self.opcode, self.variant));
+        let decoded = self.profile(Phase::Decode, started);
+        if self.strict {
+            self.check_strict(instruction)?;
+        }
+
+        self.record_executed(instruction);
+        let result = self.run_instruction(instruction);
+        self.profile(Phase::of(instruction), decoded);
+        result
+    }
+
+    fn run_instruction(&mut self, instruction: Instruction) -> Result<(), FaultKind> {
+        match instruction {
+            Instruction::ClearScreen => self.clear_screen(),
+            Instruction::Return => return self.return_from_subroutine(),
+            Instruction::ScrollDown(n) => self.scroll(0, n as usize),
+            Instruction::ScrollRight => self.scroll(4, 0),
+            Instruction::ScrollLeft => self.scroll(-4, 0),
+            Instruction::MachineCall(nnn) => return self.machine_call(nnn),
+            Instruction::Unknown(opcode) => {
+                log::debug!("unknown opcode {:04X} at {:#05x}, skipped", opcode, self.pc);
+                self.next_opcode()
+            }
+            Instruction::Jump(nnn) => self.jump(nnn as usize),
+            Instruction::Call(nnn) => return self.call_subroutine(nnn as usize),
+            Instruction::SkipEqImm { x, nn } => self.skip_eq(self.v[x as usize], nn),
+            Instruction::SkipNeImm { x, nn } => self.skip_neq(self.v[x as usize], nn),
+            Instruction::SkipEq { x, y } => self.skip_eq(self.v[x as usize], self.v[y as usize]),
+            Instruction::SetImm { x, nn } => self.write_v(x as usize, nn),
+            Instruction::AddImm { x, nn } => self.add_to_v(x as usize, nn),
+            Instruction::Set { x, y } => self.write_v(x as usize, self.v[y as usize]),
+            Instruction::Or { x, y } => self.write_logic(x as usize, self.v[x as usize] | self.v[y as usize]),
+            Instruction::And { x, y } => self.write_logic(x as usize, self.v[x as usize] & self.v[y as usize]),
+            Instruction::Xor { x, y } => self.write_logic(x as usize, self.v[x as usize] ^ self.v[y as usize]),
+            Instruction::Add { x, y } => self.add_vx_vy(x as usize, y as usize),
+            Instruction::Sub { x, y } => self.sub_vx_vy(x as usize, y as usize),
+            Instruction::ShiftRight { x, y } => self.shift_vx_right(x as usize, y as usize),
+            Instruction::SubReverse { x, y } => self.sub_vy_vx(x as usize, y as usize),
+            Instruction::ShiftLeft { x, y } => self.shift_vx_left(x as usize, y as usize),
+            Instruction::SkipNe { x, y } => self.skip_neq(self.v[x as usize], self.v[y as usize]),
+            Instruction::SetI(nnn) => self.write_i(nnn as usize),
+            Instruction::JumpOffset(nnn) => self.jump_with_offset(nnn),
+            Instruction::Random { x, nn } => {
+                let random = self.rng.next_u8();
+                self.write_v(x as usize, nn & random)
+            }
+            Instruction::Draw { x, y, n } => self.draw_sprite(self.v[x as usize], self.v[y as usize], n),
+            Instruction::SkipKey { x } => self.skip_key_pressed(self.v[x as usize]),
+            Instruction::SkipNotKey { x } => self.skip_key_not_pressed(self.v[x as usize]),
+            Instruction::GetDelay { x } => self.write_v(x as usize, self.delay_timer),
+            Instruction::WaitKey { x } => self.wait_key(x as usize),
+            Instruction::SetDelay { x } => self.set_delay_timer(self.v[x as usize]),
+            Instruction::SetSound { x } => self.set_sound_timer(self.v[x as usize]),
+            Instruction::AddI { x } => self.write_i(self.i + usize::from(self.v[x as usize])),
+            Instruction::Font { x } => self.write_i(usize::from(self.v[x as usize]) * 5),
+            Instruction::Bcd { x } => self.set_bcd(self.v[x as usize]),
+            Instruction::Store { x } => self.store_v(x as usize),
+            Instruction::Load { x } => self.fill_v(x as usize),
+            Instruction::LongI => self.set_long_i(),
+            Instruction::SelectPlanes(planes) => {
+                self.planes = planes & PLANES;
+                self.next_opcode()
+            }
+            Instruction::CycleBackground => self.cycle_background(),
+            Instruction::AddOctal { x, y } => {
+                self.write_v(x as usize, chip8x::add_octal(self.v[x as usize], self.v[y as usize]))
+            }
+            Instruction::Color { x, y, n } => self.set_zone_color(x as usize, y as usize, n),
+            Instruction::SkipKey2 { x } => self.skip_key2(self.v[x as usize], true),
+            Instruction::SkipNotKey2 { x } => self.skip_key2(self.v[x as usize], false),
+        }
 
-        let firstnib = (self.opcode >> 12) as u8;
-        let nnn = self.opcode & 0x0fff;
-        let nn = (self.opcode & 0x00ff) as u8;
-        let n = (self.opcode & 0x000f) as u8;
-        let x = ((self.opcode & 0x0f00) >> 8) as usize;
-        let y = ((self.opcode & 0x00f0) >> 4) as usize;
-        let vx = self.v[x];
-        let vy = self.v[y];
-
-        match firstnib {
-            0x0 => match nn {
-                0xe0 => self.clear_screen(),
-                0xee => self.return_from_subroutine(),
-                _ => self.next_opcode(),
-            },
-            0x1 => self.jump(nnn as usize),
-            0x2 => self.call_subroutine(nnn as usize),
-            0x3 => self.skip_eq(vx, nn),
-            0x4 => self.skip_neq(vx, nn),
-            0x5 => self.skip_eq(vx, vy),
-            0x6 => self.set_v(x, nn),
-            0x7 => self.add_to_v(x, nn),
-            0x8 => match n {
-                0x0 => self.set_v(x, vy),
-                0x1 => self.set_v(x, vx | vy),
-                0x2 => self.set_v(x, vx & vy),
-                0x3 => self.set_v(x, vx ^ vy),
-                0x4 => self.add_vx_vy(x, y),
-                0x5 => self.sub_vx_vy(x, y),
-                0x6 => self.shift_vx_right(x),
-                0x7 => self.sub_vy_vx(x, y),
-                0xe => self.shift_vx_left(x),
-                _ => self.next_opcode(),
-            },
-            0x9 => self.skip_neq(vx, vy),
-            0xa => self.set_i(nnn as usize),
-            0xb => self.jump((nnn + u16::from(self.v[0])) as usize),
-            0xc => self.set_v(x, nn & rand::thread_rng().gen::<u8>()),
-            0xd => self.draw_sprite(vx, vy, n),
-            0xe => match nn {
-                0x9e => self.skip_key_pressed(vx),
-                0xa1 => self.skip_key_not_pressed(vx),
-                _ => self.next_opcode(),
-            },
-            0xf => match nn {
-                0x07 => self.set_v(x, self.delay_timer),
-                0x0a => self.wait_key(x),
-                0x15 => self.set_delay_timer(vx),
-                0x18 => self.set_sound_timer(vx),
-                0x1e => self.set_i(self.i + usize::from(vx)),
-                0x29 => self.set_i(usize::from(vx * 5)),
-                0x33 => self.set_bcd(vx),
-                0x55 => self.store_v(x),
-                0x65 => self.fill_v(x),
-                _ => self.next_opcode(),
-            },
-            _ => self.next_opcode(),
+        Ok(())
+    }
+
+    // Executes one instruction as part of the current frame, starting and ending frames as needed.
+    fn cycle(&mut self) {
+        if self.frame_cycle == 0 {
+            self.begin_frame();
+        }
+
+        self.tick();
+        self.frame_cycle += 1;
+
+        if self.frame_cycle >= self.cycles_per_frame.max(1) {
+            self.end_frame();
         }
+    }
+
+    fn begin_frame(&mut self) {
+        self.draw_flag = false;
+        let scheduled = if self.input.is_exclusive() {
+            self.input.mask(self.frame)
+        } else {
+            self.input.mask(self.frame) | self.macros.next_mask()
+        };
+        let live = self.live_keys();
+        self.keypad.latch(self.frame, scheduled, live);
+        self.macros.record(self.keypad.snapshot());
+        self.record_movie_frame();
+    }
+
+    fn end_frame(&mut self) {
+        self.update_timers();
+        self.profiler.end_frame();
+        self.heatmap.decay();
+        self.flush_save();
+        #[cfg(target_arch = "wasm32")]
+        self.notify_frame();
+        self.frame += 1;
+        self.frame_cycle = 0;
+    }
 
+    fn update_timers(&mut self) {
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
 
         if self.sound_timer > 0 {
-            if !self.audio.is_active() {
-                self.audio.start();
-            }
-
+            self.sync_audio();
             self.sound_timer -= 1;
-
             if self.sound_timer == 0 {
-                self.audio.stop()
+                self.sync_audio();
             }
         }
     }
 
-    fn prepare_memory() -> [u8; 4096] {
-        let mut memory = [0; 4096];
-        for (i, &byte) in CHIP8_FONTSET.iter().enumerate() {
+    fn prepare_memory(variant: Variant, init: MemoryInit) -> Vec<u8> {
+        let mut memory = vec![0; variant.memory_size()];
+        init.fill(&mut memory);
+        for (i, &byte) in variant.font().iter().enumerate() {
             memory[i] = byte
         }
         memory
     }
 
-    fn get_opcode(&self) -> u16 { (self.memory[self.pc] as u16) << 8 | (self.memory[self.pc + 1] as u16) }
+    fn get_opcode(&self) -> u16 {
+        let byte = |address: usize| self.memory.get(address).copied().unwrap_or(0) as u16;
+        byte(self.pc) << 8 | byte(self.pc + 1)
+    }
 
     fn next_opcode(&mut self) { self.pc += 2; }
 
-    fn skip_opcode(&mut self) { self.pc += 4; }
+    fn skip_opcode(&mut self) {
+        self.pc += 2;
+        // XO-CHIP skips over the whole four byte long `F000 NNNN` load.
+        if self.variant.has_long_addresses() && self.get_opcode() == 0xf000 {
+            self.pc += 2;
+        }
+        self.pc += 2;
+    }
+
+    // Maps an address to memory. Addresses wrap around at the end of memory, so they are 12-bit on
+    // CHIP-8, 16-bit on XO-CHIP and mirror the first 2K on the 2K VIP.
+    fn address(&self, address: usize) -> usize { address % self.memory.len() }
 
     fn clear_screen(&mut self) {
-        self.gfx = [[0; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT];
+        for planes in self.gfx.as_flattened_mut() {
+            *planes &= !self.planes;
+        }
+        self.draw_flag = true;
+        self.next_opcode();
+    }
+
+    // Moves the selected planes, pixels scrolled in are unlit. The other planes stay in place.
+    fn scroll(&mut self, dx: isize, dy: usize) {
+        let (dx, dy) = if self.quirks.lores_scroll_halved { (dx / 2, dy / 2) } else { (dx, dy) };
+        let mut gfx = self.gfx;
+        for planes in gfx.as_flattened_mut() {
+            *planes &= !self.planes;
+        }
+        for (y, row) in self.gfx.iter().enumerate().take(CHIP8_DISPLAY_HEIGHT.saturating_sub(dy)) {
+            for (x, &planes) in row.iter().enumerate() {
+                let target = x as isize + dx;
+                if (0..CHIP8_DISPLAY_WIDTH as isize).contains(&target) {
+                    gfx[y + dy][target as usize] |= planes & self.planes;
+                }
+            }
+        }
+        self.gfx = gfx;
         self.draw_flag = true;
         self.next_opcode();
     }
 
-    fn return_from_subroutine(&mut self) { self.pc = self.stack.pop().unwrap(); }
+    fn return_from_subroutine(&mut self) -> Result<(), FaultKind> {
+        self.pc = self.stack.pop().ok_or(FaultKind::StackUnderflow)?;
+        Ok(())
+    }
 
     fn jump(&mut self, address: usize) { self.pc = address; }
 
-    fn call_subroutine(&mut self, address: usize) {
+    fn jump_with_offset(&mut self, nnn: u16) {
+        let register = if self.quirks.jump_uses_vx { (nnn >> 8) as usize } else { 0 };
+        self.jump(nnn as usize + usize::from(self.v[register]));
+    }
+
+    fn call_subroutine(&mut self, address: usize) -> Result<(), FaultKind> {
+        if self.stack.len() >= STACK_DEPTH {
+            return Err(FaultKind::StackOverflow);
+        }
+        if address == self.pc {
+            self.warn(EventKind::Recursion { address: address as u16 });
+            if self.pause_on_recursion {
+                self.pause();
+            }
+        }
         self.stack.push(self.pc + 2);
+        if self.stack.len() > VIP_STACK_DEPTH {
+            self.warn(EventKind::DeepStack { depth: self.stack.len() as u8 });
+        }
         self.pc = address;
+        Ok(())
     }
 
     fn skip_if(&mut self, cond: bool) {
@@ -279,11 +948,20 @@ impl Emulator {
         self.skip_if(a != b);
     }
 
-    fn set_v(&mut self, x: usize, value: u8) {
+    fn write_v(&mut self, x: usize, value: u8) {
         self.v[x] = value;
         self.next_opcode();
     }
 
+    // Stores the result of 8XY1/8XY2/8XY3.
+    fn write_logic(&mut self, x: usize, value: u8) {
+        self.v[x] = value;
+        if self.quirks.vf_reset {
+            self.v[0xf] = 0;
+        }
+        self.next_opcode();
+    }
+
     fn add_to_v(&mut self, x: usize, value: u8) {
         self.v[x] = self.v[x].overflowing_add(value).0;
         self.next_opcode();
@@ -303,81 +981,178 @@ impl Emulator {
 
     fn sub_vy_vx(&mut self, x: usize, y: usize) {
         self.v[0xf] = if self.v[y] < self.v[x] { 0 } else { 1 };
-        self.v[x] = self.v[y] - self.v[x];
+        self.v[x] = self.v[y].wrapping_sub(self.v[x]);
         self.next_opcode();
     }
 
-    fn shift_vx_right(&mut self, x: usize) {
-        self.v[0xf] = self.v[x] & 0x0f;
-        self.v[x] >>= 1;
+    fn shift_source(&self, x: usize, y: usize) -> u8 {
+        if self.quirks.shift_uses_vy {
+            self.v[y]
+        } else {
+            self.v[x]
+        }
+    }
+
+    fn shift_vx_right(&mut self, x: usize, y: usize) {
+        let source = self.shift_source(x, y);
+        self.v[x] = source >> 1;
+        self.v[0xf] = source & 0x01;
         self.next_opcode();
     }
 
-    fn shift_vx_left(&mut self, x: usize) {
-        self.v[0xf] = self.v[x] & 0xf0;
-        self.v[x] <<= 1;
+    fn shift_vx_left(&mut self, x: usize, y: usize) {
+        let source = self.shift_source(x, y);
+        self.v[x] = source << 1;
+        self.v[0xf] = source >> 7;
         self.next_opcode();
     }
 
-    fn set_i(&mut self, value: usize) {
-        self.i = value;
+    fn write_i(&mut self, value: usize) {
+        self.move_i(value);
         self.next_opcode();
     }
 
+    fn move_i(&mut self, value: usize) {
+        if value >= self.memory.len() {
+            self.warn(EventKind::IOutOfRange { value: value as u32 });
+        }
+        self.i = self.address(value);
+    }
+
+    // Writes a byte of memory at an address that was already mapped with `address`.
+    fn write_memory(&mut self, address: usize, value: u8) {
+        if address < self.variant.font().len() {
+            self.warn(EventKind::FontWrite { address: address as u16 });
+        }
+        if self.journal.is_enabled() {
+            self.journal.write(address, self.memory[address]);
+        }
+        self.memory[address] = value;
+        self.battery.write(address);
+        self.touch(Access::Write, address);
+    }
+
+    fn set_long_i(&mut self) {
+        let (high, low) = (self.address(self.pc + 2), self.address(self.pc + 3));
+        self.touch(Access::Execute, high);
+        self.touch(Access::Execute, low);
+        self.i = (self.memory[high] as usize) << 8 | self.memory[low] as usize;
+        self.pc += 4;
+    }
+
     fn draw_sprite(&mut self, vx: u8, vy: u8, height: u8) {
-        let from = self.i;
-        let to = self.i + height as usize;
-        let sprite = &self.memory[from..to];
+        // DXY0 draws a 16x16 sprite of two bytes per row where the variant supports it.
+        let large = height == 0 && self.quirks.large_sprites;
+        let (width, height) = if large { (16, 16) } else { (8, height as usize) };
+        let bytes = width / 8;
+        if !large {
+            self.record_sprite(self.i, height as u8);
+        }
+
+        // Every selected plane gets its own sprite, stored one after the other from I.
+        let planes: Vec<u8> = (0..8).map(|plane| 1 << plane).filter(|&plane| self.planes & plane != 0).collect();
+        let size = height * bytes;
+        let byte = |offset: usize| u16::from(self.memory[self.address(self.i + offset)]);
+        let sprites: Vec<Vec<u16>> = (0..planes.len())
+            .map(|index| {
+                let start = index * size;
+                (0..height).map(|row| (0..bytes).fold(0, |bits, at| bits << 8 | byte(start + row * bytes + at))).collect()
+            })
+            .collect();
+        for offset in 0..size * planes.len() {
+            self.touch(Access::Read, self.address(self.i + offset));
+        }
 
-        let mut flipped: u8 = 0;
+        // Rows that collided in any plane, a bit per row.
+        let mut collided: u32 = 0;
 
-        for y in 0..sprite.len() {
-            for x in 0..8 {
-                if sprite[y] & (0x80 >> x) != 0 {
-                    let mut y = (vy + (y) as u8) as usize;
-                    let mut x = (vx + x) as usize;
+        let left = vx as usize % CHIP8_DISPLAY_WIDTH;
+        let top = vy as usize % CHIP8_DISPLAY_HEIGHT;
+        let wrap = self.quirks.wrap_sprites;
 
-                    if y >= 32 {
-                        y = 31;
+        for (&plane, sprite) in planes.iter().zip(&sprites) {
+            for (row, bits) in sprite.iter().enumerate() {
+                let y = top + row;
+                if y >= CHIP8_DISPLAY_HEIGHT && !wrap {
+                    // SCHIP 1.1 counts the rows cut off at the bottom edge as collided.
+                    if self.quirks.collision_counts_rows {
+                        collided |= (!0 << row) & !(!0 << sprite.len());
                     }
+                    break;
+                }
+                let y = y % CHIP8_DISPLAY_HEIGHT;
 
-                    if x >= 64 {
-                        x = 63;
+                for bit in 0..width {
+                    if bits & (1 << (width - 1 - bit)) == 0 {
+                        continue;
                     }
 
-                    if self.gfx[y][x] == 1 {
-                        flipped = 1;
+                    let x = left + bit;
+                    if x >= CHIP8_DISPLAY_WIDTH && !wrap {
+                        break;
                     }
+                    let x = x % CHIP8_DISPLAY_WIDTH;
 
-                    self.gfx[y][x] ^= 1;
+                    if self.gfx[y][x] & plane != 0 {
+                        collided |= 1 << row;
+                    }
+
+                    self.gfx[y][x] ^= plane;
+                    if self.journal.is_enabled() {
+                        self.journal.flip(x, y, plane);
+                    }
                 }
             }
         }
 
-        self.v[0xf] = flipped;
+        let rows = collided.count_ones() as u8;
+        self.v[0xf] = if self.quirks.collision_counts_rows { rows } else { rows.min(1) };
         self.draw_flag = true;
         self.next_opcode();
     }
 
     fn is_key_pressed(&self, key: u8) -> bool {
-        self.keyboard.is_key_pressed(key)
+        match self.key_polling {
+            KeyPolling::Frame => self.keypad.is_pressed(key),
+            KeyPolling::Instruction => key <= 0xf && self.keypad.live_state(self.live_keys()) & (1 << key) != 0,
+        }
+    }
+
+    // Keys reported by sources that can change in the middle of a frame, none during netplay.
+    fn live_keys(&self) -> u16 {
+        if self.input.is_exclusive() {
+            return 0;
+        }
+
+        let mut keys = self.players.mask();
+        for key in 0..16 {
+            if self.keyboard.is_key_pressed(key) {
+                keys |= 1 << key;
+            }
+        }
+        keys
     }
 
     fn skip_key_pressed(&mut self, key: u8) {
-        self.skip_if(self.is_key_pressed(key));
+        self.players.record_poll(key);
+        let pressed = self.is_key_pressed(key);
+        self.note_key_observed(key, Some(pressed));
+        self.skip_if(pressed);
     }
 
     fn skip_key_not_pressed(&mut self, key: u8) {
-        self.skip_if(!self.is_key_pressed(key));
+        self.players.record_poll(key);
+        let pressed = self.is_key_pressed(key);
+        self.note_key_observed(key, Some(pressed));
+        self.skip_if(!pressed);
     }
 
     fn wait_key(&mut self, x: usize) {
-        for i in 0..16 {
-            if self.is_key_pressed(i) {
-                self.v[x] = i;
-                self.next_opcode();
-                break;
-            }
+        self.players.record_wait();
+        if let Some(key) = self.keypad.take_wait_key() {
+            self.note_key_observed(key, None);
+            self.v[x] = key;
+            self.next_opcode();
         }
     }
 
@@ -392,23 +1167,195 @@ impl Emulator {
     }
 
     fn set_bcd(&mut self, vx: u8) {
-        self.memory[self.i] = vx / 100;
-        self.memory[self.i + 1] = (vx / 10) % 10;
-        self.memory[self.i + 2] = (vx % 100) % 10;
+        let (hundreds, tens, ones) = (self.address(self.i), self.address(self.i + 1), self.address(self.i + 2));
+        self.write_memory(hundreds, vx / 100);
+        self.write_memory(tens, (vx / 10) % 10);
+        self.write_memory(ones, (vx % 100) % 10);
         self.next_opcode();
     }
 
     fn store_v(&mut self, x: usize) {
         for i in 0..=x {
-            self.memory[self.i + i] = self.v[i];
+            let address = self.address(self.i + i);
+            self.write_memory(address, self.v[i]);
+        }
+        if self.quirks.load_store_increments_i {
+            self.move_i(self.i + x + 1);
         }
         self.next_opcode();
     }
 
     fn fill_v(&mut self, x: usize) {
         for i in 0..=x {
-            self.v[i] = self.memory[self.i + i];
+            let address = self.address(self.i + i);
+            self.v[i] = self.memory[address];
+            self.touch(Access::Read, address);
+        }
+        if self.quirks.load_store_increments_i {
+            self.move_i(self.i + x + 1);
         }
         self.next_opcode();
     }
 }
+
+impl Emulator {
+    /// Create new Emulator that uses custom sound and keyboard devices.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio` - Sound output switched on and off by the sound timer.
+    /// * `keyboard` - Keypad state source polled by the key opcodes.
+    pub fn with_backends(audio: Box<dyn AudioBackend>, keyboard: Box<dyn KeyboardBackend>) -> Emulator {
+        utils::set_panic_hook();
+
+        Emulator {
+            pc: Variant::default().load_address(),
+            i: Variant::default().load_address(),
+            opcode: 0,
+            stack: vec![],
+            v: [0; 16],
+            delay_timer: 0,
+            sound_timer: 0,
+            gfx: [[0; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT],
+            planes: 1,
+            draw_flag: false,
+            audio,
+            keyboard,
+            time: Box::new(HostClock),
+            last_advance_ms: None,
+            keypad: Keypad::default(),
+            memory: Emulator::prepare_memory(Variant::default(), MemoryInit::default()),
+            memory_init: MemoryInit::default(),
+            variant: Variant::default(),
+            quirks: Quirks::default(),
+            frame: 0,
+            cycles_per_frame: 10,
+            timer_hz: None,
+            frame_cycle: 0,
+            rom_hash: 0,
+            slots: vec![],
+            input: InputBuffer::default(),
+            drawn_sprites: BTreeMap::new(),
+            synth: Synth::default(),
+            renderer: Renderer::default(),
+            overlay: overlay::Overlay::default(),
+            history: History::default(),
+            fault: None,
+            boot_state: None,
+            breakpoints: Breakpoints::default(),
+            events: Events::default(),
+            machine_calls: BTreeMap::new(),
+            machine_call_policy: MachineCallPolicy::default(),
+            players: Players::default(),
+            strict: false,
+            auto_variant: false,
+            pause_on_recursion: false,
+            macros: Macros::default(),
+            movie: None,
+            trace: None,
+            #[cfg(feature = "debug")]
+            mutation: None,
+            heatmap: Heatmap::default(),
+            coverage: coverage::Coverage::default(),
+            rng: utils::XorShift::entropy(),
+            #[cfg(target_arch = "wasm32")]
+            frame_listeners: callbacks::FrameListeners::default(),
+            paused: false,
+            muted: false,
+            key_polling: KeyPolling::default(),
+            stop: StopHandle::default(),
+            color_board: chip8x::ColorBoard::default(),
+            banks: vec![],
+            current_bank: None,
+            skip_idle: false,
+            low_power: false,
+            clock: Scheduler::new(Variant::default().frame_rate(), 10, 0),
+            last_debug_state: None,
+            auto_speed: auto_speed::AutoSpeed::default(),
+            total_cycles: 0,
+            costs: cost::CostTracker::default(),
+            latency: latency::LatencyProbe::default(),
+            last_executed: None,
+            battery: battery::Battery::default(),
+            journal: journal::Journal::default(),
+            profiler: Profiler::default(),
+            midi_base_note: midi::DEFAULT_MIDI_BASE_NOTE,
+        }
+    }
+
+    /// Replaces the clock host time is measured with, e.g. with a `MockClock` in tests.
+    pub fn set_time_source(&mut self, time: Box<dyn TimeSource>) {
+        self.time = time;
+        self.last_advance_ms = None;
+    }
+
+    /// Returns the whole emulator memory.
+    ///
+    /// Memory is `Variant::memory_size` bytes long and every access through I wraps around at its
+    /// end: sprites, FX33, FX55 and FX65 starting near the last address continue at address 0, and
+    /// I itself is kept inside memory. Strict mode (see `set_strict`) faults instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 1, v1 := 2, i := 0xfff, save v1
+    /// emulator.load(&[0x60, 0x01, 0x61, 0x02, 0xaf, 0xff, 0xf1, 0x55]).unwrap();
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.memory()[0xfff], 1);
+    /// assert_eq!(emulator.memory()[0x000], 2);
+    /// ```
+    pub fn memory(&self) -> &[u8] { &self.memory }
+
+    /// Copies the live memory, including code the program modified and data it built at
+    /// runtime, for analysis in external tools. `range` is the first and the last address to
+    /// copy and is clipped to memory, `None` copies all of it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 7, i := 0x300, save v0
+    /// emulator.load(&[0x60, 0x07, 0xa3, 0x00, 0xf0, 0x55]).unwrap();
+    /// for _ in 0..3 {
+    ///     emulator.tick();
+    /// }
+    /// assert_eq!(emulator.dump_memory(Some((0x300, 0x301))), [7, 0]);
+    /// assert_eq!(emulator.dump_memory(None).len(), 4096);
+    /// ```
+    pub fn dump_memory(&self, range: Option<(u16, u16)>) -> Vec<u8> {
+        let (first, last) = range.map_or((0, self.memory.len()), |(first, last)| {
+            (usize::from(first), (usize::from(last) + 1).min(self.memory.len()))
+        });
+        self.memory.get(first..last).map_or_else(Vec::new, <[u8]>::to_vec)
+    }
+
+    /// Iterates over every display pixel in row-major order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let emulator = Emulator::new();
+    /// assert_eq!(emulator.pixels().filter(|pixel| pixel.is_lit()).count(), 0);
+    /// ```
+    pub fn pixels(&self) -> PixelIter<'_> { PixelIter::new(&self.gfx) }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `dump_memory` from `first` to `last`. `first` defaults to the start of memory and
+    /// `last` to its end.
+    #[wasm_bindgen(js_name = dump_memory)]
+    pub fn dump_memory_js(&self, first: Option<u16>, last: Option<u16>) -> Vec<u8> {
+        let last = last.map_or(self.memory.len() - 1, usize::from).min(usize::from(u16::MAX)) as u16;
+        self.dump_memory(Some((first.unwrap_or(0), last)))
+    }
+}
+
+impl Default for Emulator {
+    fn default() -> Emulator { Emulator::new() }
+}
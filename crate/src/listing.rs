@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use wasm_bindgen::prelude::*;
+
+use crate::analysis::{reference, Analysis};
+use crate::state_json::platform_name;
+use crate::{Emulator, Instruction};
+
+// Data bytes shown on one line at most.
+const DATA_PER_LINE: usize = 8;
+
+/// Output format of `export_listing`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ListingFormat {
+    /// Plain text with `;` comments.
+    #[default]
+    Text,
+    /// A `<pre>` block where labels and cross-references link to the lines they name.
+    Html,
+}
+
+/// Settings of `export_listing`.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct ListingOptions {
+    format: ListingFormat,
+    symbols: BTreeMap<usize, String>,
+}
+
+#[wasm_bindgen]
+impl ListingOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(format: ListingFormat) -> ListingOptions { ListingOptions { format, symbols: BTreeMap::new() } }
+
+    /// Names an address. The name replaces the generated label and labels the address even if
+    /// nothing refers to it.
+    pub fn set_symbol(&mut self, address: u16, name: &str) {
+        self.symbols.insert(usize::from(address), name.into());
+    }
+}
+
+// Writes the lines of a listing in one of the formats.
+struct Listing<'a> {
+    options: &'a ListingOptions,
+    analysis: Analysis,
+    out: String,
+}
+
+impl<'a> Listing<'a> {
+    fn html(&self) -> bool { self.options.format == ListingFormat::Html }
+
+    fn name(&self, address: usize) -> Option<String> {
+        self.options.symbols.get(&address).cloned().or_else(|| self.analysis.label_name(address))
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.html() {
+            self.out.push_str(&escape(text));
+        } else {
+            self.out.push_str(text);
+        }
+    }
+
+    // Text referring to `address`, a link to its line in HTML.
+    fn link(&mut self, address: usize, text: &str) {
+        if self.html() {
+            let _ = write!(self.out, r##"<a href="#x{:X}">{}</a>"##, address, escape(text));
+        } else {
+            self.out.push_str(text);
+        }
+    }
+
+    fn label(&mut self, address: usize) {
+        let name = match self.name(address) {
+            Some(name) => name,
+            None => return,
+        };
+        if self.html() {
+            let _ = write!(self.out, r#"<span class="label">{}:</span>"#, escape(&name));
+        } else {
+            let _ = write!(self.out, "{}:", name);
+        }
+
+        let xrefs = self.analysis.xrefs.get(&address).cloned().unwrap_or_default();
+        for (index, xref) in xrefs.into_iter().enumerate() {
+            self.text(if index == 0 { "  ; xrefs " } else { ", " });
+            self.link(xref, &format!("0x{:03X}", xref));
+        }
+        self.out.push('\n');
+    }
+
+    // Start of the line of `address`, the target of links in HTML.
+    fn line(&mut self, address: usize) {
+        if self.html() {
+            let _ = write!(self.out, r#"<span id="x{:X}"></span>"#, address);
+        }
+        let _ = write!(self.out, "0x{:03X}  ", address);
+    }
+
+    fn instruction(&mut self, address: usize, bytes: &[u8], instruction: Instruction) {
+        self.line(address);
+        let hex: String = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        let text = match instruction {
+            Instruction::LongI if bytes.len() == 4 => {
+                format!("{} I, 0x{:04X}", instruction.mnemonic(), u16::from(bytes[2]) << 8 | u16::from(bytes[3]))
+            }
+            _ => instruction.to_string(),
+        };
+        let target = reference(instruction).and_then(|(target, _)| Some((target, self.name(target)?)));
+        match target {
+            Some((target, name)) => {
+                self.text(&format!("{:<8}  {:<20}  ; ", hex, text));
+                self.link(target, &name);
+            }
+            None => self.text(&format!("{:<8}  {}", hex, text)),
+        }
+        self.out.push('\n');
+    }
+
+    fn data(&mut self, address: usize, bytes: &[u8]) {
+        self.line(address);
+        let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        if self.html() {
+            let _ = writeln!(self.out, r#"<span class="data">{}</span>"#, hex.join(" "));
+        } else {
+            let _ = writeln!(self.out, "{}", hex.join(" "));
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Disassembles the loaded program into an annotated listing for documenting games.
+    ///
+    /// Code is found by following every path from the load address and, when coverage is
+    /// enabled (see `set_coverage_enabled`), from every address the program executed, which also
+    /// finds code only reachable through BNNN. Everything else is listed as data. Targets of
+    /// jumps, calls and I loads get labels listing the instructions that refer to them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, ListingFormat, ListingOptions};
+    /// let mut emulator = Emulator::new();
+    /// // i := sprite, call draw, loop: jump loop, draw: sprite v0 v0 1, return, sprite: 0x80
+    /// emulator.load(&[0xa2, 0x0a, 0x22, 0x06, 0x12, 0x04, 0xd0, 0x01, 0x00, 0xee, 0x80]).unwrap();
+    /// let mut options = ListingOptions::new(ListingFormat::Text);
+    /// options.set_symbol(0x206, "draw");
+    /// let listing = emulator.export_listing(&options);
+    /// assert!(listing.contains("0x202  2206      CALL 0x206            ; draw"));
+    /// assert!(listing.contains("draw:  ; xrefs 0x202"));
+    /// assert!(listing.contains("data_20A:  ; xrefs 0x200\n0x20A  80 00"));
+    /// ```
+    pub fn export_listing(&self, options: &ListingOptions) -> String {
+        let analysis = self.analyze();
+        let (start, end) = (analysis.start, analysis.end);
+        let mut listing = Listing { options, analysis, out: String::new() };
+
+        if listing.html() {
+            listing.out.push_str(r#"<pre class="chip8-listing">"#);
+        }
+        listing.text(&format!("; {} program, 0x{:03X}-0x{:03X}\n", platform_name(self.variant), start, end));
+
+        let mut address = start;
+        while address < end {
+            listing.label(address);
+            if listing.analysis.is_code(address) {
+                let size = self.instruction_size_at(address).min(end - address);
+                let instruction = Instruction::decode(self.word_at(address), self.variant);
+                listing.instruction(address, &self.memory[address..address + size], instruction);
+                address += size;
+            } else {
+                let mut last = address + 1;
+                while last < end
+                    && last - address < DATA_PER_LINE
+                    && !listing.analysis.is_code(last)
+                    && listing.name(last).is_none()
+                {
+                    last += 1;
+                }
+                listing.data(address, &self.memory[address..last]);
+                address = last;
+            }
+        }
+
+        if listing.html() {
+            listing.out.push_str("</pre>\n");
+        }
+        listing.out
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,240 @@
+//! Loading programs from text and from files that say where the program goes, e.g. hex dumps
+//! pasted from forums and the Intel HEX files some development tools write.
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Reason a program could not be loaded. Memory is left untouched.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// A word of the text is not a byte value.
+    InvalidNumber,
+    /// A run of hex digits has an odd length, so it does not split into bytes.
+    OddDigits,
+    /// The program does not fit into memory after the load address of the current variant.
+    TooLarge,
+    /// An Intel HEX line is not a well-formed record.
+    InvalidRecord,
+    /// The checksum of an Intel HEX record does not match its contents.
+    BadChecksum,
+    /// The program is placed below the load address of the current variant.
+    OutOfRange,
+}
+
+/// Program `Emulator::load` rejected because it does not fit into memory after the load address.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RomTooLarge {
+    /// Size of the program in bytes.
+    pub size: usize,
+    /// Largest program the current variant holds, see `Emulator::max_rom_size`.
+    pub max_size: usize,
+}
+
+impl fmt::Display for RomTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "program is {} bytes, at most {} bytes fit into memory", self.size, self.max_size)
+    }
+}
+
+impl std::error::Error for RomTooLarge {}
+
+/// Program loaded from a format that is converted first, e.g. by `Emulator::load_octo_source`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RomInfo {
+    /// Size of the program in bytes.
+    pub size: usize,
+    /// Hash of the program, see `Emulator::rom_hash`.
+    pub rom_hash: u32,
+}
+
+// Octo directive that makes the following number a single byte.
+const BYTE_DIRECTIVE: &str = ":byte";
+
+/// Turns a hex dump into bytes.
+///
+/// Words are separated by whitespace or commas and `#` or `;` start a comment that runs to the
+/// end of the line. A word is a byte with a `0x` or `$` prefix or a run of hex digits that is
+/// split into bytes. After Octo's `:byte` the number is read like Octo reads it, decimal unless
+/// it has a `0x` or `0b` prefix.
+pub(crate) fn parse_hex(text: &str) -> Result<Vec<u8>, LoadError> {
+    let mut bytes = vec![];
+    for line in text.lines() {
+        let code = line.split(['#', ';']).next().unwrap_or("");
+        let mut octo_number = false;
+        for word in code.split(|c: char| c.is_whitespace() || c == ',').filter(|word| !word.is_empty()) {
+            if word == BYTE_DIRECTIVE {
+                octo_number = true;
+                continue;
+            }
+
+            if std::mem::take(&mut octo_number) {
+                bytes.push(parse_octo_number(word)?);
+            } else if let Some(digits) = word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+                bytes.push(parse_prefixed(digits)?);
+            } else if let Some(digits) = word.strip_prefix('$') {
+                bytes.push(parse_prefixed(digits)?);
+            } else {
+                parse_digits(word, &mut bytes)?;
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+fn parse_prefixed(digits: &str) -> Result<u8, LoadError> {
+    if digits.is_empty() || digits.len() > 2 {
+        return Err(LoadError::InvalidNumber);
+    }
+    u8::from_str_radix(digits, 16).map_err(|_| LoadError::InvalidNumber)
+}
+
+fn parse_digits(word: &str, bytes: &mut Vec<u8>) -> Result<(), LoadError> {
+    if !word.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(LoadError::InvalidNumber);
+    }
+    if word.len() & 1 != 0 {
+        return Err(LoadError::OddDigits);
+    }
+    for pair in word.as_bytes().chunks(2) {
+        let pair = std::str::from_utf8(pair).map_err(|_| LoadError::InvalidNumber)?;
+        bytes.push(u8::from_str_radix(pair, 16).map_err(|_| LoadError::InvalidNumber)?);
+    }
+    Ok(())
+}
+
+fn parse_octo_number(word: &str) -> Result<u8, LoadError> {
+    let value = if let Some(digits) = word.strip_prefix("0x") {
+        i32::from_str_radix(digits, 16)
+    } else if let Some(digits) = word.strip_prefix("0b") {
+        i32::from_str_radix(digits, 2)
+    } else {
+        word.parse()
+    };
+    // Octo accepts negative bytes and stores them in two's complement.
+    match value {
+        Ok(value) if (-128..=255).contains(&value) => Ok(value as u8),
+        _ => Err(LoadError::InvalidNumber),
+    }
+}
+
+/// Turns Intel HEX records into the bytes they place and the address of the first one.
+///
+/// Data, end of file and both kinds of extended address records are understood, start address
+/// records are ignored. Gaps between records are filled with zeros.
+pub(crate) fn parse_intel_hex(text: &str) -> Result<(usize, Vec<u8>), LoadError> {
+    let mut chunks = vec![];
+    let mut base = 0;
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let digits = line.strip_prefix(':').ok_or(LoadError::InvalidRecord)?;
+        let mut record = vec![];
+        parse_digits(digits, &mut record).map_err(|_| LoadError::InvalidRecord)?;
+        if record.len() < 5 || record.len() != usize::from(record[0]) + 5 {
+            return Err(LoadError::InvalidRecord);
+        }
+        if record.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte)) != 0 {
+            return Err(LoadError::BadChecksum);
+        }
+
+        let address = usize::from(record[1]) << 8 | usize::from(record[2]);
+        let data = &record[4..record.len() - 1];
+        let word = || data.iter().fold(0, |value, &byte| value << 8 | usize::from(byte));
+        match record[3] {
+            // Nothing past the 64K of XO-CHIP fits anywhere, so do not allocate it.
+            0x00 if base + address >= 0x10000 || base + address + data.len() > 0x10000 => {
+                return Err(LoadError::TooLarge);
+            }
+            0x00 => chunks.push((base + address, data.to_vec())),
+            0x01 => break,
+            0x02 if data.len() == 2 => base = word() << 4,
+            0x04 if data.len() == 2 => base = word() << 16,
+            0x03 | 0x05 => {}
+            _ => return Err(LoadError::InvalidRecord),
+        }
+    }
+
+    let start = chunks.iter().map(|(address, _)| *address).min().unwrap_or(0);
+    let end = chunks.iter().map(|(address, data)| address + data.len()).max().unwrap_or(0);
+    let mut image = vec![0; end - start];
+    for (address, data) in chunks {
+        image[address - start..address - start + data.len()].copy_from_slice(&data);
+    }
+    Ok((start, image))
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Loads a program that belongs at `address` instead of the load address, e.g. one saved
+    /// together with its load address. The memory between the load address and `address` is
+    /// cleared.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, LoadError};
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_at(&[0x60, 0x01], 0x202).unwrap();
+    /// assert_eq!(&emulator.memory()[0x200..0x204], &[0x00, 0x00, 0x60, 0x01]);
+    /// assert_eq!(emulator.load_at(&[0x60, 0x01], 0x100), Err(LoadError::OutOfRange));
+    /// assert_eq!(emulator.load_at(&[0x60, 0x01], usize::MAX), Err(LoadError::TooLarge));
+    /// ```
+    pub fn load_at(&mut self, program: &[u8], address: usize) -> Result<(), LoadError> {
+        let start = self.variant.load_address();
+        let offset = address.checked_sub(start).ok_or(LoadError::OutOfRange)?;
+        if offset.checked_add(program.len()).is_none_or(|size| size > self.max_rom_size()) {
+            return Err(LoadError::TooLarge);
+        }
+        let mut image = vec![0; offset];
+        image.extend_from_slice(program);
+        self.load(&image).map_err(|_| LoadError::TooLarge)
+    }
+
+    /// Loads a program from Intel HEX records. Record addresses are absolute memory addresses,
+    /// so the program has to start at or after the load address of the current variant, like
+    /// with `load_at`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, LoadError};
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 1 at 0x200
+    /// emulator.load_intel_hex(":0202000060019B\n:00000001FF").unwrap();
+    /// assert_eq!(&emulator.memory()[0x200..0x202], &[0x60, 0x01]);
+    /// assert_eq!(emulator.load_intel_hex(":02020000600100"), Err(LoadError::BadChecksum));
+    /// // v0 := 1 at 0x000
+    /// assert_eq!(emulator.load_intel_hex(":0200000060019D"), Err(LoadError::OutOfRange));
+    /// // Extended linear address 0xffff0000, far past the end of memory
+    /// assert_eq!(emulator.load_intel_hex(":02000004FFFFFC\n:0200000060019D"), Err(LoadError::TooLarge));
+    /// ```
+    pub fn load_intel_hex(&mut self, text: &str) -> Result<(), LoadError> {
+        let (address, image) = parse_intel_hex(text)?;
+        self.load_at(&image, address)
+    }
+
+    /// Loads a program written as text, so small programs can be pasted instead of saved to a
+    /// binary file first. Accepts hex dumps like `60 01 A2 0A`, `0x60, 0x01` or `6001A20A` and
+    /// lists of Octo `:byte` directives.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, LoadError};
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 42, v1 := 7
+    /// emulator.load_hex("0x60, 0x2a # a comment\n:byte 97 :byte 0x07").unwrap();
+    /// emulator.tick();
+    /// emulator.tick();
+    /// assert_eq!((emulator.v(0), emulator.v(1)), (42, 7));
+    /// assert_eq!(emulator.load_hex("60 0"), Err(LoadError::OddDigits));
+    /// ```
+    pub fn load_hex(&mut self, text: &str) -> Result<(), LoadError> {
+        let program = parse_hex(text)?;
+        self.load(&program).map_err(|_| LoadError::TooLarge)
+    }
+}
@@ -0,0 +1,85 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::events::EventKind;
+use crate::{Emulator, FaultKind};
+
+/// What happens when the program executes 0NNN, a call into machine code of the COSMAC VIP.
+///
+/// Most "hybrid" ROMs that rely on it cannot work without emulating the host CPU, the policy
+/// decides how loudly that is reported.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MachineCallPolicy {
+    /// Skip the instruction like the interpreter did before it was detected.
+    #[default]
+    Ignore,
+    /// Skip the instruction and raise a `machine_call` event the first time every address runs it.
+    Warn,
+    /// Stop the program with `FaultKind::MachineCall`.
+    Fault,
+}
+
+/// 0NNN instruction that was executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct MachineCall {
+    // Address of the instruction and the machine code routine it calls.
+    pub pc: u16,
+    pub address: u16,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    pub fn set_machine_call_policy(&mut self, policy: MachineCallPolicy) { self.machine_call_policy = policy; }
+
+    pub fn machine_call_policy(&self) -> MachineCallPolicy { self.machine_call_policy }
+
+    /// Returns `true` if the program executed a 0NNN instruction since the last reset.
+    pub fn has_machine_calls(&self) -> bool { !self.machine_calls.is_empty() }
+}
+
+impl Emulator {
+    /// 0NNN instructions executed since the last reset, ordered by their address.
+    ///
+    /// Calls are recorded whatever the policy is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, MachineCall, MachineCallPolicy};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_machine_call_policy(MachineCallPolicy::Warn);
+    /// // Call machine code at 0x3a0, then loop.
+    /// emulator.load(&[0x03, 0xa0, 0x12, 0x02]).unwrap();
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.machine_calls(), [MachineCall { pc: 0x200, address: 0x3a0 }]);
+    /// assert_eq!(emulator.take_events().len(), 1);
+    /// ```
+    pub fn machine_calls(&self) -> Vec<MachineCall> {
+        self.machine_calls.iter().map(|(&pc, &address)| MachineCall { pc, address }).collect()
+    }
+
+    pub(crate) fn machine_call(&mut self, address: u16) -> Result<(), FaultKind> {
+        let first = self.machine_calls.insert(self.pc as u16, address).is_none();
+        if first {
+            log::warn!("{:#05x} calls machine code at {:#05x}, which cannot be emulated", self.pc, address);
+        }
+        match self.machine_call_policy {
+            MachineCallPolicy::Ignore => {}
+            MachineCallPolicy::Warn if first => self.emit(EventKind::MachineCall { address }),
+            MachineCallPolicy::Warn => {}
+            MachineCallPolicy::Fault => return Err(FaultKind::MachineCall),
+        }
+
+        self.next_opcode();
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `machine_calls` as an array of `{ pc, address }` objects.
+    #[wasm_bindgen(js_name = machine_calls)]
+    pub fn machine_calls_js(&self) -> JsValue { crate::utils::to_js(&self.machine_calls()) }
+}
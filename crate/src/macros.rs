@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Longest macro that can be recorded, one minute at 60 frames per second.
+pub const MAX_MACRO_FRAMES: usize = 3600;
+
+/// Recorded input sequences, one keypad bitmask per frame.
+#[derive(Clone, Debug, Default)]
+pub struct Macros {
+    recorded: BTreeMap<u32, Vec<u16>>,
+    recording: Option<(u32, Vec<u16>)>,
+    // Macro being played back and the index of the frame it presses next.
+    playing: Option<(u32, usize)>,
+}
+
+impl Macros {
+    /// Keys the playing macro holds during the frame that starts, advancing the playback.
+    pub fn next_mask(&mut self) -> u16 {
+        let (id, position) = match self.playing {
+            Some(playing) => playing,
+            None => return 0,
+        };
+
+        match self.recorded.get(&id).and_then(|masks| masks.get(position)) {
+            Some(&mask) => {
+                self.playing = Some((id, position + 1));
+                mask
+            }
+            None => {
+                self.playing = None;
+                0
+            }
+        }
+    }
+
+    /// Recording stored under the given id.
+    #[cfg(feature = "share")]
+    pub(crate) fn get(&self, id: u32) -> Option<&[u16]> { self.recorded.get(&id).map(Vec::as_slice) }
+
+    /// Stores a recording made elsewhere under the given id.
+    pub(crate) fn insert(&mut self, id: u32, masks: Vec<u16>) { self.recorded.insert(id, masks); }
+
+    /// Appends the keypad snapshot of the frame that starts to the recording.
+    pub fn record(&mut self, mask: u16) {
+        if let Some((_, masks)) = &mut self.recording {
+            if masks.len() < MAX_MACRO_FRAMES {
+                masks.push(mask);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Starts recording the keypad, frame by frame, into the macro with the given id. The keys seen
+    /// by the program are recorded however they were pressed, until `stop_recording`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.record_macro(1);
+    /// emulator.set_key(0x5, true);
+    /// emulator.tick_frame();
+    /// emulator.set_key(0x5, false);
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.stop_recording(), 2);
+    ///
+    /// // v0 := 5, wait until key 5 is held, v1 := 1
+    /// emulator.reset();
+    /// emulator.load(&[0x60, 0x05, 0xe0, 0x9e, 0x12, 0x02, 0x61, 0x01, 0x12, 0x08]).unwrap();
+    /// assert!(emulator.play_macro(1));
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.crash_dump().v[1], 1);
+    /// ```
+    pub fn record_macro(&mut self, id: u32) { self.macros.recording = Some((id, vec![])); }
+
+    /// Stores the recording and returns how many frames it spans, 0 when nothing was recorded.
+    pub fn stop_recording(&mut self) -> usize {
+        match self.macros.recording.take() {
+            Some((id, masks)) => {
+                let frames = masks.len();
+                self.macros.recorded.insert(id, masks);
+                frames
+            }
+            None => 0,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool { self.macros.recording.is_some() }
+
+    /// Plays a macro back starting with the next frame, on top of the keys pressed by the host.
+    /// Returns `false` if no macro has the id.
+    pub fn play_macro(&mut self, id: u32) -> bool {
+        if !self.macros.recorded.contains_key(&id) {
+            return false;
+        }
+
+        self.macros.playing = Some((id, 0));
+        true
+    }
+
+    pub fn stop_macro(&mut self) { self.macros.playing = None; }
+
+    pub fn is_playing_macro(&self) -> bool { self.macros.playing.is_some() }
+
+    /// Number of frames of a recorded macro, 0 for unknown ids.
+    pub fn macro_frames(&self, id: u32) -> usize { self.macros.recorded.get(&id).map_or(0, Vec::len) }
+
+    /// Forgets a macro, stopping it if it is playing.
+    pub fn delete_macro(&mut self, id: u32) {
+        self.macros.recorded.remove(&id);
+        if self.macros.playing.is_some_and(|(playing, _)| playing == id) {
+            self.macros.playing = None;
+        }
+    }
+
+    /// Ids of the recorded macros in ascending order.
+    pub fn macro_ids(&self) -> Vec<u32> { self.macros.recorded.keys().copied().collect() }
+}
@@ -0,0 +1,148 @@
+//! ROM metadata in the format of the chip8Archive `programs.json` entries used by Octo.
+
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Quirks, Variant};
+
+/// Reason a ROM manifest could not be applied.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ManifestError {
+    /// The manifest is not valid JSON or a field has the wrong type.
+    InvalidJson,
+    /// The program targets a platform the emulator does not implement.
+    UnsupportedPlatform,
+    /// A color is not written as `#RRGGBB` or `#RGB`.
+    InvalidColor,
+    /// The program does not fit into the memory of its platform.
+    TooLarge,
+}
+
+/// Entry of `programs.json`. Fields the emulator has no use for (title, authors, ...) are ignored.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Manifest {
+    platform: Option<String>,
+    options: Options,
+}
+
+/// Octo's cartridge options. The quirk flags are named after how the behavior deviates from Octo.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Options {
+    tickrate: Option<u32>,
+    background_color: Option<String>,
+    fill_color: Option<String>,
+    fill_color2: Option<String>,
+    blend_color: Option<String>,
+    shift_quirks: Option<bool>,
+    load_store_quirks: Option<bool>,
+    jump_quirks: Option<bool>,
+    clip_quirks: Option<bool>,
+    logic_quirks: Option<bool>,
+}
+
+fn platform(name: &str) -> Result<Variant, ManifestError> {
+    match name {
+        "chip8" => Ok(Variant::Chip8),
+        "xochip" => Ok(Variant::XoChip),
+        _ => {
+            log::warn!("manifest platform {:?} is not supported", name);
+            Err(ManifestError::UnsupportedPlatform)
+        }
+    }
+}
+
+fn color(value: &str) -> Result<u32, ManifestError> {
+    let digits = value.strip_prefix('#').ok_or(ManifestError::InvalidColor)?;
+    let rgb = u32::from_str_radix(digits, 16).map_err(|_| ManifestError::InvalidColor)?;
+    match digits.len() {
+        6 => Ok(rgb),
+        // Every digit of the short form is doubled: #1af is #11aaff.
+        3 => Ok((0..3).map(|n| (((rgb >> (n * 4)) & 0xf) * 0x11) << (n * 8)).sum()),
+        _ => Err(ManifestError::InvalidColor),
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Loads a program and configures the emulator from its chip8Archive metadata.
+    ///
+    /// The manifest is a single entry of `programs.json`; a bare Octo `options` object is accepted
+    /// as well. The platform selects the variant, the quirk flags override its quirks, `tickrate`
+    /// sets the cycles per frame and the background, fill, fill2 and blend colors set the palette.
+    /// Nothing is changed when the manifest is rejected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Variant};
+    /// let mut emulator = Emulator::new();
+    /// let manifest = r##"{
+    ///     "title": "Example",
+    ///     "platform": "xochip",
+    ///     "options": { "tickrate": 200, "clipQuirks": true, "fillColor": "#FF6600" }
+    /// }"##;
+    /// emulator.load_with_manifest(&[0x12, 0x00], manifest).unwrap();
+    /// assert_eq!(emulator.variant(), Variant::XoChip);
+    /// assert!(!emulator.quirks().wrap_sprites);
+    /// ```
+    pub fn load_with_manifest(&mut self, rom: &[u8], manifest_json: &str) -> Result<(), ManifestError> {
+        let invalid = |_| ManifestError::InvalidJson;
+        let value: serde_json::Value = serde_json::from_str(manifest_json).map_err(invalid)?;
+        let manifest = if value.get("options").is_some() || value.get("platform").is_some() {
+            serde_json::from_value::<Manifest>(value)
+        } else {
+            serde_json::from_value::<Options>(value).map(|options| Manifest { platform: None, options })
+        }
+        .map_err(invalid)?;
+        let options = &manifest.options;
+
+        let variant = match &manifest.platform {
+            Some(name) => platform(name)?,
+            None => self.variant,
+        };
+        if rom.len() > variant.max_rom_size() {
+            return Err(ManifestError::TooLarge);
+        }
+
+        let colors = [&options.background_color, &options.fill_color, &options.fill_color2, &options.blend_color];
+        let mut palette = [None; 4];
+        for (entry, value) in palette.iter_mut().zip(colors.iter()) {
+            *entry = value.as_deref().map(color).transpose()?;
+        }
+
+        let mut quirks = Quirks::for_variant(variant);
+        if let Some(shift) = options.shift_quirks {
+            quirks.shift_uses_vy = !shift;
+        }
+        if let Some(load_store) = options.load_store_quirks {
+            quirks.load_store_increments_i = !load_store;
+        }
+        if let Some(jump) = options.jump_quirks {
+            quirks.jump_uses_vx = jump;
+        }
+        if let Some(clip) = options.clip_quirks {
+            quirks.wrap_sprites = !clip;
+        }
+        if let Some(logic) = options.logic_quirks {
+            quirks.vf_reset = logic;
+        }
+
+        self.set_variant(variant);
+        self.set_quirks(quirks);
+        if let Some(tickrate) = options.tickrate {
+            self.set_cycles_per_frame(tickrate);
+        }
+        for (planes, color) in palette.iter().enumerate() {
+            if let Some(color) = color {
+                self.renderer.set_color(planes, *color);
+            }
+        }
+
+        // The size was checked against the variant of the manifest above.
+        let _ = self.load(rom);
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::utils::XorShift;
+use crate::Emulator;
+
+/// What memory outside the font holds after a reset.
+///
+/// Interpreters left RAM in different states and some buggy programs read memory they never
+/// wrote, so they only work with the contents they were developed with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MemoryInit {
+    /// Every byte is 0x00.
+    #[default]
+    Zero,
+    /// Every byte is 0xFF.
+    Ones,
+    /// Pseudo-random bytes, the same for every reset with the same seed.
+    Random(u32),
+}
+
+impl MemoryInit {
+    /// Fills the memory with the pattern.
+    pub(crate) fn fill(self, memory: &mut [u8]) {
+        match self {
+            MemoryInit::Zero => memory.fill(0x00),
+            MemoryInit::Ones => memory.fill(0xff),
+            MemoryInit::Random(seed) => {
+                let mut rng = XorShift::new(seed);
+                memory.iter_mut().for_each(|byte| *byte = rng.next_u8());
+            }
+        }
+    }
+}
+
+impl Emulator {
+    /// Chooses what memory holds after the next reset, the font being written over it. Loading a
+    /// program does not reset, so call `reset` before `load` for the pattern to apply.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, MemoryInit};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_memory_init(MemoryInit::Ones);
+    /// emulator.reset();
+    /// emulator.load(&[0x12, 0x00]).unwrap();
+    /// assert_eq!(emulator.memory()[0x202..0x204], [0xff, 0xff]);
+    ///
+    /// emulator.set_memory_init(MemoryInit::Random(7));
+    /// emulator.reset();
+    /// let contents = emulator.memory().to_vec();
+    /// emulator.reset();
+    /// assert_eq!(emulator.memory(), &contents[..]);
+    /// ```
+    pub fn set_memory_init(&mut self, init: MemoryInit) { self.memory_init = init; }
+
+    pub fn memory_init(&self) -> MemoryInit { self.memory_init }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Sets `set_memory_init` by name: `"zero"`, `"ones"` or `"random"` with the given seed.
+    /// Returns `false` for other names.
+    #[wasm_bindgen(js_name = set_memory_init)]
+    pub fn set_memory_init_js(&mut self, init: &str, seed: u32) -> bool {
+        let init = match init {
+            "zero" => MemoryInit::Zero,
+            "ones" => MemoryInit::Ones,
+            "random" => MemoryInit::Random(seed),
+            _ => return false,
+        };
+        self.set_memory_init(init);
+        true
+    }
+}
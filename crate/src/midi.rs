@@ -0,0 +1,36 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// MIDI note mapped to key 0 unless `set_midi_base_note` changes it, middle C.
+pub const DEFAULT_MIDI_BASE_NOTE: u8 = 60;
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Presses or releases the key a MIDI note maps to, so keyboard controllers can play the
+    /// keypad. The 16 notes from the base note up map to keys 0x0 to 0xF, other notes are
+    /// ignored. A note on with velocity 0 is a note off and should be passed with `on` false.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::headless();
+    /// // v0 := 5, if key v0 then skip, v1 := 1
+    /// emulator.load(&[0x60, 0x05, 0xe0, 0x9e, 0x61, 0x01]).unwrap();
+    /// // F above middle C is key 5.
+    /// emulator.set_midi_note_state(65, true);
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.v(1), 0);
+    /// ```
+    pub fn set_midi_note_state(&mut self, note: u8, on: bool) {
+        if let Some(key) = note.checked_sub(self.midi_base_note).filter(|&key| key <= 0xf) {
+            self.set_key(key, on);
+        }
+    }
+
+    /// Moves the 16 notes mapped to the keypad so they start at `note`.
+    pub fn set_midi_base_note(&mut self, note: u8) { self.midi_base_note = note; }
+
+    pub fn midi_base_note(&self) -> u8 { self.midi_base_note }
+}
@@ -0,0 +1,295 @@
+//! Recordings of a run that can be re-executed offline, e.g. to encode gameplay videos.
+//!
+//! A movie holds the state the recording started from and the keypad of every frame after it.
+//! Its format is `C8MV` followed by `MOVIE_VERSION`, the random number generator state and the
+//! cycles per frame as little endian `u32`, the length of the savestate as `u32` and the
+//! savestate, then the number of frames as `u32` and the keypad bitmask of every frame as `u16`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::audio::DEFAULT_SAMPLE_RATE;
+use crate::utils::XorShift;
+use crate::Emulator;
+
+const MAGIC: &[u8; 4] = b"C8MV";
+
+/// Version of the movie format written by `stop_movie`.
+pub const MOVIE_VERSION: u8 = 1;
+
+// Macro id the replaying emulator plays the keypad of the movie with.
+const MOVIE_MACRO: u32 = 0;
+
+/// Reason a movie could not be replayed.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovieError {
+    /// The data does not start with the movie signature.
+    BadMagic,
+    /// The movie was written by an unknown format version.
+    UnsupportedVersion,
+    /// The data ended before the movie was complete.
+    Truncated,
+    /// The savestate of the movie cannot be restored.
+    InvalidState,
+    /// The program does not fault while the movie plays.
+    NoFault,
+}
+
+/// Movie being recorded.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MovieRecorder {
+    // Header and savestate, written when the first frame starts.
+    start: Vec<u8>,
+    masks: Vec<u16>,
+}
+
+impl MovieRecorder {
+    fn encode(&self) -> Vec<u8> {
+        let mut movie = self.start.clone();
+        movie.extend_from_slice(&(self.masks.len() as u32).to_le_bytes());
+        movie.extend(self.masks.iter().flat_map(|mask| mask.to_le_bytes()));
+        movie
+    }
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], MovieError> {
+    if data.len() < len {
+        return Err(MovieError::Truncated);
+    }
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+fn take_u32(data: &mut &[u8]) -> Result<u32, MovieError> {
+    let bytes = take(data, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+// Emulator set up to replay a movie, the number of frames and the length of the movie header.
+fn replay(movie: &[u8]) -> Result<(Emulator, usize, usize), MovieError> {
+    let mut data = movie;
+    if take(&mut data, MAGIC.len())? != MAGIC {
+        return Err(MovieError::BadMagic);
+    }
+    if take(&mut data, 1)?[0] != MOVIE_VERSION {
+        return Err(MovieError::UnsupportedVersion);
+    }
+    let rng = take_u32(&mut data)?;
+    let cycles_per_frame = take_u32(&mut data)?;
+    let state_len = take_u32(&mut data)? as usize;
+    let state = take(&mut data, state_len)?;
+    let header = movie.len() - data.len();
+    let frames = take_u32(&mut data)? as usize;
+    let masks: Vec<u16> = take(&mut data, frames.checked_mul(2).ok_or(MovieError::Truncated)?)?
+        .chunks_exact(2)
+        .map(|mask| u16::from_le_bytes([mask[0], mask[1]]))
+        .collect();
+
+    let mut emulator = Emulator::headless();
+    emulator.load_state(state).map_err(|_| MovieError::InvalidState)?;
+    emulator.set_cycles_per_frame(cycles_per_frame);
+    emulator.rng = XorShift::new(rng);
+    emulator.macros.insert(MOVIE_MACRO, masks);
+    emulator.play_macro(MOVIE_MACRO);
+    Ok((emulator, frames, header))
+}
+
+/// Frames of a movie re-executed at a fixed output frame rate, independent of real time.
+///
+/// Output frame `n` shows the display at `n / fps` seconds into the movie and comes with the
+/// audio of the emulated frames that ran since the previous output frame, so the samples of all
+/// output frames add up to the whole soundtrack.
+#[wasm_bindgen]
+pub struct FrameStream {
+    emulator: Emulator,
+    fps: u32,
+    frame_rate: u32,
+    sample_rate: u32,
+    // Emulated frames of the movie and how many of them ran.
+    frames: u64,
+    emulated: u64,
+    // Output frames returned so far.
+    index: u64,
+    audio: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl FrameStream {
+    /// Sets how many audio samples per second `audio` returns, 44100 by default.
+    pub fn set_sample_rate(&mut self, rate: u32) {
+        self.sample_rate = rate.max(1);
+        self.emulator.set_sample_rate(self.sample_rate as f32);
+    }
+
+    /// Advances to the next output frame. Returns `false` once the movie has ended.
+    pub fn next_frame(&mut self) -> bool {
+        let due = self.index * u64::from(self.frame_rate) / u64::from(self.fps);
+        if due > self.frames {
+            return false;
+        }
+
+        self.audio.clear();
+        while self.emulated < due {
+            let rate = u64::from(self.frame_rate);
+            let samples = |frame: u64| frame * u64::from(self.sample_rate) / rate;
+            let count = (samples(self.emulated + 1) - samples(self.emulated)) as usize;
+            self.emulator.tick_frame();
+            let start = self.audio.len();
+            self.audio.resize(start + count, 0.0);
+            self.emulator.fill_audio(&mut self.audio[start..]);
+            self.emulated += 1;
+        }
+        self.index += 1;
+        true
+    }
+
+    /// Number of the current output frame, counting from 0.
+    pub fn frame_index(&self) -> u64 { self.index.saturating_sub(1) }
+
+    /// Time of the current output frame in the movie.
+    pub fn timestamp_ms(&self) -> f64 { self.frame_index() as f64 * 1000.0 / f64::from(self.fps) }
+
+    /// Audio samples of the current output frame.
+    pub fn audio(&self) -> Vec<f32> { self.audio.clone() }
+
+    /// Display of the current output frame in the format of `Emulator::pixels_packed`.
+    pub fn pixels_packed(&self) -> Vec<u8> { self.emulator.pixels_packed() }
+
+    /// Renders the current output frame like `Emulator::render_rgba` does.
+    pub fn rgba(&mut self) -> Vec<u8> {
+        self.emulator.render_rgba();
+        self.emulator.rgba().to_vec()
+    }
+}
+
+impl FrameStream {
+    /// Emulator replaying the movie, e.g. to choose colors and display scale for `rgba`.
+    pub fn emulator(&mut self) -> &mut Emulator { &mut self.emulator }
+}
+
+/// Re-executes a movie recorded with `Emulator::start_movie` and returns its frames at `fps`
+/// frames per second.
+///
+/// # Example
+///
+/// ```
+/// use wasm_chip8::{render_movie, Emulator};
+/// let mut emulator = Emulator::new();
+/// // loop: v0 := key, buzzer := v0, jump loop
+/// emulator.load(&[0xf0, 0x0a, 0xf0, 0x18, 0x12, 0x00]).unwrap();
+/// emulator.start_movie();
+/// emulator.set_key(0x3, true);
+/// emulator.tick_frame();
+/// emulator.set_key(0x3, false);
+/// for _ in 0..59 {
+///     emulator.tick_frame();
+/// }
+/// let movie = emulator.stop_movie();
+///
+/// let mut stream = render_movie(&movie, 30).unwrap();
+/// let mut frames = 0;
+/// let mut samples = 0;
+/// while stream.next_frame() {
+///     frames += 1;
+///     samples += stream.audio().len();
+/// }
+/// assert_eq!((frames, samples), (31, 44_100));
+/// ```
+#[wasm_bindgen]
+pub fn render_movie(movie: &[u8], fps: u32) -> Result<FrameStream, MovieError> {
+    let (emulator, frames, _) = replay(movie)?;
+    let mut stream = FrameStream {
+        frame_rate: emulator.frame_rate(),
+        emulator,
+        fps: fps.max(1),
+        sample_rate: 0,
+        frames: frames as u64,
+        emulated: 0,
+        index: 0,
+        audio: vec![],
+    };
+    stream.set_sample_rate(DEFAULT_SAMPLE_RATE as u32);
+    Ok(stream)
+}
+
+/// Trims a movie to the frames up to the first fault of the program, for small repros to attach
+/// to bug reports. Input after the fault cannot affect it, so a single replay finds the shortest
+/// prefix that still faults.
+///
+/// # Example
+///
+/// ```
+/// use wasm_chip8::{minimize_repro, Emulator, MovieError};
+/// let mut emulator = Emulator::new();
+/// // loop: if v0 -key then jump loop, return without a call
+/// emulator.load(&[0xe0, 0x9e, 0x12, 0x00, 0x00, 0xee]).unwrap();
+/// emulator.start_movie();
+/// for frame in 0..100 {
+///     emulator.set_key(0x0, frame == 10);
+///     emulator.tick_frame();
+/// }
+/// let movie = emulator.stop_movie();
+///
+/// let repro = minimize_repro(&movie).unwrap();
+/// assert_eq!(movie.len() - repro.len(), (100 - 11) * 2);
+/// assert_eq!(minimize_repro(&repro), Ok(repro));
+/// ```
+#[wasm_bindgen]
+pub fn minimize_repro(movie: &[u8]) -> Result<Vec<u8>, MovieError> {
+    let (mut emulator, frames, header) = replay(movie)?;
+    for frame in 0..frames {
+        emulator.tick_frame();
+        if emulator.fault.is_some() {
+            let mut repro = movie[..header].to_vec();
+            repro.extend_from_slice(&(frame as u32 + 1).to_le_bytes());
+            repro.extend_from_slice(&movie[header + 4..header + 4 + (frame + 1) * 2]);
+            return Ok(repro);
+        }
+    }
+    Err(MovieError::NoFault)
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Starts recording a movie for `render_movie` with the next frame. Keys held when the
+    /// recording starts are seen as pressed in its first frame.
+    pub fn start_movie(&mut self) { self.movie = Some(MovieRecorder::default()); }
+
+    /// Stops recording and returns the movie, empty if none was recorded.
+    pub fn stop_movie(&mut self) -> Vec<u8> {
+        match self.movie.take() {
+            Some(movie) if !movie.start.is_empty() => movie.encode(),
+            _ => vec![],
+        }
+    }
+
+    pub fn is_recording_movie(&self) -> bool { self.movie.is_some() }
+}
+
+impl Emulator {
+    /// Adds the keypad snapshot of the frame that starts to the movie being recorded.
+    pub(crate) fn record_movie_frame(&mut self) {
+        let starts = self.movie.as_ref().is_some_and(|movie| movie.start.is_empty());
+        let start = if starts { Some(self.movie_start()) } else { None };
+        let mask = self.keypad.snapshot();
+        if let Some(movie) = &mut self.movie {
+            if let Some(start) = start {
+                movie.start = start;
+            }
+            movie.masks.push(mask);
+        }
+    }
+
+    // Header and savestate of a movie starting now.
+    fn movie_start(&self) -> Vec<u8> {
+        let state = self.save_state();
+        let mut start = MAGIC.to_vec();
+        start.push(MOVIE_VERSION);
+        start.extend_from_slice(&self.rng.state().to_le_bytes());
+        start.extend_from_slice(&self.cycles_per_frame.to_le_bytes());
+        start.extend_from_slice(&(state.len() as u32).to_le_bytes());
+        start.extend_from_slice(&state);
+        start
+    }
+}
@@ -0,0 +1,175 @@
+//! Mutation testing of the instruction handlers, enabled by the `debug` feature.
+//!
+//! A mutant perturbs the result of every instruction matching one opcode pattern. If the embedded
+//! test programs end the same way with and without the mutant, their run does not depend on what
+//! that instruction computes, so a broken handler would go unnoticed.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::instruction::PATTERNS;
+use crate::quirk_test::quirk_probes;
+use crate::utils::Fnv1a;
+use crate::{Builtin, Emulator, Instruction};
+
+// Frames every test program runs for.
+const TEST_FRAMES: u32 = 120;
+
+// Seed of the random number generator of every test run, so only the mutant changes it.
+const TEST_SEED: u32 = 0x5eed;
+
+/// Handler perturbed by `set_mutation`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Mutation {
+    pattern: &'static str,
+    // Instructions perturbed so far.
+    applied: u32,
+}
+
+/// State an instruction can change, taken before it executes.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Before {
+    v: [u8; 16],
+    i: usize,
+    delay_timer: u8,
+    sound_timer: u8,
+}
+
+/// Outcome of the mutant of one opcode pattern.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MutantResult {
+    /// Opcode pattern as listed by `opcode_reference`.
+    pub pattern: &'static str,
+    pub mnemonic: &'static str,
+    /// The test programs executed the instruction.
+    pub executed: bool,
+    /// A test program ended differently with the mutant, i.e. the tests cover the instruction.
+    pub killed: bool,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Perturbs the result of every instruction matching an opcode pattern of `opcode_reference`,
+    /// e.g. "8XY4": the lowest V register it changed has its lowest bit flipped, otherwise I or
+    /// a timer it changed, and an instruction changing none of them skips the next one. Returns
+    /// `false` if the pattern is unknown.
+    pub fn set_mutation(&mut self, pattern: &str) -> bool {
+        match PATTERNS.iter().find(|&&(known, ..)| known == pattern) {
+            Some(&(pattern, ..)) => {
+                self.mutation = Some(Mutation { pattern, applied: 0 });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops perturbing instructions.
+    pub fn clear_mutation(&mut self) { self.mutation = None; }
+}
+
+impl Emulator {
+    /// Runs the embedded test programs (the quirk probes of `detect_quirks`, the splash screen
+    /// and the bundled games with the `games` feature) under the current variant and quirks, once
+    /// unchanged and once with the mutant of every opcode pattern of the variant. A mutant is
+    /// killed if any program ends with a different display, registers or fault.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let report = Emulator::new().mutation_report();
+    /// let add = report.iter().find(|mutant| mutant.pattern == "7XNN").unwrap();
+    /// assert!(add.executed && add.killed);
+    /// // None of the programs subtract, so nothing checks that instruction.
+    /// let sub = report.iter().find(|mutant| mutant.pattern == "8XY5").unwrap();
+    /// assert!(!sub.executed && !sub.killed);
+    /// ```
+    pub fn mutation_report(&self) -> Vec<MutantResult> {
+        let programs = self.test_programs();
+        let baseline: Vec<u32> = programs.iter().map(|program| self.run_mutant(program, None).0).collect();
+        PATTERNS
+            .iter()
+            .filter(|&&(pattern, sample, _)| Instruction::decode(sample, self.variant).pattern() == pattern)
+            .map(|&(pattern, sample, _)| {
+                let runs = programs.iter().map(|program| self.run_mutant(program, Some(pattern)));
+                let (executed, killed) = runs
+                    .zip(&baseline)
+                    .fold((false, false), |(executed, killed), ((end, applied), &base)| {
+                        (executed || applied, killed || end != base)
+                    });
+                let mnemonic = Instruction::decode(sample, self.variant).mnemonic();
+                MutantResult { pattern, mnemonic, executed, killed }
+            })
+            .collect()
+    }
+
+    fn test_programs(&self) -> Vec<Vec<u8>> {
+        let mut programs = quirk_probes(self.variant.load_address() as u16).to_vec();
+        programs.push(crate::builtin::program(Builtin::Splash, self.variant));
+        #[cfg(feature = "games")]
+        programs.extend(crate::games::ROMS.iter().map(|&(.., rom)| rom.to_vec()));
+        programs
+    }
+
+    // Runs a test program, returning the fingerprint of how it ended and whether the mutant was
+    // applied.
+    fn run_mutant(&self, program: &[u8], pattern: Option<&'static str>) -> (u32, bool) {
+        let mut emulator = Emulator::headless();
+        emulator.set_variant(self.variant);
+        emulator.set_quirks(self.quirks);
+        emulator.set_random_seed(TEST_SEED);
+        // The test programs fit into every variant.
+        let _ = emulator.load(program);
+        emulator.mutation = pattern.map(|pattern| Mutation { pattern, applied: 0 });
+        for _ in 0..TEST_FRAMES {
+            emulator.tick_frame();
+        }
+
+        let mut hash = Fnv1a::default();
+        hash.write(&emulator.pixels_packed());
+        hash.write(&emulator.v);
+        hash.write(&(emulator.i as u32).to_le_bytes());
+        hash.write(&(emulator.pc as u32).to_le_bytes());
+        hash.write(&[u8::from(emulator.fault.is_some())]);
+        (hash.finish(), emulator.mutation.is_some_and(|mutation| mutation.applied > 0))
+    }
+
+    /// State to compare with after the next instruction, if a mutation is set.
+    pub(crate) fn mutation_before(&self) -> Option<Before> {
+        self.mutation.map(|_| Before {
+            v: self.v,
+            i: self.i,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+        })
+    }
+
+    /// Perturbs the result of the instruction that just executed if it matches the mutation.
+    pub(crate) fn mutate(&mut self, before: Before) {
+        let pattern = Instruction::decode(self.opcode, self.variant).pattern();
+        match &mut self.mutation {
+            Some(mutation) if mutation.pattern == pattern => mutation.applied += 1,
+            _ => return,
+        }
+
+        if let Some(x) = (0..16).find(|&x| self.v[x] != before.v[x]) {
+            self.v[x] ^= 1;
+        } else if self.i != before.i {
+            self.i ^= 1;
+        } else if self.delay_timer != before.delay_timer {
+            self.delay_timer ^= 1;
+        } else if self.sound_timer != before.sound_timer {
+            self.sound_timer ^= 1;
+        } else {
+            self.pc += 2;
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `mutation_report` as an array of plain JS objects.
+    #[wasm_bindgen(js_name = mutation_report)]
+    pub fn mutation_report_js(&self) -> JsValue { crate::utils::to_js(&self.mutation_report()) }
+}
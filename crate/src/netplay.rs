@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Frame-indexed keypad input of the local and the remote player.
+///
+/// Masks have bit N set while key N is down. Missing remote frames are predicted by repeating the
+/// last known remote input, which is what a rollback session corrects later.
+#[derive(Clone, Debug, Default)]
+pub struct InputBuffer {
+    local: BTreeMap<u64, u16>,
+    remote: BTreeMap<u64, u16>,
+    // How many frames local input is delayed by.
+    delay: u64,
+    // Whether frames run with these inputs only, ignoring keys set on the emulator.
+    exclusive: bool,
+}
+
+impl InputBuffer {
+    pub fn set_delay(&mut self, frames: u64) { self.delay = frames; }
+
+    pub fn delay(&self) -> u64 { self.delay }
+
+    pub fn set_exclusive(&mut self, exclusive: bool) { self.exclusive = exclusive; }
+
+    pub fn is_exclusive(&self) -> bool { self.exclusive }
+
+    pub fn set_local(&mut self, frame: u64, mask: u16) { self.local.insert(frame, mask); }
+
+    pub fn set_remote(&mut self, frame: u64, mask: u16) { self.remote.insert(frame, mask); }
+
+    pub fn local(&self, frame: u64) -> Option<u16> { self.local.get(&frame).copied() }
+
+    pub fn remote(&self, frame: u64) -> Option<u16> { self.remote.get(&frame).copied() }
+
+    /// Remote input for the frame, or the last known remote input before it.
+    pub fn predicted_remote(&self, frame: u64) -> u16 {
+        self.remote.range(..=frame).next_back().map_or(0, |(_, &mask)| mask)
+    }
+
+    /// Local input for the frame, or the last known local input before it.
+    pub fn held_local(&self, frame: u64) -> u16 {
+        self.local.range(..=frame).next_back().map_or(0, |(_, &mask)| mask)
+    }
+
+    /// Combined keypad mask the frame runs with.
+    pub fn mask(&self, frame: u64) -> u16 { self.held_local(frame) | self.predicted_remote(frame) }
+
+    /// Drops inputs of frames before `frame`, keeping the latest one so predictions still work.
+    pub fn discard_before(&mut self, frame: u64) {
+        for inputs in [&mut self.local, &mut self.remote] {
+            let last = inputs.range(..frame).next_back().map(|(&at, &mask)| (at, mask));
+            *inputs = inputs.split_off(&frame);
+            if let Some((at, mask)) = last {
+                inputs.entry(at).or_insert(mask);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.local.clear();
+        self.remote.clear();
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Set how many frames local input is delayed by before it reaches the keypad.
+    pub fn set_input_delay(&mut self, frames: u32) { self.input.set_delay(u64::from(frames)); }
+
+    /// Records the local player's keys and returns the frame they take effect in
+    /// (the next frame plus the input delay).
+    ///
+    /// # Arguments
+    ///
+    /// * `mask` - Keypad mask, bit N is set while key N is down.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_input_delay(2);
+    /// assert_eq!(emulator.set_local_input(0b1000), 2);
+    /// emulator.set_remote_input(0, 0b0001);
+    /// ```
+    pub fn set_local_input(&mut self, mask: u16) -> u64 {
+        let frame = self.frame + self.input.delay();
+        self.input.set_local(frame, mask);
+        frame
+    }
+
+    /// Records the remote player's keys for a frame.
+    ///
+    /// Frames without remote input repeat the last known one.
+    pub fn set_remote_input(&mut self, frame: u64, mask: u16) { self.input.set_remote(frame, mask); }
+}
+
+/// How many frames the local side may run ahead of the confirmed remote input.
+pub const MAX_PREDICTION_FRAMES: u64 = 8;
+
+// How many local inputs are resent at most in one packet.
+const MAX_PACKET_INPUTS: u64 = 64;
+const PACKET_MAGIC: u8 = 0xc8;
+
+/// Reason an input packet was rejected.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NetError {
+    /// The packet is truncated or was not produced by `NetSession`.
+    BadPacket,
+    /// The packet corrects a frame the session no longer has a snapshot of, so it cannot roll
+    /// back and the peers are out of sync.
+    NoSnapshot,
+}
+
+/// Rollback netplay session between two emulators running the same ROM.
+///
+/// The session does not know anything about the transport: `advance` returns the packet to send to
+/// the peer and `receive` consumes the peer's packets. Every packet repeats the inputs the peer has
+/// not acknowledged yet, so lost packets only delay confirmation. When a remote input turns out to
+/// differ from the prediction the session restores the snapshot of that frame and re-runs the
+/// frames up to the present.
+///
+/// While a session is active the emulator runs with the inputs exchanged by the session only:
+/// `set_key`, `inject_key_event`, player keys, macros and the keyboard backend are ignored, because
+/// the peer never sees them and rollbacks could not replay them. Local keys have to be passed
+/// to `advance`.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct NetSession {
+    // Snapshots taken right before each unconfirmed frame ran, ordered by frame.
+    snapshots: std::collections::VecDeque<crate::Snapshot>,
+    // First frame without remote input, i.e. all earlier frames are confirmed.
+    remote_next: u64,
+    // First frame of local input the peer has not received yet.
+    peer_next: u64,
+    // Last frame local input was recorded for.
+    local_last: Option<u64>,
+    stalled: bool,
+    rollbacks: u32,
+}
+
+#[wasm_bindgen]
+impl NetSession {
+    /// Create new session.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_delay` - How many frames local input is delayed by, which hides network latency
+    ///   at the cost of responsiveness.
+    /// * `seed` - Seed of the random number generator behind CXNN. Both peers have to agree on it,
+    ///   e.g. the host picks it and sends it along with the ROM, or CXNN gives them different
+    ///   numbers and they desync.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, NetSession};
+    /// // loop: v0 := random 0xff, if key v1 is held: v2 += v0, jump loop
+    /// let rom = [0xc0, 0xff, 0xe1, 0xa1, 0x82, 0x04, 0x12, 0x00];
+    /// let (mut a, mut b) = (Emulator::new(), Emulator::new());
+    /// a.load(&rom).unwrap();
+    /// b.load(&rom).unwrap();
+    /// // Whatever the emulators were seeded with, the sessions seed them alike.
+    /// a.set_random_seed(1);
+    /// b.set_random_seed(2);
+    /// let mut session_a = NetSession::new(&mut a, 0, 7);
+    /// let mut session_b = NetSession::new(&mut b, 0, 7);
+    /// // Packets arrive a frame late, so every change of input rolls the other side back.
+    /// let (mut late_a, mut late_b) = (vec![], vec![]);
+    /// for frame in 0..30 {
+    ///     let packet_a = session_a.advance(&mut a, 0);
+    ///     let packet_b = session_b.advance(&mut b, if frame % 4 == 0 { 0b1 } else { 0 });
+    ///     if frame > 0 {
+    ///         session_a.receive(&mut a, &late_b).unwrap();
+    ///         session_b.receive(&mut b, &late_a).unwrap();
+    ///     }
+    ///     late_a = packet_a;
+    ///     late_b = packet_b;
+    /// }
+    /// session_a.receive(&mut a, &late_b).unwrap();
+    /// session_b.receive(&mut b, &late_a).unwrap();
+    /// assert!(session_a.rollbacks() > 0);
+    /// assert_eq!(a.frame(), b.frame());
+    /// assert_eq!(a.state_hash(), b.state_hash());
+    /// ```
+    pub fn new(emulator: &mut Emulator, input_delay: u32, seed: u32) -> NetSession {
+        emulator.input.clear();
+        emulator.input.set_exclusive(true);
+        emulator.keypad.release_all();
+        emulator.set_input_delay(input_delay);
+        emulator.set_random_seed(seed);
+        NetSession { remote_next: emulator.frame, peer_next: emulator.frame, ..NetSession::default() }
+    }
+
+    /// Ends the session, giving the emulator back its own inputs (`set_key`, keyboard, etc.).
+    pub fn end(self, emulator: &mut Emulator) {
+        emulator.input.clear();
+        emulator.input.set_exclusive(false);
+    }
+
+    /// Runs one frame with the given local keys and returns the packet to send to the peer.
+    ///
+    /// The frame is not run (see `stalled`) when the emulator would get more than
+    /// `MAX_PREDICTION_FRAMES` ahead of the remote input, but the packet is still produced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, NetSession};
+    /// let rom = [0xe0, 0x9e, 0x12, 0x00, 0x70, 0x01, 0x12, 0x00];
+    /// let (mut a, mut b) = (Emulator::new(), Emulator::new());
+    /// a.load(&rom).unwrap();
+    /// b.load(&rom).unwrap();
+    /// let mut session_a = NetSession::new(&mut a, 1, 42);
+    /// let mut session_b = NetSession::new(&mut b, 1, 42);
+    /// // Keys set on the emulator never reach the peer, so the session ignores them.
+    /// a.set_key(0x0, true);
+    /// for frame in 0..20 {
+    ///     let packet_a = session_a.advance(&mut a, if frame > 5 { 0b1 } else { 0 });
+    ///     let packet_b = session_b.advance(&mut b, 0);
+    ///     session_a.receive(&mut a, &packet_b).unwrap();
+    ///     session_b.receive(&mut b, &packet_a).unwrap();
+    /// }
+    /// assert_eq!(a.frame(), b.frame());
+    /// assert_eq!(a.state_hash(), b.state_hash());
+    /// ```
+    pub fn advance(&mut self, emulator: &mut Emulator, local_mask: u16) -> Vec<u8> {
+        self.stalled = emulator.frame >= self.remote_next + MAX_PREDICTION_FRAMES;
+
+        if !self.stalled {
+            self.local_last = Some(emulator.set_local_input(local_mask));
+            self.snapshots.push_back(emulator.snapshot());
+            emulator.tick_frame();
+        }
+
+        self.packet(emulator)
+    }
+
+    /// Consumes a packet produced by the peer's `advance`, rolling back if needed.
+    ///
+    /// Rolling back re-runs the frames even while the emulator is paused.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, NetSession};
+    /// // loop: if key v1 is held: v2 += 1, jump loop
+    /// let rom = [0xe1, 0xa1, 0x72, 0x01, 0x12, 0x00];
+    /// let (mut a, mut b) = (Emulator::new(), Emulator::new());
+    /// a.load(&rom).unwrap();
+    /// b.load(&rom).unwrap();
+    /// let mut session_a = NetSession::new(&mut a, 0, 7);
+    /// let mut session_b = NetSession::new(&mut b, 0, 7);
+    /// let packet_b = session_b.advance(&mut b, 0b10);
+    /// session_a.advance(&mut a, 0);
+    /// // A backgrounded tab pauses, the mispredicted packet still rolls it back.
+    /// a.pause();
+    /// session_a.receive(&mut a, &packet_b).unwrap();
+    /// assert_eq!(session_a.rollbacks(), 1);
+    /// assert_eq!(a.frame(), 1);
+    /// ```
+    pub fn receive(&mut self, emulator: &mut Emulator, packet: &[u8]) -> Result<(), NetError> {
+        if packet.len() < 10 || packet[0] != PACKET_MAGIC {
+            return Err(NetError::BadPacket);
+        }
+
+        let peer_next = u64::from(u32::from_le_bytes([packet[1], packet[2], packet[3], packet[4]]));
+        let start = u64::from(u32::from_le_bytes([packet[5], packet[6], packet[7], packet[8]]));
+        let count = packet[9] as usize;
+        let masks = &packet[10..];
+        if masks.len() != count * 2 {
+            return Err(NetError::BadPacket);
+        }
+
+        self.peer_next = self.peer_next.max(peer_next);
+
+        let mut rollback_to = None;
+        for (offset, mask) in masks.chunks(2).enumerate() {
+            let frame = start + offset as u64;
+            if frame < self.remote_next {
+                continue;
+            }
+            if frame > self.remote_next {
+                break;
+            }
+
+            let mask = u16::from_le_bytes([mask[0], mask[1]]);
+            let predicted = emulator.input.predicted_remote(frame);
+            emulator.input.set_remote(frame, mask);
+            self.remote_next += 1;
+
+            if frame < emulator.frame && predicted != mask && rollback_to.is_none() {
+                rollback_to = Some(frame);
+            }
+        }
+
+        if let Some(frame) = rollback_to {
+            self.rollback(emulator, frame)?;
+        }
+
+        // Confirmed frames can never be rolled back to.
+        while self.snapshots.front().is_some_and(|snapshot| snapshot.frame() < self.remote_next) {
+            self.snapshots.pop_front();
+        }
+        emulator.input.discard_before(self.remote_next.min(self.peer_next));
+
+        Ok(())
+    }
+
+    /// Every frame before this one ran with confirmed remote input.
+    pub fn confirmed_frame(&self) -> u64 { self.remote_next }
+
+    /// Whether the last `advance` skipped the frame to wait for the peer.
+    pub fn stalled(&self) -> bool { self.stalled }
+
+    /// How many times the session had to roll back.
+    pub fn rollbacks(&self) -> u32 { self.rollbacks }
+
+    fn rollback(&mut self, emulator: &mut Emulator, frame: u64) -> Result<(), NetError> {
+        let target = emulator.frame;
+        let position =
+            self.snapshots.iter().position(|snapshot| snapshot.frame() == frame).ok_or(NetError::NoSnapshot)?;
+
+        emulator.restore_snapshot(&self.snapshots[position]);
+        self.snapshots.truncate(position);
+        // Every `run_frame` executes at least one instruction of the frame, paused or not, so the
+        // frames already shown are always re-run.
+        let paused = std::mem::replace(&mut emulator.paused, false);
+        while emulator.frame < target {
+            self.snapshots.push_back(emulator.snapshot());
+            emulator.run_frame();
+        }
+        emulator.paused |= paused;
+        self.rollbacks += 1;
+        Ok(())
+    }
+
+    fn packet(&self, emulator: &Emulator) -> Vec<u8> {
+        let start = self.peer_next;
+        let end = match self.local_last {
+            Some(last) if last >= start => (last + 1).min(start + MAX_PACKET_INPUTS),
+            _ => start,
+        };
+
+        let mut packet = Vec::with_capacity(10 + 2 * (end - start) as usize);
+        packet.push(PACKET_MAGIC);
+        packet.extend_from_slice(&(self.remote_next as u32).to_le_bytes());
+        packet.extend_from_slice(&(start as u32).to_le_bytes());
+        packet.push((end - start) as u8);
+        for frame in start..end {
+            packet.extend_from_slice(&emulator.input.held_local(frame).to_le_bytes());
+        }
+        packet
+    }
+}
@@ -0,0 +1,641 @@
+//! Assembler for Octo source files (`.8o`), so programs written in Octo run without compiling
+//! them in Octo first.
+//!
+//! Labels, `:alias`, `:const`, `:calc`, `:byte`, `:org` and `:call` are understood, as are the
+//! structured `if`, `loop` and `while` statements. `:macro`, `:stringmode` and the comparison
+//! pseudo-ops (`<`, `>`, `<=` and `>=`) are not. Instructions the emulator does not run, the
+//! SUPER-CHIP ones and XO-CHIP's `scroll-up`, `audio`, `pitch` and register range `save` and
+//! `load`, are rejected, as are the scrolling and XO-CHIP ones when assembling for a variant
+//! without them.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, RomInfo, Variant};
+
+/// Octo source `assemble_octo` rejected: what is wrong and where. Memory is left untouched.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AsmError {
+    pub kind: AsmErrorKind,
+    /// Line of the source the error is on, counting from 1. 0 when no line is at fault: for a
+    /// missing `main` and a program too large for memory.
+    pub line: usize,
+}
+
+/// Reason an Octo source file could not be assembled.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AsmErrorKind {
+    /// A token is not a statement or does not fit where it is.
+    UnexpectedToken,
+    /// The source ends in the middle of a statement.
+    UnexpectedEnd,
+    /// A name is neither a register, a constant nor a label. Also reported for a missing `main`.
+    UndefinedName,
+    /// A label, constant or alias is defined twice.
+    Redefined,
+    /// A number does not fit into its operand, e.g. an address above 0xFFF.
+    OutOfRange,
+    /// `else`, `end`, `again` or `while` has no matching `if` or `loop`, or a block is not closed.
+    UnbalancedBlock,
+    /// The program does not fit into memory after the load address of the current variant.
+    TooLarge,
+    /// The instruction is Octo syntax for an opcode the variant does not run, e.g. `hires`.
+    Unsupported,
+}
+
+// Opened `if ... begin` and `loop` blocks.
+enum Block {
+    // Address of the jump over the `if` branch.
+    If(usize),
+    // Address of the jump over the `else` branch.
+    Else(usize),
+    // Start of the loop and the jumps of its `while` statements.
+    Loop(usize, Vec<usize>),
+}
+
+// Operand a label address is patched into once it is known.
+#[derive(Clone, Copy)]
+enum Operand {
+    // The lower 12 bits of the opcode at the address.
+    Nnn,
+    // The word at the address, following `F000`.
+    Long,
+}
+
+// Condition of `if` and `while`, as the opcodes skipping when it holds and when it does not.
+struct Condition {
+    skip_true: u16,
+    skip_false: u16,
+}
+
+struct Assembler<'a> {
+    // Every token and the line it is on.
+    tokens: Vec<(usize, &'a str)>,
+    next: usize,
+    // Line of the last taken token, where errors are reported.
+    line: usize,
+    variant: Variant,
+    origin: usize,
+    pc: usize,
+    image: Vec<u8>,
+    labels: HashMap<&'a str, usize>,
+    constants: HashMap<&'a str, i64>,
+    aliases: HashMap<&'a str, u8>,
+    fixups: Vec<(usize, &'a str, Operand, usize)>,
+    blocks: Vec<Block>,
+}
+
+/// Assembles Octo source into a program that starts at the load address of `variant`.
+///
+/// Like Octo, the program starts with a jump to the `main` label unless `main` is the first
+/// label and nothing is emitted before it. A name that is not defined yet is a call to a label
+/// defined further down.
+///
+/// # Example
+///
+/// ```
+/// use wasm_chip8::{assemble_octo, AsmErrorKind, Variant};
+/// let source = ": main\n  v0 := 5\n  loop\n    v0 += -1\n    if v0 != 0 then\n  again\n";
+/// assert_eq!(assemble_octo(source, Variant::Chip8).unwrap(), [0x60, 0x05, 0x70, 0xff, 0x30, 0x00, 0x12, 0x02]);
+/// let error = assemble_octo(": main\n  jump nowhere", Variant::Chip8).unwrap_err();
+/// assert_eq!((error.kind, error.line), (AsmErrorKind::UndefinedName, 2));
+/// let error = assemble_octo(": main\n  clear\n  plane 3\n", Variant::Chip8).unwrap_err();
+/// assert_eq!((error.kind, error.line), (AsmErrorKind::Unsupported, 3));
+/// assert!(assemble_octo(": main\n  clear\n  plane 3\n", Variant::XoChip).is_ok());
+/// ```
+pub fn assemble_octo(source: &str, variant: Variant) -> Result<Vec<u8>, AsmError> {
+    let tokens = source
+        .lines()
+        .enumerate()
+        .flat_map(|(index, line)| {
+            line.split('#').next().unwrap_or("").split_whitespace().map(move |token| (index + 1, token))
+        })
+        .collect();
+    let origin = variant.load_address();
+    let mut assembler = Assembler {
+        tokens,
+        next: 0,
+        line: 0,
+        variant,
+        origin,
+        pc: origin,
+        image: vec![],
+        labels: HashMap::new(),
+        constants: HashMap::new(),
+        aliases: HashMap::new(),
+        fixups: vec![],
+        blocks: vec![],
+    };
+    assembler.assemble().map_err(|kind| AsmError { kind, line: assembler.line })
+}
+
+impl<'a> Assembler<'a> {
+    fn assemble(&mut self) -> Result<Vec<u8>, AsmErrorKind> {
+        self.jump_to("main")?;
+        while self.next < self.tokens.len() {
+            self.statement()?;
+        }
+        self.finish()
+    }
+
+    fn take(&mut self) -> Result<&'a str, AsmErrorKind> {
+        let (line, token) = self.tokens.get(self.next).copied().ok_or(AsmErrorKind::UnexpectedEnd)?;
+        self.next += 1;
+        self.line = line;
+        Ok(token)
+    }
+
+    fn peek(&self) -> Option<&'a str> { self.tokens.get(self.next).map(|&(_, token)| token) }
+
+    // Fails for instructions the variant does not run.
+    fn require(&self, supported: fn(Variant) -> bool) -> Result<(), AsmErrorKind> {
+        if !supported(self.variant) {
+            return Err(AsmErrorKind::Unsupported);
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), AsmErrorKind> {
+        if self.take()? != expected {
+            return Err(AsmErrorKind::UnexpectedToken);
+        }
+        Ok(())
+    }
+
+    fn emit_byte(&mut self, byte: u8) {
+        let offset = self.pc - self.origin;
+        if self.image.len() <= offset {
+            self.image.resize(offset + 1, 0);
+        }
+        self.image[offset] = byte;
+        self.pc += 1;
+    }
+
+    fn emit(&mut self, opcode: u16) {
+        self.emit_byte((opcode >> 8) as u8);
+        self.emit_byte(opcode as u8);
+    }
+
+    // Emits an opcode whose lower 12 bits are the address of a label, patched later if the
+    // label is not defined yet.
+    fn emit_address(&mut self, opcode: u16, name: &'a str) -> Result<(), AsmErrorKind> {
+        match self.labels.get(name) {
+            Some(&address) => self.emit(opcode | nnn(address)?),
+            None => {
+                self.fixups.push((self.pc, name, Operand::Nnn, self.line));
+                self.emit(opcode);
+            }
+        }
+        Ok(())
+    }
+
+    fn jump_to(&mut self, name: &'a str) -> Result<(), AsmErrorKind> { self.emit_address(0x1000, name) }
+
+    // Emits an opcode taking an address operand, which is a number, a constant or a label.
+    fn address_operand(&mut self, opcode: u16) -> Result<(), AsmErrorKind> {
+        let token = self.take()?;
+        match self.number(token) {
+            Some(value) => self.emit(opcode | nnn(value_usize(value)?)?),
+            None => self.emit_address(opcode, token)?,
+        }
+        Ok(())
+    }
+
+    // Emits a placeholder jump patched by `patch_jump`.
+    fn placeholder(&mut self) -> usize {
+        let at = self.pc;
+        self.emit(0x1000);
+        at
+    }
+
+    fn patch_jump(&mut self, at: usize, target: usize) -> Result<(), AsmErrorKind> {
+        let offset = at - self.origin;
+        let opcode = u16::from(self.image[offset]) << 8 & 0xf000 | nnn(target)?;
+        self.image[offset..offset + 2].copy_from_slice(&opcode.to_be_bytes());
+        Ok(())
+    }
+
+    fn number(&self, token: &str) -> Option<i64> {
+        if let Some(&value) = self.constants.get(token) {
+            return Some(value);
+        }
+        if let Some(&address) = self.labels.get(token) {
+            return Some(address as i64);
+        }
+        parse_number(token)
+    }
+
+    fn value(&mut self) -> Result<i64, AsmErrorKind> {
+        let token = self.take()?;
+        if token == "{" {
+            return self.calc();
+        }
+        self.number(token).ok_or(AsmErrorKind::UndefinedName)
+    }
+
+    fn byte(&mut self) -> Result<u8, AsmErrorKind> { byte(self.value()?) }
+
+    fn nibble(&mut self) -> Result<u16, AsmErrorKind> {
+        match self.value()? {
+            value @ 0..=15 => Ok(value as u16),
+            _ => Err(AsmErrorKind::OutOfRange),
+        }
+    }
+
+    fn register(&self, token: &str) -> Option<u8> {
+        if let Some(&register) = self.aliases.get(token) {
+            return Some(register);
+        }
+        let digit = token.strip_prefix('v').or_else(|| token.strip_prefix('V'))?;
+        if digit.len() != 1 {
+            return None;
+        }
+        u8::from_str_radix(digit, 16).ok()
+    }
+
+    fn take_register(&mut self) -> Result<u16, AsmErrorKind> {
+        let token = self.take()?;
+        self.register(token).map(u16::from).ok_or(AsmErrorKind::UnexpectedToken)
+    }
+
+    // Evaluates a `{ ... }` expression whose opening brace was taken.
+    fn calc(&mut self) -> Result<i64, AsmErrorKind> {
+        let value = self.expression()?;
+        self.expect("}")?;
+        Ok(value)
+    }
+
+    // Octo evaluates expressions right to left without operator precedence.
+    fn expression(&mut self) -> Result<i64, AsmErrorKind> {
+        let left = self.term()?;
+        let operator = match self.peek() {
+            Some(operator @ ("+" | "-" | "*" | "/" | "%" | "&" | "|" | "^" | "<<" | ">>" | "min" | "max")) => operator,
+            _ => return Ok(left),
+        };
+        self.next += 1;
+        let right = self.expression()?;
+        let value = match operator {
+            "+" => left.checked_add(right),
+            "-" => left.checked_sub(right),
+            "*" => left.checked_mul(right),
+            "/" => left.checked_div(right),
+            "%" => left.checked_rem(right),
+            "&" => Some(left & right),
+            "|" => Some(left | right),
+            "^" => Some(left ^ right),
+            "<<" => u32::try_from(right).ok().and_then(|right| left.checked_shl(right)),
+            ">>" => u32::try_from(right).ok().and_then(|right| left.checked_shr(right)),
+            "min" => Some(left.min(right)),
+            _ => Some(left.max(right)),
+        };
+        value.ok_or(AsmErrorKind::OutOfRange)
+    }
+
+    fn term(&mut self) -> Result<i64, AsmErrorKind> {
+        match self.take()? {
+            "(" => {
+                let value = self.expression()?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            "-" => Ok(-self.term()?),
+            "~" => Ok(!self.term()?),
+            "HERE" => Ok(self.pc as i64),
+            token => self.number(token).ok_or(AsmErrorKind::UndefinedName),
+        }
+    }
+
+    fn define<T>(map: &mut HashMap<&'a str, T>, name: &'a str, value: T) -> Result<(), AsmErrorKind> {
+        if map.insert(name, value).is_some() {
+            return Err(AsmErrorKind::Redefined);
+        }
+        Ok(())
+    }
+
+    fn condition(&mut self) -> Result<Condition, AsmErrorKind> {
+        let x = self.take_register()? << 8;
+        let operator = self.take()?;
+        let (skip_true, skip_false) = match operator {
+            "key" => (0xe09e | x, 0xe0a1 | x),
+            "-key" => (0xe0a1 | x, 0xe09e | x),
+            "==" | "!=" => {
+                let token = self.take()?;
+                let (eq, ne) = match self.register(token) {
+                    Some(y) => (0x5000 | x | u16::from(y) << 4, 0x9000 | x | u16::from(y) << 4),
+                    None => {
+                        let nn = u16::from(byte(self.number(token).ok_or(AsmErrorKind::UndefinedName)?)?);
+                        (0x3000 | x | nn, 0x4000 | x | nn)
+                    }
+                };
+                if operator == "==" {
+                    (eq, ne)
+                } else {
+                    (ne, eq)
+                }
+            }
+            _ => return Err(AsmErrorKind::UnexpectedToken),
+        };
+        Ok(Condition { skip_true, skip_false })
+    }
+
+    fn statement(&mut self) -> Result<(), AsmErrorKind> {
+        let token = self.take()?;
+        match token {
+            ":" => {
+                let name = self.take()?;
+                // Drop the jump to `main` when the program starts with it.
+                if name == "main" && self.pc == self.origin + 2 && self.image.len() == 2 {
+                    self.image.clear();
+                    self.pc = self.origin;
+                    self.fixups.retain(|&(_, name, ..)| name != "main");
+                }
+                Assembler::define(&mut self.labels, name, self.pc)?;
+            }
+            ":alias" => {
+                let name = self.take()?;
+                let register = self.take_register()? as u8;
+                Assembler::define(&mut self.aliases, name, register)?;
+            }
+            ":const" => {
+                let name = self.take()?;
+                let value = self.value()?;
+                Assembler::define(&mut self.constants, name, value)?;
+            }
+            ":calc" => {
+                let name = self.take()?;
+                self.expect("{")?;
+                let value = self.calc()?;
+                Assembler::define(&mut self.constants, name, value)?;
+            }
+            ":byte" => {
+                let byte = self.byte()?;
+                self.emit_byte(byte);
+            }
+            ":org" => {
+                let address = value_usize(self.value()?)?;
+                if address < self.origin || address > 0xffff {
+                    return Err(AsmErrorKind::OutOfRange);
+                }
+                self.pc = address;
+            }
+            ":call" => self.address_operand(0x2000)?,
+            ";" | "return" => self.emit(0x00ee),
+            "clear" => self.emit(0x00e0),
+            "hires" | "lores" | "exit" | "scroll-up" | "audio" | "pitch" | "saveflags" | "loadflags" => {
+                return Err(AsmErrorKind::Unsupported)
+            }
+            "scroll-down" => {
+                self.require(Variant::has_scrolling)?;
+                let n = self.nibble()?;
+                self.emit(0x00c0 | n);
+            }
+            "scroll-right" | "scroll-left" => {
+                self.require(Variant::has_scrolling)?;
+                self.emit(if token == "scroll-right" { 0x00fb } else { 0x00fc });
+            }
+            "native" => self.address_operand(0x0000)?,
+            "jump" => self.address_operand(0x1000)?,
+            "jump0" => self.address_operand(0xb000)?,
+            "sprite" => {
+                let (x, y) = (self.take_register()?, self.take_register()?);
+                let n = self.nibble()?;
+                self.emit(0xd000 | x << 8 | y << 4 | n);
+            }
+            "plane" => {
+                self.require(Variant::has_planes)?;
+                let n = self.nibble()?;
+                self.emit(0xf001 | n << 8);
+            }
+            "bcd" => self.register_op(0xf033)?,
+            "save" | "load" => {
+                let x = self.take_register()?;
+                if self.peek() == Some("-") {
+                    return Err(AsmErrorKind::Unsupported);
+                }
+                let opcode = if token == "save" { 0xf055 } else { 0xf065 };
+                self.emit(opcode | x << 8);
+            }
+            "delay" | "buzzer" => {
+                self.expect(":=")?;
+                let x = self.take_register()?;
+                let opcode = if token == "delay" { 0xf015 } else { 0xf018 };
+                self.emit(opcode | x << 8);
+            }
+            "i" => self.i_statement()?,
+            "if" => {
+                let condition = self.condition()?;
+                match self.take()? {
+                    "then" => self.emit(condition.skip_false),
+                    "begin" => {
+                        self.emit(condition.skip_true);
+                        let at = self.placeholder();
+                        self.blocks.push(Block::If(at));
+                    }
+                    _ => return Err(AsmErrorKind::UnexpectedToken),
+                }
+            }
+            "else" => match self.blocks.pop() {
+                Some(Block::If(at)) => {
+                    let over = self.placeholder();
+                    self.patch_jump(at, self.pc)?;
+                    self.blocks.push(Block::Else(over));
+                }
+                _ => return Err(AsmErrorKind::UnbalancedBlock),
+            },
+            "end" => match self.blocks.pop() {
+                Some(Block::If(at)) | Some(Block::Else(at)) => self.patch_jump(at, self.pc)?,
+                _ => return Err(AsmErrorKind::UnbalancedBlock),
+            },
+            "loop" => self.blocks.push(Block::Loop(self.pc, vec![])),
+            "while" => {
+                let condition = self.condition()?;
+                self.emit(condition.skip_true);
+                let at = self.placeholder();
+                match self.blocks.iter_mut().rev().find(|block| matches!(block, Block::Loop(..))) {
+                    Some(Block::Loop(_, exits)) => exits.push(at),
+                    _ => return Err(AsmErrorKind::UnbalancedBlock),
+                }
+            }
+            "again" => match self.blocks.pop() {
+                Some(Block::Loop(start, exits)) => {
+                    self.emit(0x1000 | nnn(start)?);
+                    for at in exits {
+                        self.patch_jump(at, self.pc)?;
+                    }
+                }
+                _ => return Err(AsmErrorKind::UnbalancedBlock),
+            },
+            _ => {
+                if let Some(x) = self.register(token) {
+                    self.register_statement(u16::from(x) << 8)?;
+                } else if let Some(value) = self.constants.get(token).copied().or_else(|| parse_number(token)) {
+                    self.emit_byte(byte(value)?);
+                } else if token.starts_with(':') || token == "{" || token == "}" {
+                    return Err(AsmErrorKind::UnexpectedToken);
+                } else {
+                    self.emit_address(0x2000, token)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn register_op(&mut self, opcode: u16) -> Result<(), AsmErrorKind> {
+        let x = self.take_register()?;
+        self.emit(opcode | x << 8);
+        Ok(())
+    }
+
+    fn i_statement(&mut self) -> Result<(), AsmErrorKind> {
+        match self.take()? {
+            ":=" => match self.peek() {
+                Some("hex") => {
+                    self.next += 1;
+                    self.register_op(0xf029)
+                }
+                Some("bighex") => Err(AsmErrorKind::Unsupported),
+                Some("long") => {
+                    self.require(Variant::has_long_addresses)?;
+                    self.next += 1;
+                    self.emit(0xf000);
+                    let token = self.take()?;
+                    match self.number(token) {
+                        Some(value) if (0..=0xffff).contains(&value) => self.emit(value as u16),
+                        Some(_) => return Err(AsmErrorKind::OutOfRange),
+                        None => {
+                            self.fixups.push((self.pc, token, Operand::Long, self.line));
+                            self.emit(0);
+                        }
+                    }
+                    Ok(())
+                }
+                _ => self.address_operand(0xa000),
+            },
+            "+=" => self.register_op(0xf01e),
+            _ => Err(AsmErrorKind::UnexpectedToken),
+        }
+    }
+
+    fn register_statement(&mut self, x: u16) -> Result<(), AsmErrorKind> {
+        let operator = self.take()?;
+        let token = self.take()?;
+        if let Some(y) = self.register(token) {
+            let low = match operator {
+                ":=" => 0x0,
+                "|=" => 0x1,
+                "&=" => 0x2,
+                "^=" => 0x3,
+                "+=" => 0x4,
+                "-=" => 0x5,
+                ">>=" => 0x6,
+                "=-" => 0x7,
+                "<<=" => 0xe,
+                _ => return Err(AsmErrorKind::UnexpectedToken),
+            };
+            self.emit(0x8000 | x | u16::from(y) << 4 | low);
+            return Ok(());
+        }
+
+        match (operator, token) {
+            (":=", "delay") => self.emit(0xf007 | x),
+            (":=", "key") => self.emit(0xf00a | x),
+            (":=", "random") => {
+                let nn = self.byte()?;
+                self.emit(0xc000 | x | u16::from(nn));
+            }
+            (":=" | "+=" | "-=", _) => {
+                self.next -= 1;
+                let value = self.value()?;
+                let (opcode, value) = match operator {
+                    ":=" => (0x6000, value),
+                    "+=" => (0x7000, value),
+                    _ => (0x7000, -value),
+                };
+                self.emit(opcode | x | u16::from(byte(value)?));
+            }
+            _ => return Err(AsmErrorKind::UnexpectedToken),
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<Vec<u8>, AsmErrorKind> {
+        if !self.blocks.is_empty() {
+            return Err(AsmErrorKind::UnbalancedBlock);
+        }
+        for (at, name, operand, line) in std::mem::take(&mut self.fixups) {
+            self.line = line;
+            let address = *self.labels.get(name).ok_or(AsmErrorKind::UndefinedName)?;
+            match operand {
+                Operand::Nnn => self.patch_jump(at, address)?,
+                Operand::Long => {
+                    let word = u16::try_from(address).map_err(|_| AsmErrorKind::OutOfRange)?;
+                    let offset = at - self.origin;
+                    self.image[offset..offset + 2].copy_from_slice(&word.to_be_bytes());
+                }
+            }
+        }
+        Ok(std::mem::take(&mut self.image))
+    }
+}
+
+// Reads a number the way Octo does, decimal unless it has a `0x` or `0b` prefix.
+fn parse_number(token: &str) -> Option<i64> {
+    let (negative, digits) = match token.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, token),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")) {
+        i64::from_str_radix(binary, 2).ok()?
+    } else if !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        digits.parse().ok()?
+    } else {
+        return None;
+    };
+    Some(if negative { -value } else { value })
+}
+
+// Octo accepts negative bytes and stores them in two's complement.
+fn byte(value: i64) -> Result<u8, AsmErrorKind> {
+    if !(-128..=255).contains(&value) {
+        return Err(AsmErrorKind::OutOfRange);
+    }
+    Ok(value as u8)
+}
+
+fn value_usize(value: i64) -> Result<usize, AsmErrorKind> { usize::try_from(value).map_err(|_| AsmErrorKind::OutOfRange) }
+
+fn nnn(address: usize) -> Result<u16, AsmErrorKind> {
+    if address > 0xfff {
+        return Err(AsmErrorKind::OutOfRange);
+    }
+    Ok(address as u16)
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Assembles Octo source with `assemble_octo` and loads the program, so `.8o` files can be
+    /// run as they are. Returns the size and hash of the assembled program.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// let source = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/roms/pong.8o"));
+    /// let rom = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/roms/pong.ch8"));
+    /// let info = emulator.load_octo_source(source).unwrap();
+    /// assert_eq!((info.size, info.rom_hash), (rom.len(), emulator.rom_hash()));
+    /// assert_eq!(&emulator.memory()[0x200..0x200 + rom.len()], &rom[..]);
+    /// ```
+    pub fn load_octo_source(&mut self, source: &str) -> Result<RomInfo, AsmError> {
+        let program = assemble_octo(source, self.variant)?;
+        self.load(&program).map_err(|_| AsmError { kind: AsmErrorKind::TooLarge, line: 0 })?;
+        Ok(RomInfo { size: program.len(), rom_hash: self.rom_hash })
+    }
+}
@@ -0,0 +1,193 @@
+//! Debug overlays drawn by the host on top of the `render_rgba` image, e.g. an FPS counter or
+//! key hints. They never touch the emulated display, so programs, savestates and screenshot
+//! comparisons do not see them.
+
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Color of overlays until `set_overlay_color` is called, opaque white.
+pub const DEFAULT_OVERLAY_COLOR: u32 = 0xffff_ffff;
+
+// 4 by 5 glyphs of the characters the CHIP-8 font lacks, the hex digits come from the font.
+const GLYPHS: [(char, [u8; 5]); 26] = [
+    ('G', [0xf0, 0x80, 0xb0, 0x90, 0xf0]),
+    ('H', [0x90, 0x90, 0xf0, 0x90, 0x90]),
+    ('I', [0xe0, 0x40, 0x40, 0x40, 0xe0]),
+    ('J', [0x70, 0x20, 0x20, 0xa0, 0xe0]),
+    ('K', [0x90, 0xa0, 0xc0, 0xa0, 0x90]),
+    ('L', [0x80, 0x80, 0x80, 0x80, 0xf0]),
+    ('M', [0x90, 0xf0, 0xf0, 0x90, 0x90]),
+    ('N', [0x90, 0xd0, 0xb0, 0x90, 0x90]),
+    ('O', [0xf0, 0x90, 0x90, 0x90, 0xf0]),
+    ('P', [0xf0, 0x90, 0xf0, 0x80, 0x80]),
+    ('Q', [0xf0, 0x90, 0x90, 0xb0, 0xf0]),
+    ('R', [0xe0, 0x90, 0xe0, 0xa0, 0x90]),
+    ('S', [0xf0, 0x80, 0xf0, 0x10, 0xf0]),
+    ('T', [0xe0, 0x40, 0x40, 0x40, 0x40]),
+    ('U', [0x90, 0x90, 0x90, 0x90, 0xf0]),
+    ('V', [0x90, 0x90, 0x90, 0xa0, 0x40]),
+    ('W', [0x90, 0x90, 0xf0, 0xf0, 0x90]),
+    ('X', [0x90, 0x90, 0x60, 0x90, 0x90]),
+    ('Y', [0xa0, 0xa0, 0x40, 0x40, 0x40]),
+    ('Z', [0xf0, 0x10, 0x20, 0x40, 0xf0]),
+    (':', [0x00, 0x40, 0x00, 0x40, 0x00]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x40]),
+    ('-', [0x00, 0x00, 0xf0, 0x00, 0x00]),
+    ('/', [0x10, 0x10, 0x20, 0x40, 0x80]),
+    ('=', [0x00, 0xf0, 0x00, 0xf0, 0x00]),
+    ('%', [0x90, 0x10, 0x20, 0x40, 0x90]),
+];
+
+#[derive(Clone, Debug)]
+enum Item {
+    Text { x: i32, y: i32, text: String, color: u32 },
+    Rect { x: i32, y: i32, width: u32, height: u32, color: u32 },
+}
+
+/// Overlay items in drawing order.
+#[derive(Clone, Debug)]
+pub(crate) struct Overlay {
+    items: Vec<Item>,
+    color: u32,
+}
+
+impl Default for Overlay {
+    fn default() -> Overlay { Overlay { items: vec![], color: DEFAULT_OVERLAY_COLOR } }
+}
+
+// Blends a 0xAARRGGBB color into an RGBA pixel.
+fn blend(pixel: &mut [u8], color: u32) {
+    let alpha = color >> 24;
+    for (channel, shift) in pixel.iter_mut().zip([16, 8, 0]) {
+        let source = (color >> shift) & 0xff;
+        *channel = ((source * alpha + u32::from(*channel) * (255 - alpha)) / 255) as u8;
+    }
+}
+
+impl Overlay {
+    /// Draws the items over an RGBA image. Glyph pixels are `scale` image pixels wide.
+    pub(crate) fn composite(&self, image: &mut [u8], width: usize, height: usize, font: &[u8; 80], scale: u32) {
+        // Items may lie partly or entirely outside the image, the bounds are computed in i64 so
+        // no position or size overflows before clipping.
+        let clip = |start: i64, len: u32, end: usize| {
+            start.clamp(0, end as i64) as usize..(start + i64::from(len)).clamp(0, end as i64) as usize
+        };
+        let mut fill = |x: i64, y: i64, w: u32, h: u32, color: u32| {
+            let columns = clip(x, w, width);
+            for row in clip(y, h, height) {
+                for column in columns.clone() {
+                    let offset = (row * width + column) * 4;
+                    blend(&mut image[offset..offset + 3], color);
+                }
+            }
+        };
+
+        for item in &self.items {
+            match *item {
+                Item::Rect { x, y, width, height, color } => {
+                    fill(i64::from(x), i64::from(y), width, height, color)
+                }
+                Item::Text { x, y, ref text, color } => {
+                    let size = i64::from(scale);
+                    for (index, character) in text.chars().enumerate() {
+                        let left = i64::from(x) + index as i64 * 5 * size;
+                        if left >= width as i64 {
+                            break;
+                        }
+                        for (row, bits) in glyph(font, character).iter().enumerate() {
+                            for column in (0..4).filter(|column| bits & (0x80 >> column) != 0) {
+                                fill(left + column * size, i64::from(y) + row as i64 * size, scale, scale, color);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Glyph of a character, blank for characters without one.
+fn glyph(font: &[u8; 80], character: char) -> [u8; 5] {
+    let character = character.to_ascii_uppercase();
+    let mut glyph = [0; 5];
+    if let Some(digit) = character.to_digit(16) {
+        glyph.copy_from_slice(&font[digit as usize * 5..][..5]);
+    } else if let Some(&(_, rows)) = GLYPHS.iter().find(|&&(known, _)| known == character) {
+        glyph = rows;
+    }
+    glyph
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Sets the 0xAARRGGBB color of the overlays added next, opaque white by default.
+    pub fn set_overlay_color(&mut self, color: u32) { self.overlay.color = color; }
+
+    /// Adds a line of text to the overlay with its top left corner at `(x, y)` in pixels of the
+    /// `render_rgba` image. Hex digits use the font of the variant, the other letters and
+    /// `: . - / = %` a font of the same style; other characters are left blank. Glyph pixels are a
+    /// quarter of a display pixel wide (at least one image pixel), characters five glyph pixels.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_colors(0x000000, 0xffffff);
+    /// emulator.set_display_scale(8);
+    /// emulator.set_overlay_color(0x80ff_0000);
+    /// emulator.overlay_rect(0, 0, 4, 4);
+    /// emulator.set_overlay_color(0xff00_ff00);
+    /// emulator.overlay_text(8, 0, "FPS 60");
+    /// emulator.render_rgba();
+    /// assert_eq!(emulator.rgba()[..4], [0x80, 0, 0, 0xff]);
+    /// // The top row of the F glyph.
+    /// assert_eq!(emulator.rgba()[8 * 4..8 * 4 + 4], [0, 0xff, 0, 0xff]);
+    /// assert!(emulator.pixels().all(|pixel| !pixel.is_lit()));
+    /// emulator.clear_overlay();
+    /// emulator.render_rgba();
+    /// assert!(emulator.rgba().chunks(4).all(|pixel| pixel == [0, 0, 0, 0xff]));
+    /// ```
+    pub fn overlay_text(&mut self, x: i32, y: i32, text: &str) {
+        let color = self.overlay.color;
+        self.overlay.items.push(Item::Text { x, y, text: text.to_string(), color });
+    }
+
+    /// Adds a filled rectangle to the overlay, e.g. a translucent backdrop for text, in pixels of
+    /// the `render_rgba` image. The part outside the image is clipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_colors(0x000000, 0xffffff);
+    /// emulator.overlay_rect(i32::MIN, i32::MIN, u32::MAX, u32::MAX);
+    /// emulator.overlay_rect(i32::MAX, i32::MAX, u32::MAX, u32::MAX);
+    /// emulator.overlay_text(i32::MAX, i32::MIN, "OUT OF SIGHT");
+    /// emulator.render_rgba();
+    /// // The first rectangle covers the whole image.
+    /// assert!(emulator.rgba().chunks(4).all(|pixel| pixel == [0xff, 0xff, 0xff, 0xff]));
+    /// ```
+    pub fn overlay_rect(&mut self, x: i32, y: i32, width: u32, height: u32) {
+        let color = self.overlay.color;
+        self.overlay.items.push(Item::Rect { x, y, width, height, color });
+    }
+
+    /// Removes everything from the overlay. Overlays stay until cleared, so frontends redrawing
+    /// them every frame clear them first.
+    pub fn clear_overlay(&mut self) { self.overlay.items.clear(); }
+}
+
+impl Emulator {
+    /// Draws the overlay over the image of the last `render_rgba` call.
+    pub(crate) fn composite_overlay(&mut self) {
+        if self.overlay.items.is_empty() {
+            return;
+        }
+        let (width, height, scale) = (self.renderer.width(), self.renderer.height(), self.renderer.scale());
+        let font = self.variant.font();
+        self.overlay.composite(self.renderer.buffer_mut(), width, height, font, (scale / 4).max(1));
+    }
+}
@@ -0,0 +1,182 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Host key codes (`KeyboardEvent.code`) of the left cluster, laid out like the COSMAC VIP keypad.
+#[rustfmt::skip]
+const LEFT_CLUSTER: [(&str, u8); 16] = [
+    ("Digit1", 0x1), ("Digit2", 0x2), ("Digit3", 0x3), ("Digit4", 0xc),
+    ("KeyQ", 0x4), ("KeyW", 0x5), ("KeyE", 0x6), ("KeyR", 0xd),
+    ("KeyA", 0x7), ("KeyS", 0x8), ("KeyD", 0x9), ("KeyF", 0xe),
+    ("KeyZ", 0xa), ("KeyX", 0x0), ("KeyC", 0xb), ("KeyV", 0xf),
+];
+
+/// The same layout on the right side of the keyboard.
+#[rustfmt::skip]
+const RIGHT_CLUSTER: [(&str, u8); 16] = [
+    ("Digit7", 0x1), ("Digit8", 0x2), ("Digit9", 0x3), ("Digit0", 0xc),
+    ("KeyU", 0x4), ("KeyI", 0x5), ("KeyO", 0x6), ("KeyP", 0xd),
+    ("KeyJ", 0x7), ("KeyK", 0x8), ("KeyL", 0x9), ("Semicolon", 0xe),
+    ("KeyM", 0xa), ("Comma", 0x0), ("Period", 0xb), ("Slash", 0xf),
+];
+
+/// How host keys passed to `set_host_key` reach the keypad.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InputMode {
+    /// Only the left cluster is mapped and every key goes through.
+    #[default]
+    Single,
+    /// The left cluster belongs to player one and the right one to player two. Keys outside of
+    /// the filter of their player are dropped.
+    TwoPlayer,
+}
+
+/// One of the two players sharing the keypad.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Player {
+    One,
+    Two,
+}
+
+/// Per-player key filters and held keys.
+#[derive(Clone, Debug)]
+pub struct Players {
+    mode: InputMode,
+    // Keypad keys every player may press and keys they hold down, indexed by player.
+    filters: [u16; 2],
+    held: [u16; 2],
+    // Keys tested by EX9E/EXA1 since the last reset and whether FX0A ran.
+    polled: u16,
+    waited: bool,
+}
+
+impl Default for Players {
+    fn default() -> Players {
+        Players { mode: InputMode::default(), filters: [0xffff; 2], held: [0; 2], polled: 0, waited: false }
+    }
+}
+
+impl Players {
+    /// Keys held down by both players.
+    pub fn mask(&self) -> u16 { self.held[0] | self.held[1] }
+
+    pub fn record_poll(&mut self, key: u8) {
+        if key <= 0xf {
+            self.polled |= 1 << key;
+        }
+    }
+
+    pub fn record_wait(&mut self) { self.waited = true; }
+
+    pub fn clear_stats(&mut self) {
+        self.polled = 0;
+        self.waited = false;
+    }
+
+    fn press(&mut self, player: usize, key: u8, pressed: bool) {
+        if pressed {
+            self.held[player] |= 1 << key;
+        } else {
+            self.held[player] &= !(1 << key);
+        }
+    }
+}
+
+fn index(player: Player) -> usize {
+    match player {
+        Player::One => 0,
+        Player::Two => 1,
+    }
+}
+
+fn lookup(cluster: &[(&str, u8); 16], code: &str) -> Option<u8> {
+    cluster.iter().find(|(name, _)| *name == code).map(|&(_, key)| key)
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Switches between one player and two players sharing the keypad. Held player keys are released.
+    pub fn set_input_mode(&mut self, mode: InputMode) {
+        self.players.mode = mode;
+        self.players.held = [0; 2];
+    }
+
+    pub fn input_mode(&self) -> InputMode { self.players.mode }
+
+    /// Restricts the keypad keys a player can press to the bits set in `mask` (bit N is key N).
+    pub fn set_player_keys(&mut self, player: Player, mask: u16) {
+        self.players.filters[index(player)] = mask;
+        self.players.held[index(player)] &= mask;
+    }
+
+    /// Returns the filter of the player set with `set_player_keys`, all keys by default.
+    pub fn player_keys(&self, player: Player) -> u16 { self.players.filters[index(player)] }
+
+    /// Returns the keys the player holds down as a bitmask.
+    pub fn player_state(&self, player: Player) -> u16 { self.players.held[index(player)] }
+
+    /// Sets the state of a keypad key on behalf of a player, ignoring keys outside of their filter.
+    pub fn set_player_key(&mut self, player: Player, key: u8, pressed: bool) {
+        let player = index(player);
+        if key <= 0xf && self.players.filters[player] & (1 << key) != 0 {
+            self.players.press(player, key, pressed);
+            self.note_key_change(key, pressed);
+        }
+    }
+
+    /// Forwards a host key to the player whose cluster contains it.
+    ///
+    /// Returns `false` if the key is not mapped in the current input mode, so the host can let the
+    /// browser handle it.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - Physical key as reported by `KeyboardEvent.code`, e.g. `"KeyQ"`.
+    /// * `pressed` - Whether the key is held down.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, InputMode, Player};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_input_mode(InputMode::TwoPlayer);
+    /// // Pong: the left paddle uses 1 and 4, the right one C and D.
+    /// emulator.set_player_keys(Player::One, 1 << 0x1 | 1 << 0x4);
+    /// emulator.set_player_keys(Player::Two, 1 << 0xc | 1 << 0xd);
+    /// assert!(emulator.set_host_key("KeyQ", true));
+    /// assert!(emulator.set_host_key("Digit0", true));
+    /// emulator.set_host_key("Digit2", true); // 2 is filtered out for player one
+    /// assert_eq!(emulator.player_state(Player::One), 1 << 0x4);
+    /// assert_eq!(emulator.player_state(Player::Two), 1 << 0xc);
+    /// ```
+    pub fn set_host_key(&mut self, code: &str, pressed: bool) -> bool {
+        match self.players.mode {
+            InputMode::Single => match lookup(&LEFT_CLUSTER, code) {
+                Some(key) => {
+                    self.players.press(0, key, pressed);
+                    self.note_key_change(key, pressed);
+                    true
+                }
+                None => false,
+            },
+            InputMode::TwoPlayer => {
+                let (player, key) = match (lookup(&LEFT_CLUSTER, code), lookup(&RIGHT_CLUSTER, code)) {
+                    (Some(key), _) => (Player::One, key),
+                    (None, Some(key)) => (Player::Two, key),
+                    (None, None) => return false,
+                };
+                self.set_player_key(player, key, pressed);
+                true
+            }
+        }
+    }
+
+    /// Keys tested by EX9E/EXA1 since the last reset as a bitmask. Together with `waits_for_key`
+    /// it tells which keys are worth handing out to the players.
+    pub fn polled_keys(&self) -> u16 { self.players.polled }
+
+    /// Returns `true` if the program waited for a key press with FX0A since the last reset.
+    pub fn waits_for_key(&self) -> bool { self.players.waited }
+}
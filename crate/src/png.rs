@@ -0,0 +1,119 @@
+//! Decoding PNG images, enabled by the `png` feature.
+//!
+//! Only what screenshots use is supported: 8 bits per channel or palette index, no interlacing.
+//! Chunk checksums are not verified, the zlib checksum of the image data is.
+
+use crate::CompareError;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// Color types of the IHDR chunk.
+const GRAY: u8 = 0;
+const RGB: u8 = 2;
+const INDEXED: u8 = 3;
+const GRAY_ALPHA: u8 = 4;
+const RGBA: u8 = 6;
+
+/// Returns `true` if the data starts like a PNG file.
+pub(crate) fn is_png(data: &[u8]) -> bool { data.starts_with(&SIGNATURE) }
+
+fn u32_at(data: &[u8], at: usize) -> Result<u32, CompareError> {
+    let bytes = data.get(at..at + 4).ok_or(CompareError::Corrupted)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = i16::from(left) + i16::from(up) - i16::from(up_left);
+    let distance = |value: u8| (estimate - i16::from(value)).abs();
+    if distance(left) <= distance(up) && distance(left) <= distance(up_left) {
+        left
+    } else if distance(up) <= distance(up_left) {
+        up
+    } else {
+        up_left
+    }
+}
+
+/// Decodes a PNG image into its width, height and the 0xRRGGBB color of every pixel in
+/// row-major order. Transparency is ignored.
+pub(crate) fn decode(data: &[u8]) -> Result<(usize, usize, Vec<u32>), CompareError> {
+    if !is_png(data) {
+        return Err(CompareError::UnknownFormat);
+    }
+
+    let mut header = None;
+    let mut palette = vec![];
+    let mut compressed = vec![];
+    let mut at = SIGNATURE.len();
+    loop {
+        let length = u32_at(data, at)? as usize;
+        let kind = data.get(at + 4..at + 8).ok_or(CompareError::Corrupted)?;
+        let end = (at + 8).checked_add(length).ok_or(CompareError::Corrupted)?;
+        let body = data.get(at + 8..end).ok_or(CompareError::Corrupted)?;
+        match kind {
+            b"IHDR" if body.len() == 13 => header = Some(body),
+            b"PLTE" => {
+                let rgb = |color: &[u8]| u32::from_be_bytes([0, color[0], color[1], color[2]]);
+                palette = body.chunks_exact(3).map(rgb).collect();
+            }
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        // Length, kind, body and CRC.
+        at = end + 4;
+    }
+
+    let header = header.ok_or(CompareError::Corrupted)?;
+    let (width, height) = (u32_at(header, 0)? as usize, u32_at(header, 4)? as usize);
+    let (depth, color_type, interlaced) = (header[8], header[9], header[12] != 0);
+    let channels = match color_type {
+        GRAY | INDEXED => 1,
+        GRAY_ALPHA => 2,
+        RGB => 3,
+        RGBA => 4,
+        _ => return Err(CompareError::Corrupted),
+    };
+    if depth != 8 || interlaced {
+        return Err(CompareError::Unsupported);
+    }
+
+    let stride = width.checked_mul(channels).ok_or(CompareError::Corrupted)?;
+    let size = stride.checked_add(1).and_then(|line| line.checked_mul(height)).ok_or(CompareError::Corrupted)?;
+    let raw = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&compressed, size)
+        .map_err(|_| CompareError::Corrupted)?;
+    if raw.len() != size {
+        return Err(CompareError::Corrupted);
+    }
+
+    // Undo the filter of every row, each row starts with its filter type.
+    let mut pixels = vec![0u8; stride * height];
+    for y in 0..height {
+        let filter = raw[y * (stride + 1)];
+        let line = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        for x in 0..stride {
+            let left = if x >= channels { pixels[y * stride + x - channels] } else { 0 };
+            let up = if y > 0 { pixels[(y - 1) * stride + x] } else { 0 };
+            let up_left = if x >= channels && y > 0 { pixels[(y - 1) * stride + x - channels] } else { 0 };
+            let predicted = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(CompareError::Corrupted),
+            };
+            pixels[y * stride + x] = line[x].wrapping_add(predicted);
+        }
+    }
+
+    let colors = pixels
+        .chunks_exact(channels)
+        .map(|pixel| match color_type {
+            GRAY | GRAY_ALPHA => Ok(u32::from(pixel[0]) * 0x01_0101),
+            INDEXED => palette.get(usize::from(pixel[0])).copied().ok_or(CompareError::Corrupted),
+            _ => Ok(u32::from_be_bytes([0, pixel[0], pixel[1], pixel[2]])),
+        })
+        .collect::<Result<Vec<u32>, CompareError>>()?;
+    Ok((width, height, colors))
+}
@@ -0,0 +1,101 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Variant, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH};
+
+const THUMBNAIL_SIZE: usize = THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT;
+
+/// Runs many ROMs for a short while to produce animated thumbnails for a ROM gallery.
+///
+/// All ROMs share one headless emulator and one buffer of captured thumbnails, so a gallery needs
+/// a single instance no matter how many ROMs it shows.
+#[wasm_bindgen]
+pub struct PreviewRunner {
+    emulator: Emulator,
+    roms: Vec<(Vec<u8>, Variant)>,
+    // How long every ROM runs and how many frames pass between two captures.
+    frames: u32,
+    capture_every: u32,
+    // `captures_per_rom` thumbnails of every ROM, one after another.
+    captures: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl PreviewRunner {
+    #[wasm_bindgen(constructor)]
+    pub fn new(frames: u32, capture_every: u32) -> PreviewRunner {
+        PreviewRunner {
+            emulator: Emulator::headless(),
+            roms: vec![],
+            frames,
+            capture_every: capture_every.max(1),
+            captures: vec![],
+        }
+    }
+
+    /// Queues a ROM and returns its index. ROMs that do not fit the variant are kept with empty
+    /// thumbnails.
+    pub fn add(&mut self, rom: &[u8], variant: Variant) -> usize {
+        self.roms.push((rom.to_vec(), variant));
+        self.roms.len() - 1
+    }
+
+    /// Number of queued ROMs.
+    pub fn len(&self) -> usize { self.roms.len() }
+
+    pub fn is_empty(&self) -> bool { self.roms.is_empty() }
+
+    /// Thumbnails captured for every ROM.
+    pub fn captures_per_rom(&self) -> usize { (self.frames / self.capture_every) as usize }
+
+    /// Runs every queued ROM from a fresh machine and captures its thumbnails. The display is
+    /// captured after every `capture_every` frames.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{PreviewRunner, Variant, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH};
+    /// let mut runner = PreviewRunner::new(300, 60);
+    /// // i := font 0, draw it, loop
+    /// let first = runner.add(&[0xa0, 0x00, 0xd0, 0x05, 0x12, 0x04], Variant::Chip8);
+    /// runner.add(&[0x12, 0x00], Variant::XoChip);
+    /// runner.run();
+    /// assert_eq!(runner.captures_per_rom(), 5);
+    /// assert_eq!(runner.thumbnails(first).len(), 5 * THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT);
+    /// assert!(runner.thumbnails(first).iter().any(|&planes| planes != 0));
+    /// ```
+    pub fn run(&mut self) {
+        let per_rom = self.captures_per_rom() * THUMBNAIL_SIZE;
+        self.captures.clear();
+        self.captures.resize(per_rom * self.roms.len(), 0);
+
+        for (index, (rom, variant)) in self.roms.iter().enumerate() {
+            self.emulator.set_variant(*variant);
+            if self.emulator.load(rom).is_err() {
+                continue;
+            }
+            let captures = &mut self.captures[index * per_rom..(index + 1) * per_rom];
+            let mut thumbnails = captures.chunks_exact_mut(THUMBNAIL_SIZE);
+            for frame in 1..=self.frames {
+                self.emulator.tick_frame();
+                if frame % self.capture_every == 0 {
+                    if let Some(thumbnail) = thumbnails.next() {
+                        self.emulator.write_thumbnail(thumbnail);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Thumbnails of a ROM, `captures_per_rom` images of `THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT`
+    /// bytes packed like `Emulator::pixels_packed`. Empty before `run` or for unknown indices.
+    pub fn thumbnails(&self, index: usize) -> Vec<u8> {
+        let per_rom = self.captures_per_rom() * THUMBNAIL_SIZE;
+        self.captures.get(index * per_rom..(index + 1) * per_rom).map(<[u8]>::to_vec).unwrap_or_default()
+    }
+
+    /// Drops the queued ROMs and their thumbnails, keeping the buffers for the next batch.
+    pub fn clear(&mut self) {
+        self.roms.clear();
+        self.captures.clear();
+    }
+}
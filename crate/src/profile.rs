@@ -0,0 +1,138 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Instruction};
+
+/// Host time the core spent in each of its parts, in milliseconds measured with the time source
+/// (see `Emulator::set_time_source`). Frontends compare it with the time their own rendering
+/// takes to find out where a slow frame comes from.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PerfCounters {
+    /// Frames the counters cover.
+    pub frames: u32,
+    /// Fetching and decoding opcodes.
+    pub decode_ms: f64,
+    /// Executing every instruction but DXYN.
+    pub execute_ms: f64,
+    /// Drawing sprites with DXYN.
+    pub draw_ms: f64,
+    /// Generating samples with `fill_audio`.
+    pub audio_ms: f64,
+}
+
+impl PerfCounters {
+    fn add(&mut self, other: &PerfCounters) {
+        self.frames += other.frames;
+        self.decode_ms += other.decode_ms;
+        self.execute_ms += other.execute_ms;
+        self.draw_ms += other.draw_ms;
+        self.audio_ms += other.audio_ms;
+    }
+}
+
+/// Part of the core time is attributed to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Phase {
+    Decode,
+    Execute,
+    Draw,
+    Audio,
+}
+
+impl Phase {
+    /// Phase the instruction is executed in.
+    pub(crate) fn of(instruction: Instruction) -> Phase {
+        match instruction {
+            Instruction::Draw { .. } => Phase::Draw,
+            _ => Phase::Execute,
+        }
+    }
+}
+
+/// Sums up the time spent in every phase of the current frame, the last frame and all frames.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Profiler {
+    enabled: bool,
+    current: PerfCounters,
+    last_frame: PerfCounters,
+    total: PerfCounters,
+}
+
+impl Profiler {
+    pub(crate) fn is_enabled(&self) -> bool { self.enabled }
+
+    pub(crate) fn record(&mut self, phase: Phase, ms: f64) {
+        let counter = match phase {
+            Phase::Decode => &mut self.current.decode_ms,
+            Phase::Execute => &mut self.current.execute_ms,
+            Phase::Draw => &mut self.current.draw_ms,
+            Phase::Audio => &mut self.current.audio_ms,
+        };
+        *counter += ms.max(0.0);
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        self.last_frame = PerfCounters { frames: 1, ..std::mem::take(&mut self.current) };
+        self.total.add(&self.last_frame);
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Starts or stops measuring the host time spent decoding, executing, drawing and generating
+    /// audio. Enabling it forgets earlier measurements. Every measurement reads the time source
+    /// twice, so leave it off when not looking at the numbers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, MockClock};
+    /// let clock = MockClock::default();
+    /// // Every reading of the clock takes 1ms.
+    /// clock.set_step(1.0);
+    /// let mut emulator = Emulator::headless();
+    /// emulator.set_time_source(Box::new(clock));
+    /// emulator.set_cycles_per_frame(2);
+    /// emulator.set_profiling(true);
+    /// // v0 := 1, sprite v0 v0 1
+    /// emulator.load(&[0x60, 0x01, 0xd0, 0x01]).unwrap();
+    /// emulator.tick_frame();
+    /// let frame = emulator.last_frame_perf();
+    /// assert_eq!((frame.frames, frame.decode_ms, frame.execute_ms, frame.draw_ms), (1, 2.0, 1.0, 1.0));
+    /// assert_eq!(emulator.perf_counters(), frame);
+    /// ```
+    pub fn set_profiling(&mut self, enabled: bool) { self.profiler = Profiler { enabled, ..Profiler::default() }; }
+
+    pub fn is_profiling(&self) -> bool { self.profiler.is_enabled() }
+
+    /// Time spent in the last completed frame. Audio generated since then is counted towards
+    /// the next frame.
+    pub fn last_frame_perf(&self) -> PerfCounters { self.profiler.last_frame }
+
+    /// Time spent in all frames completed since profiling was enabled.
+    pub fn perf_counters(&self) -> PerfCounters { self.profiler.total }
+}
+
+impl Emulator {
+    /// Reads the time source if profiling is enabled, to start a measurement.
+    pub(crate) fn profile_start(&self) -> Option<f64> {
+        if self.profiler.is_enabled() {
+            Some(self.time.now_ms())
+        } else {
+            None
+        }
+    }
+
+    /// Attributes the time since `started` to the phase and returns the time the next phase
+    /// starts at.
+    pub(crate) fn profile(&mut self, phase: Phase, started: Option<f64>) -> Option<f64> {
+        let started = started?;
+        let now = self.time.now_ms();
+        self.profiler.record(phase, now - started);
+        Some(now)
+    }
+}
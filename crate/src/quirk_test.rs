@@ -0,0 +1,141 @@
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Quirks, CHIP8_DISPLAY_WIDTH};
+
+// Instructions a probe may take, enough for every probe to reach its final loop.
+const PROBE_CYCLES: usize = 100;
+
+// Bytes of the code `probe` appends to every probe program.
+const EPILOGUE_BYTES: u16 = 10;
+
+// Assembles a probe: `code` leaves 1 in V0 if the quirk is exhibited and 0 otherwise, and the
+// epilogue clears the display and draws the digit in V0 at the top left corner. `data` follows
+// the epilogue at `base + code.len() * 2 + EPILOGUE_BYTES`.
+fn probe(base: u16, code: &[u16], data: &[u8]) -> Vec<u8> {
+    let epilogue = base + code.len() as u16 * 2;
+    let mut words = code.to_vec();
+    // clear, i := hex v0, vb := 0, sprite vb vb 5, loop: jump loop
+    words.extend_from_slice(&[0x00e0, 0xf029, 0x6b00, 0xdbb5, 0x1000 | (epilogue + 8)]);
+
+    let mut program: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+    program.extend_from_slice(data);
+    program
+}
+
+/// Test programs of the quirks in the field order of `Quirks`, assembled for the load address
+/// `base`. Each one draws 1 if its quirk is exhibited and 0 otherwise.
+pub(crate) fn quirk_probes(base: u16) -> [Vec<u8>; 8] {
+    let data = |code: &[u16]| base + code.len() as u16 * 2 + EPILOGUE_BYTES;
+
+    // v1 := 1, v2 := 4, v1 >>= v2, v0 := 0, if v1 == 2 then v0 := 1
+    let shift = [0x6101, 0x6204, 0x8126, 0x6000, 0x4102, 0x6001];
+
+    // Loads V0 twice from data 0, 1. Without the quirk both loads read the first byte.
+    let load_code = [0xa000, 0xf065, 0xf065];
+    let load = [0xa000 | data(&load_code), 0xf065, 0xf065];
+
+    // vX := 4, v0 := 0, jump0 target, target: v0 := 0, jump epilogue, v0 := 1
+    let target = base + 6;
+    let register = (target >> 8) & 0xf;
+    let jump = [0x6004 | register << 8, 0x6000, 0xb000 | target, 0x6000, 0x1000 | (base + 12), 0x6001];
+
+    // Draws a byte at x 60 and at x 0, they only collide if the first one wrapped.
+    let wrap_code = [0x613c, 0x6200, 0xa000, 0xd121, 0x6100, 0xd121, 0x80f0];
+    let wrap = [0x613c, 0x6200, 0xa000 | data(&wrap_code), 0xd121, 0x6100, 0xd121, 0x80f0];
+
+    // Draws two rows twice, VF is 2 if collided rows are counted.
+    let rows_code = [0x6100, 0xa000, 0xd112, 0xd112, 0x6000, 0x4f02, 0x6001];
+    let rows = [0x6100, 0xa000 | data(&rows_code), 0xd112, 0xd112, 0x6000, 0x4f02, 0x6001];
+
+    // Draws a pixel at x 0, scrolls right and draws a pixel at x 2.
+    let scroll_code = [0x6100, 0xa000, 0xd111, 0x00fb, 0xa000, 0xd111, 0x80f0];
+    let scroll_data = data(&scroll_code);
+    let scroll = [0x6100, 0xa000 | scroll_data, 0xd111, 0x00fb, 0xa000 | (scroll_data + 1), 0xd111, 0x80f0];
+
+    // vF := 1, v1 := 0, v1 |= v2, v0 := 0, if vF == 0 then v0 := 1
+    let logic = [0x6f01, 0x6100, 0x8121, 0x6000, 0x4f00, 0x6001];
+
+    // Draws DXY0 twice, which only collides if it draws anything.
+    let large_code = [0x6100, 0xa000, 0xd110, 0xd110, 0x6000, 0x3f00, 0x6001];
+    let large = [0x6100, 0xa000 | data(&large_code), 0xd110, 0xd110, 0x6000, 0x3f00, 0x6001];
+
+    [
+        probe(base, &shift, &[]),
+        probe(base, &load, &[0x00, 0x01]),
+        probe(base, &jump, &[]),
+        probe(base, &wrap, &[0xff]),
+        probe(base, &rows, &[0xff, 0xff]),
+        probe(base, &scroll, &[0x80, 0x20]),
+        probe(base, &logic, &[]),
+        probe(base, &large, &[0x80]),
+    ]
+}
+
+impl Emulator {
+    // Runs a probe under the variant and quirks of this emulator and reads the digit it draws.
+    fn run_probe(&self, program: &[u8]) -> Option<u8> {
+        let mut emulator = Emulator::headless();
+        emulator.set_variant(self.variant);
+        emulator.set_quirks(self.quirks);
+        // The probes are a few instructions long.
+        let _ = emulator.load(program);
+        for _ in 0..PROBE_CYCLES {
+            emulator.tick();
+        }
+        if emulator.fault.is_some() {
+            return None;
+        }
+
+        let font = self.variant.font();
+        let glyph: Vec<u8> = (0..5)
+            .map(|row| (0..8).fold(0, |byte, column| byte << 1 | u8::from(emulator.gfx[row][column] != 0)))
+            .collect();
+        let lit_elsewhere = emulator
+            .gfx
+            .iter()
+            .enumerate()
+            .any(|(row, pixels)| pixels[if row < 5 { 8 } else { 0 }..CHIP8_DISPLAY_WIDTH].iter().any(|&p| p != 0));
+        (0..2u8).find(|&digit| !lit_elsewhere && glyph[..] == font[usize::from(digit) * 5..][..5])
+    }
+
+    /// Runs a built-in test program for every quirk headlessly under the current variant and
+    /// quirks and reports the behavior it reads back from the display, to verify a
+    /// configuration the way the Timendus quirks test ROM does without shipping that ROM. A quirk
+    /// whose test cannot run because the variant lacks the instructions it needs (e.g. the
+    /// scrolling of `lores_scroll_halved`) is reported as not exhibited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Quirks, Variant};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_variant(Variant::XoChip);
+    /// assert_eq!(emulator.detect_quirks(), Quirks::for_variant(Variant::XoChip));
+    /// let quirks = Quirks { jump_uses_vx: true, collision_counts_rows: true, ..emulator.quirks() };
+    /// emulator.set_quirks(quirks);
+    /// assert_eq!(emulator.detect_quirks(), quirks);
+    /// ```
+    pub fn detect_quirks(&self) -> Quirks {
+        let [shift, load, jump, wrap, rows, scroll, logic, large] = quirk_probes(self.variant.load_address() as u16);
+        let exhibited = |program: Vec<u8>| self.run_probe(&program) == Some(1);
+        Quirks {
+            shift_uses_vy: exhibited(shift),
+            load_store_increments_i: exhibited(load),
+            jump_uses_vx: exhibited(jump),
+            wrap_sprites: exhibited(wrap),
+            collision_counts_rows: exhibited(rows),
+            lores_scroll_halved: self.variant.has_scrolling() && exhibited(scroll),
+            vf_reset: exhibited(logic),
+            large_sprites: exhibited(large),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `detect_quirks` as a plain JS object.
+    #[wasm_bindgen(js_name = detect_quirks)]
+    pub fn detect_quirks_js(&self) -> JsValue { crate::utils::to_js(&self.detect_quirks()) }
+}
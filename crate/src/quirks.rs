@@ -0,0 +1,220 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Variant};
+
+/// Behaviors that differ between CHIP-8 interpreters and that games rely on.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct Quirks {
+    /// 8XY6/8XYE shift VY and store the result in VX instead of shifting VX in place.
+    pub shift_uses_vy: bool,
+    /// FX55/FX65 leave I pointing past the last register that was stored or loaded.
+    pub load_store_increments_i: bool,
+    /// BNNN jumps to NNN + VX (X being the highest nibble of NNN) instead of NNN + V0.
+    pub jump_uses_vx: bool,
+    /// Sprites crossing the screen edge wrap around instead of being clipped.
+    pub wrap_sprites: bool,
+    /// DXYN sets VF to the number of sprite rows that collided or were cut off at the bottom edge,
+    /// like SCHIP 1.1 in hi-res mode, instead of 1. Some SCHIP games check VF > 1. The emulator has
+    /// no hi-res mode, so the flag applies to every sprite.
+    pub collision_counts_rows: bool,
+    /// Scroll instructions move the low resolution display by half the distance, as legacy SCHIP
+    /// measures it in hi-res pixels. Octo scrolls by the full distance.
+    pub lores_scroll_halved: bool,
+    /// 8XY1/8XY2/8XY3 reset VF to 0 after the OR, AND or XOR, like the COSMAC VIP interpreter.
+    pub vf_reset: bool,
+    /// DXY0 draws a 16x16 sprite like SCHIP and XO-CHIP instead of nothing. Its 32 bytes are read
+    /// row by row, the left half of every row first, which is the one order both use.
+    pub large_sprites: bool,
+}
+
+/// Names of the quirk flags as they appear in `Quirks` and in the opcode reference.
+pub const SHIFT_USES_VY: &str = "shift_uses_vy";
+pub const LOAD_STORE_INCREMENTS_I: &str = "load_store_increments_i";
+pub const JUMP_USES_VX: &str = "jump_uses_vx";
+pub const WRAP_SPRITES: &str = "wrap_sprites";
+pub const COLLISION_COUNTS_ROWS: &str = "collision_counts_rows";
+pub const LORES_SCROLL_HALVED: &str = "lores_scroll_halved";
+pub const VF_RESET: &str = "vf_reset";
+pub const LARGE_SPRITES: &str = "large_sprites";
+
+// Flag names in the bit order of `Quirks::to_bits`.
+const NAMES: [&str; 8] = [
+    SHIFT_USES_VY,
+    LOAD_STORE_INCREMENTS_I,
+    JUMP_USES_VX,
+    WRAP_SPRITES,
+    COLLISION_COUNTS_ROWS,
+    LORES_SCROLL_HALVED,
+    VF_RESET,
+    LARGE_SPRITES,
+];
+
+#[wasm_bindgen]
+impl Quirks {
+    /// Returns the quirks the given variant is usually run with.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Quirks, Variant};
+    /// assert!(Quirks::for_variant(Variant::Vip2K).vf_reset);
+    /// assert!(!Quirks::for_variant(Variant::XoChip).vf_reset);
+    ///
+    /// let mut emulator = Emulator::new();
+    /// for vf_reset in [false, true] {
+    ///     emulator.reset();
+    ///     emulator.set_quirks(Quirks { vf_reset, ..Quirks::for_variant(Variant::Chip8) });
+    ///     // vF := 1, v1 := 6, v2 := 3, v1 &= v2
+    ///     emulator.load(&[0x6f, 0x01, 0x61, 0x06, 0x62, 0x03, 0x81, 0x22]).unwrap();
+    ///     emulator.run_cycles(4);
+    ///     assert_eq!((emulator.v(1), emulator.v(0xf)), (2, if vf_reset { 0 } else { 1 }));
+    /// }
+    ///
+    /// // SCHIP games in low resolution run as CHIP-8 with the sprite quirks of SCHIP.
+    /// emulator.reset();
+    /// emulator.set_quirks(Quirks { large_sprites: true, collision_counts_rows: true, ..emulator.quirks() });
+    /// // i := font 0, drw v0, v0, 0 twice, every one of the 16 rows collides the second time
+    /// emulator.load(&[0xa0, 0x00, 0xd0, 0x00, 0xd0, 0x00]).unwrap();
+    /// emulator.run_cycles(3);
+    /// assert_eq!(emulator.v(0xf), 16);
+    /// ```
+    pub fn for_variant(variant: Variant) -> Quirks {
+        match variant {
+            // The behavior most CHIP-8 games written for later interpreters expect.
+            Variant::Chip8 => Quirks {
+                shift_uses_vy: false,
+                load_store_increments_i: false,
+                jump_uses_vx: false,
+                wrap_sprites: false,
+                collision_counts_rows: false,
+                lores_scroll_halved: false,
+                vf_reset: false,
+                large_sprites: false,
+            },
+            // CHIPOS advances I past the registers like the COSMAC VIP interpreter.
+            Variant::Dream6800 => Quirks {
+                shift_uses_vy: false,
+                load_store_increments_i: true,
+                jump_uses_vx: false,
+                wrap_sprites: false,
+                collision_counts_rows: false,
+                lores_scroll_halved: false,
+                vf_reset: false,
+                large_sprites: false,
+            },
+            // The VIP interpreter, which CHIP-8X is based on.
+            Variant::Chip8X | Variant::Vip2K => Quirks {
+                shift_uses_vy: true,
+                load_store_increments_i: true,
+                jump_uses_vx: false,
+                wrap_sprites: false,
+                collision_counts_rows: false,
+                lores_scroll_halved: false,
+                vf_reset: true,
+                large_sprites: false,
+            },
+            // Octo's defaults.
+            Variant::XoChip => Quirks {
+                shift_uses_vy: true,
+                load_store_increments_i: true,
+                jump_uses_vx: false,
+                wrap_sprites: true,
+                collision_counts_rows: false,
+                lores_scroll_halved: false,
+                vf_reset: false,
+                large_sprites: true,
+            },
+        }
+    }
+}
+
+impl Quirks {
+    /// Packs the flags into a byte in declaration order, bit 0 being `shift_uses_vy`.
+    pub(crate) fn to_bits(self) -> u8 {
+        [
+            self.shift_uses_vy,
+            self.load_store_increments_i,
+            self.jump_uses_vx,
+            self.wrap_sprites,
+            self.collision_counts_rows,
+            self.lores_scroll_halved,
+            self.vf_reset,
+            self.large_sprites,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (bit, &set)| bits | (set as u8) << bit)
+    }
+
+    pub(crate) fn from_bits(bits: u8) -> Quirks {
+        let set = |bit: u8| bits & (1 << bit) != 0;
+        Quirks {
+            shift_uses_vy: set(0),
+            load_store_increments_i: set(1),
+            jump_uses_vx: set(2),
+            wrap_sprites: set(3),
+            collision_counts_rows: set(4),
+            lores_scroll_halved: set(5),
+            vf_reset: set(6),
+            large_sprites: set(7),
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks { Quirks::for_variant(Variant::default()) }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns the quirks the emulator runs with.
+    pub fn quirks(&self) -> Quirks { self.quirks }
+
+    /// Overrides the quirks of the current variant until the variant is changed.
+    ///
+    /// Quirks can be changed while a program runs, without a reset. Every flag is read when an
+    /// instruction it affects executes, so the change applies from the next instruction on: the
+    /// shift, load/store and jump quirks to the next 8XY6/8XYE, FX55/FX65 and BNNN, the sprite
+    /// quirks to the next DXYN and the scroll quirk to the next scroll. Nothing is recomputed, so
+    /// registers, I and the display keep what the previous quirks produced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Quirks, Variant};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_quirks(Quirks { wrap_sprites: true, ..Quirks::for_variant(Variant::Chip8) });
+    /// assert!(emulator.quirks().wrap_sprites);
+    /// ```
+    pub fn set_quirks(&mut self, quirks: Quirks) { self.quirks = quirks; }
+
+    /// Sets one quirk flag by its name in `Quirks`, e.g. to flip it from a settings panel while a
+    /// game runs, see `set_quirks`. Returns `false` if no flag has the name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // v1 := 3, v2 := 4, v1 >>= v2, v1 >>= v2
+    /// emulator.load(&[0x61, 0x03, 0x62, 0x04, 0x81, 0x26, 0x81, 0x26]).unwrap();
+    /// emulator.run_cycles(3);
+    /// assert_eq!(emulator.v(1), 1);
+    /// assert!(emulator.set_quirk("shift_uses_vy", true));
+    /// emulator.tick();
+    /// assert_eq!(emulator.v(1), 2);
+    /// assert!(!emulator.set_quirk("shift_vy", true));
+    /// ```
+    pub fn set_quirk(&mut self, name: &str, enabled: bool) -> bool {
+        match NAMES.iter().position(|&flag| flag == name) {
+            Some(bit) => {
+                let bits = self.quirks.to_bits() & !(1 << bit);
+                self.quirks = Quirks::from_bits(bits | u8::from(enabled) << bit);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Variant, CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH};
+
+pub const DEFAULT_BACKGROUND: u32 = 0x0a84a0;
+pub const DEFAULT_FOREGROUND: u32 = 0xffffff;
+pub const DEFAULT_SCALE: u32 = 8;
+
+// Brightness multipliers of the overlays.
+const GRID_SHADE: f32 = 0.6;
+const SCANLINE_SHADE: f32 = 0.7;
+
+/// Overlays applied when the display is rendered to RGBA.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DisplayStyle {
+    /// Darken the last row and column of every pixel block, drawing a grid between pixels.
+    pub grid: bool,
+    /// Darken every other output row like a CRT.
+    pub scanlines: bool,
+    /// Strength of the barrel distortion, 0 keeps the image flat.
+    pub curvature: f32,
+}
+
+#[wasm_bindgen]
+impl DisplayStyle {
+    #[wasm_bindgen(constructor)]
+    pub fn new(grid: bool, scanlines: bool, curvature: f32) -> DisplayStyle {
+        DisplayStyle { grid, scanlines, curvature: curvature.max(0.0) }
+    }
+}
+
+/// How the colors of the two XO-CHIP planes combine where both are lit.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compositing {
+    /// Use the fourth palette color, Octo's blend color.
+    #[default]
+    Blend,
+    /// The second plane covers the first one, the fourth palette color is unused.
+    Overlay,
+}
+
+/// Converts the framebuffer to an RGBA image.
+#[derive(Clone, Debug)]
+pub struct Renderer {
+    style: DisplayStyle,
+    scale: u32,
+    // 0xRRGGBB colors indexed by the plane bitmask of a pixel.
+    palette: [u32; 4],
+    compositing: Compositing,
+    // Blend every display pixel with its previous color to soften flashes.
+    reduce_flashes: bool,
+    // Color of every display pixel in the last rendered image.
+    cells: Vec<u32>,
+    buffer: Vec<u8>,
+}
+
+impl Default for Renderer {
+    fn default() -> Renderer {
+        Renderer {
+            style: DisplayStyle::default(),
+            scale: DEFAULT_SCALE,
+            palette: [DEFAULT_BACKGROUND, DEFAULT_FOREGROUND, DEFAULT_FOREGROUND, DEFAULT_FOREGROUND],
+            compositing: Compositing::default(),
+            reduce_flashes: false,
+            cells: vec![],
+            buffer: vec![],
+        }
+    }
+}
+
+impl Renderer {
+    pub fn set_style(&mut self, style: DisplayStyle) { self.style = style; }
+
+    pub fn set_scale(&mut self, scale: u32) { self.scale = scale.clamp(1, 32); }
+
+    pub fn set_color(&mut self, planes: usize, color: u32) {
+        if let Some(entry) = self.palette.get_mut(planes) {
+            *entry = color & 0xff_ffff;
+        }
+    }
+
+    pub fn palette(&self) -> [u32; 4] { self.palette }
+
+    pub fn set_reduce_flashes(&mut self, reduce: bool) { self.reduce_flashes = reduce; }
+
+    pub fn set_compositing(&mut self, compositing: Compositing) { self.compositing = compositing; }
+
+    pub fn compositing(&self) -> Compositing { self.compositing }
+
+    /// Color of a pixel with the given plane bitmask.
+    pub fn color(&self, planes: u8) -> u32 {
+        match (planes & 0b11, self.compositing) {
+            (0b11, Compositing::Overlay) => self.palette[2],
+            (planes, _) => self.palette[planes as usize],
+        }
+    }
+
+    pub fn width(&self) -> usize { CHIP8_DISPLAY_WIDTH * self.scale as usize }
+
+    pub fn height(&self) -> usize { CHIP8_DISPLAY_HEIGHT * self.scale as usize }
+
+    pub fn buffer(&self) -> &[u8] { &self.buffer }
+
+    pub(crate) fn buffer_mut(&mut self) -> &mut [u8] { &mut self.buffer }
+
+    pub fn scale(&self) -> u32 { self.scale }
+
+    /// Renders the packed display (see `Emulator::pixels_packed`) into the internal buffer.
+    pub fn render(&mut self, packed: &[u8]) -> &[u8] {
+        let colors: Vec<u32> = packed.iter().map(|&planes| self.color(planes)).collect();
+        self.render_colors(&colors)
+    }
+
+    /// Renders the 0xRRGGBB color of every display pixel in row-major order, bypassing the palette.
+    pub fn render_colors(&mut self, colors: &[u32]) -> &[u8] {
+        let (width, height) = (self.width(), self.height());
+        let scale = self.scale as usize;
+        self.buffer.resize(width * height * 4, 0);
+        self.update_cells(colors);
+
+        for oy in 0..height {
+            for ox in 0..width {
+                let (sx, sy) = match self.source(ox, oy, width, height) {
+                    Some(source) => source,
+                    None => {
+                        self.put(ox, oy, width, 0, 1.0);
+                        continue;
+                    }
+                };
+
+                let color = self.cells[(sy / scale) * CHIP8_DISPLAY_WIDTH + sx / scale];
+
+                let mut shade = 1.0;
+                if self.style.grid && scale > 2 && (sx % scale == scale - 1 || sy % scale == scale - 1) {
+                    shade *= GRID_SHADE;
+                }
+                if self.style.scanlines && oy % 2 == 1 {
+                    shade *= SCANLINE_SHADE;
+                }
+
+                self.put(ox, oy, width, color, shade);
+            }
+        }
+
+        &self.buffer
+    }
+
+    // Colors every display pixel. A pixel that toggles every frame settles on the average of its
+    // two colors when flashes are reduced, a steady change still reaches its color within a few frames.
+    fn update_cells(&mut self, colors: &[u32]) {
+        let cells = CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT;
+        let fresh = self.cells.len() != cells;
+        self.cells.resize(cells, 0);
+
+        for index in 0..cells {
+            let target = colors.get(index).map_or(self.palette[0], |&color| color & 0xff_ffff);
+            let previous = self.cells[index];
+            self.cells[index] = if self.reduce_flashes && !fresh { blend(previous, target) } else { target };
+        }
+    }
+
+    // Maps an output pixel to the undistorted image, `None` when it falls outside of it.
+    fn source(&self, ox: usize, oy: usize, width: usize, height: usize) -> Option<(usize, usize)> {
+        if self.style.curvature <= 0.0 {
+            return Some((ox, oy));
+        }
+
+        let u = (ox as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+        let v = (oy as f32 + 0.5) / height as f32 * 2.0 - 1.0;
+        let factor = 1.0 + self.style.curvature * (u * u + v * v);
+        let (u, v) = (u * factor, v * factor);
+        if u.abs() >= 1.0 || v.abs() >= 1.0 {
+            return None;
+        }
+
+        // Rounding can land a hair past the last pixel.
+        let sx = (((u + 1.0) / 2.0 * width as f32) as usize).min(width - 1);
+        let sy = (((v + 1.0) / 2.0 * height as f32) as usize).min(height - 1);
+        Some((sx, sy))
+    }
+
+    fn put(&mut self, x: usize, y: usize, width: usize, color: u32, shade: f32) {
+        let offset = (y * width + x) * 4;
+        let channel = |shift: u32| (((color >> shift) & 0xff) as f32 * shade) as u8;
+        self.buffer[offset] = channel(16);
+        self.buffer[offset + 1] = channel(8);
+        self.buffer[offset + 2] = channel(0);
+        self.buffer[offset + 3] = 0xff;
+    }
+}
+
+// Per-channel average of two 0xRRGGBB colors.
+fn blend(a: u32, b: u32) -> u32 {
+    (0..3).map(|channel| {
+        let shift = channel * 8;
+        ((((a >> shift) & 0xff) + ((b >> shift) & 0xff)) / 2) << shift
+    })
+    .sum()
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Set the overlays applied by `render_rgba`.
+    pub fn set_display_style(&mut self, style: DisplayStyle) { self.renderer.set_style(style); }
+
+    /// Set how many output pixels `render_rgba` uses per display pixel (1 to 32).
+    pub fn set_display_scale(&mut self, scale: u32) { self.renderer.set_scale(scale); }
+
+    /// Set the 0xRRGGBB colors of unlit and lit pixels.
+    pub fn set_colors(&mut self, background: u32, foreground: u32) {
+        self.renderer.set_color(0, background);
+        for planes in 1..4 {
+            self.renderer.set_color(planes, foreground);
+        }
+    }
+
+    /// Choose how pixels lit in both XO-CHIP planes are colored.
+    pub fn set_plane_compositing(&mut self, compositing: Compositing) {
+        self.renderer.set_compositing(compositing);
+    }
+
+    pub fn plane_compositing(&self) -> Compositing { self.renderer.compositing() }
+
+    /// Width of the `render_rgba` image in pixels.
+    pub fn rgba_width(&self) -> usize { self.renderer.width() }
+
+    /// Height of the `render_rgba` image in pixels.
+    pub fn rgba_height(&self) -> usize { self.renderer.height() }
+
+    /// Renders the display to RGBA and returns pointer to the `rgba_width() * rgba_height() * 4`
+    /// bytes, ready to be wrapped into `ImageData`. CHIP-8X programs are shown in the colors of the
+    /// color board instead of the palette. The overlay (see `overlay_text`) is drawn on top.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{DisplayStyle, Emulator};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_display_scale(4);
+    /// emulator.set_display_style(DisplayStyle::new(true, true, 0.0));
+    /// emulator.render_rgba();
+    /// assert_eq!(emulator.rgba().len(), 64 * 4 * 32 * 4 * 4);
+    ///
+    /// // Curvature maps every output pixel to one inside the display at any scale.
+    /// for scale in 1..=8 {
+    ///     emulator.set_display_scale(scale);
+    ///     for curvature in [0.038, 0.1, 0.33, 1.0] {
+    ///         emulator.set_display_style(DisplayStyle::new(false, false, curvature));
+    ///         emulator.render_rgba();
+    ///     }
+    /// }
+    /// ```
+    pub fn render_rgba(&mut self) -> *const u8 {
+        if self.variant == Variant::Chip8X {
+            let colors = self.chip8x_colors();
+            self.renderer.render_colors(&colors);
+        } else {
+            let packed = self.pixels_packed();
+            self.renderer.render(&packed);
+        }
+        self.composite_overlay();
+        self.renderer.buffer().as_ptr()
+    }
+}
+
+impl Emulator {
+    /// Image produced by the last `render_rgba` call.
+    pub fn rgba(&self) -> &[u8] { self.renderer.buffer() }
+
+    /// Set the 0xRRGGBB colors of the XO-CHIP planes: background, first plane, second plane and
+    /// both planes (see `Compositing`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Compositing, Emulator, Variant};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_variant(Variant::XoChip);
+    /// emulator.set_plane_palette([0x000000, 0xff0000, 0x0000ff, 0xff00ff]);
+    /// emulator.set_display_scale(1);
+    /// // i := font 0, v1 := 2, plane 1, sprite v0 v0 1, plane 2, sprite v1 v0 1
+    /// emulator.load(&[0xa0, 0x00, 0x61, 0x02, 0xf1, 0x01, 0xd0, 0x01, 0xf2, 0x01, 0xd1, 0x01]).unwrap();
+    /// for _ in 0..6 {
+    ///     emulator.tick();
+    /// }
+    /// // Pixels 0 and 1 are in the first plane, 2 and 3 in both and 4 and 5 in the second.
+    /// let colors = |emulator: &mut Emulator| -> Vec<[u8; 3]> {
+    ///     emulator.render_rgba();
+    ///     emulator.rgba().chunks(4).take(7).map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect()
+    /// };
+    /// let (red, blue, magenta, black) = ([0xff, 0, 0], [0, 0, 0xff], [0xff, 0, 0xff], [0, 0, 0]);
+    /// assert_eq!(colors(&mut emulator), [red, red, magenta, magenta, blue, blue, black]);
+    /// emulator.set_plane_compositing(Compositing::Overlay);
+    /// assert_eq!(colors(&mut emulator), [red, red, blue, blue, blue, blue, black]);
+    /// ```
+    pub fn set_plane_palette(&mut self, palette: [u32; 4]) {
+        for (planes, &color) in palette.iter().enumerate() {
+            self.renderer.set_color(planes, color);
+        }
+    }
+
+    pub fn plane_palette(&self) -> [u32; 4] { self.renderer.palette() }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Takes the palette of `set_plane_palette` as an array, missing colors are left unchanged.
+    #[wasm_bindgen(js_name = set_plane_palette)]
+    pub fn set_plane_palette_js(&mut self, palette: &[u32]) {
+        for (planes, &color) in palette.iter().take(4).enumerate() {
+            self.renderer.set_color(planes, color);
+        }
+    }
+
+    #[wasm_bindgen(js_name = plane_palette)]
+    pub fn plane_palette_js(&self) -> Vec<u32> { self.renderer.palette().to_vec() }
+}
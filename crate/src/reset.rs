@@ -0,0 +1,104 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH};
+
+/// Parts of the machine `reset_with` resets.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResetOptions {
+    /// PC, I, V0 to VF, the stack, a pending FX0A and the fault that stopped the program.
+    pub registers: bool,
+    /// The delay and the sound timer.
+    pub timers: bool,
+    /// The display and the CHIP-8X color board.
+    pub display: bool,
+    /// Memory is refilled with the font and the `MemoryInit` pattern, unloading the program.
+    pub memory: bool,
+}
+
+#[wasm_bindgen]
+impl ResetOptions {
+    /// Every part, plus the frame counters, the debugging records and the boot state, like
+    /// `reset`.
+    pub fn full() -> ResetOptions { ResetOptions { registers: true, timers: true, display: true, memory: true } }
+
+    /// Restarts the loaded program: registers, timers and display, but not memory.
+    pub fn warm() -> ResetOptions { ResetOptions { memory: false, ..ResetOptions::full() } }
+
+    pub fn registers_only() -> ResetOptions { ResetOptions { registers: true, ..ResetOptions::none() } }
+
+    pub fn display_only() -> ResetOptions { ResetOptions { display: true, ..ResetOptions::none() } }
+
+    fn none() -> ResetOptions { ResetOptions { registers: false, timers: false, display: false, memory: false } }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Resets the chosen parts of the machine and keeps the rest, e.g. restarts a program that
+    /// modified itself without reloading it. Only a full reset (see `ResetOptions::full`)
+    /// restarts the frame counters, clears the records kept for debugging and restores the boot
+    /// state.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, ResetOptions};
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 1, i := font 0, sprite v0 v0 5
+    /// emulator.load(&[0x60, 0x01, 0xa0, 0x00, 0xd0, 0x05]).unwrap();
+    /// emulator.tick_frame();
+    /// emulator.reset_with(ResetOptions::registers_only());
+    /// assert_eq!((emulator.pc(), emulator.v(0)), (0x200, 0));
+    /// assert!(emulator.pixels().any(|pixel| pixel.is_lit()));
+    /// emulator.reset_with(ResetOptions::warm());
+    /// assert!(emulator.pixels().all(|pixel| !pixel.is_lit()));
+    /// assert_eq!(emulator.memory()[0x200], 0x60);
+    /// ```
+    pub fn reset_with(&mut self, options: ResetOptions) {
+        self.journal.clear();
+        if options.registers {
+            self.pc = self.variant.load_address();
+            self.i = self.variant.load_address();
+            self.opcode = 0;
+            self.stack = vec![];
+            self.v = [0; 16];
+            self.planes = 1;
+            self.fault = None;
+            self.keypad.cancel_wait();
+        }
+        if options.timers {
+            self.delay_timer = 0;
+            self.sound_timer = 0;
+        }
+        if options.display {
+            self.gfx = [[0; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT];
+            self.draw_flag = false;
+            self.color_board.clear();
+        }
+        if options.memory {
+            self.memory = Emulator::prepare_memory(self.variant, self.memory_init);
+            self.rom_hash = 0;
+            self.drawn_sprites.clear();
+        }
+        if options != ResetOptions::full() {
+            return;
+        }
+
+        self.frame = 0;
+        self.frame_cycle = 0;
+        self.total_cycles = 0;
+        self.history.clear();
+        self.events.clear();
+        self.machine_calls.clear();
+        self.players.clear_stats();
+        self.heatmap.clear();
+        self.last_debug_state = None;
+        self.last_executed = None;
+
+        if let Some(state) = self.boot_state.take() {
+            // The boot state was validated when it was set.
+            let _ = self.load_state(&state);
+            self.boot_state = Some(state);
+        }
+    }
+}
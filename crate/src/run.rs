@@ -0,0 +1,74 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Interrupts `run_cycles` and the frames run by `tick_frame` without borrowing the emulator, so it
+/// can be used from frame listeners and other callbacks that run while the emulator is busy.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct StopHandle {
+    requested: Arc<AtomicBool>,
+}
+
+#[wasm_bindgen]
+impl StopHandle {
+    /// Asks the running, or else the next, `run_cycles` call or frame to return after the current
+    /// instruction. A frame cut short this way is finished by the next `tick_frame`. `run_loop`
+    /// forgets stops requested before it was called.
+    pub fn request_stop(&self) { self.requested.store(true, Ordering::SeqCst); }
+}
+
+impl StopHandle {
+    pub(crate) fn take(&self) -> bool { self.requested.swap(false, Ordering::SeqCst) }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns a handle sharing the stop flag of this emulator.
+    pub fn stop_handle(&self) -> StopHandle { self.stop.clone() }
+
+    /// Same as `stop_handle().request_stop()`. Callbacks that run while the emulator is busy, like
+    /// frame listeners, have to use a `StopHandle` instead.
+    pub fn request_stop(&self) { self.stop.request_stop(); }
+
+    /// Executes up to `max_cycles` instructions with frames starting and ending on the way, e.g. to
+    /// fast-forward. Returns how many instructions ran, fewer when a stop was requested, the
+    /// program faulted or the emulator is paused.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.load(&[0x12, 0x00]).unwrap();
+    /// assert_eq!(emulator.run_cycles(25), 25);
+    /// assert_eq!(emulator.frame(), 2);
+    ///
+    /// emulator.stop_handle().request_stop();
+    /// assert_eq!(emulator.run_cycles(1_000_000), 0);
+    ///
+    /// // Turbo frames stop too.
+    /// emulator.set_cycles_per_frame(1_000_000);
+    /// emulator.request_stop();
+    /// emulator.tick_frame();
+    /// assert_eq!((emulator.frame(), emulator.total_cycles()), (2, 26));
+    ///
+    /// // A stop nobody took does not cancel a later run.
+    /// emulator.set_cycles_per_frame(10);
+    /// emulator.request_stop();
+    /// emulator.run_loop();
+    /// assert_eq!(emulator.run_cycles(25), 25);
+    /// ```
+    pub fn run_cycles(&mut self, max_cycles: u32) -> u32 {
+        for executed in 0..max_cycles {
+            if self.stop.take() || self.paused || self.fault.is_some() {
+                return executed;
+            }
+            self.cycle();
+        }
+        max_cycles
+    }
+}
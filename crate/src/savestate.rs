@@ -0,0 +1,483 @@
+use wasm_bindgen::prelude::*;
+
+use crate::fault::{Fault, History};
+use crate::keypad::Keypad;
+use crate::utils::{Fnv1a, XorShift};
+use crate::chip8x::{ColorBoard, COLOR_BOARD_BYTES};
+use crate::{Emulator, Gfx, Quirks, Variant, CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH, PLANES, STACK_DEPTH};
+
+const MAGIC: &[u8; 4] = b"C8SS";
+
+/// Format written by `save_state`. Every state starts with `C8SS` and this version byte, older
+/// versions are migrated when they are loaded:
+///
+/// * 1 - CPU, timers, stack, display and memory.
+/// * 2 - Adds the quirks and the CHIP-8X color board. Version 1 states get the quirks of their
+///   variant and a blank color board.
+/// * 3 - Adds `total_cycles`, which is 0 after loading an older state.
+/// * 4 - Adds the display planes selected by FN01, older states select the first plane.
+/// * 5 - Uses the last quirk bit for `large_sprites`, older states get the flag of their variant.
+/// * 6 - Adds the rate of `set_timer_hz`, 0 for the rate of the variant. Older states run at the
+///   rate of their variant.
+pub const STATE_VERSION: u8 = 6;
+
+/// Reason a savestate could not be restored.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateError {
+    /// The data does not start with the savestate signature.
+    BadMagic,
+    /// The savestate was written by an unknown format version.
+    UnsupportedVersion,
+    /// The savestate was written by a newer version of the emulator, which has to be updated.
+    NewerVersion,
+    /// The data ended before the savestate was complete.
+    Truncated,
+    /// A field holds a value the emulator cannot represent.
+    Corrupted,
+    /// The slot index is out of range.
+    InvalidSlot,
+    /// Nothing was saved to the slot yet.
+    EmptySlot,
+}
+
+struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) { self.data.push(value); }
+
+    fn u16(&mut self, value: u16) { self.data.extend_from_slice(&value.to_le_bytes()); }
+
+    fn u32(&mut self, value: u32) { self.data.extend_from_slice(&value.to_le_bytes()); }
+
+    fn u64(&mut self, value: u64) { self.data.extend_from_slice(&value.to_le_bytes()); }
+
+    fn bytes(&mut self, value: &[u8]) { self.data.extend_from_slice(value); }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if self.data.len() < len {
+            return Err(StateError::Truncated);
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> { Ok(self.bytes(1)?[0]) }
+
+    fn u16(&mut self) -> Result<u16, StateError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, StateError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, StateError> {
+        let mut value = [0; 8];
+        value.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_le_bytes(value))
+    }
+}
+
+fn variant_to_byte(variant: Variant) -> u8 {
+    match variant {
+        Variant::Chip8 => 0,
+        Variant::XoChip => 1,
+        Variant::Dream6800 => 2,
+        Variant::Chip8X => 3,
+        Variant::Vip2K => 4,
+    }
+}
+
+fn variant_from_byte(byte: u8) -> Result<Variant, StateError> {
+    match byte {
+        0 => Ok(Variant::Chip8),
+        1 => Ok(Variant::XoChip),
+        2 => Ok(Variant::Dream6800),
+        3 => Ok(Variant::Chip8X),
+        4 => Ok(Variant::Vip2K),
+        _ => Err(StateError::Corrupted),
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Serializes the whole machine state (CPU, memory, display and timers).
+    ///
+    /// Sound and keyboard devices are not part of the state.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.load(&[0x60, 0x2a, 0x12, 0x02]).unwrap();
+    /// let state = emulator.save_state();
+    /// emulator.tick();
+    /// emulator.load_state(&state).unwrap();
+    /// assert_eq!(emulator.save_state(), state);
+    /// ```
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = Writer { data: Vec::with_capacity(self.memory.len() + 2 * 1024) };
+
+        writer.bytes(MAGIC);
+        writer.u8(STATE_VERSION);
+        writer.u8(variant_to_byte(self.variant));
+        writer.u16(self.pc as u16);
+        writer.u16(self.i as u16);
+        writer.u16(self.opcode);
+        writer.bytes(&self.v);
+        writer.u8(self.delay_timer);
+        writer.u8(self.sound_timer);
+        writer.u8(self.draw_flag as u8);
+        writer.u64(self.frame);
+        writer.u32(self.rom_hash);
+        writer.u16(self.stack.len() as u16);
+        for &address in &self.stack {
+            writer.u16(address as u16);
+        }
+        for row in self.gfx.iter() {
+            writer.bytes(row);
+        }
+        writer.u32(self.memory.len() as u32);
+        writer.bytes(&self.memory);
+        // Version 2
+        writer.u8(self.quirks.to_bits());
+        writer.bytes(&self.color_board.to_bytes());
+        // Version 3
+        writer.u64(self.total_cycles);
+        // Version 4
+        writer.u8(self.planes);
+        // Version 6
+        writer.u8(self.timer_hz.unwrap_or(0));
+
+        writer.data
+    }
+
+    /// Restores a state produced by `save_state` of this or an older version of the emulator (see
+    /// `STATE_VERSION`).
+    ///
+    /// The emulator is left untouched if the state is invalid.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - A slice of bytes (u8) returned by `save_state`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, StateError};
+    /// let mut emulator = Emulator::new();
+    /// let mut state = emulator.save_state();
+    /// // A version 1 state ends after the memory.
+    /// let mut old = state[..state.len() - 268].to_vec();
+    /// old[4] = 1;
+    /// assert_eq!(emulator.load_state(&old), Ok(()));
+    /// state[4] = 99;
+    /// assert_eq!(emulator.load_state(&state), Err(StateError::NewerVersion));
+    /// ```
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let mut reader = Reader { data: state };
+
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(StateError::BadMagic);
+        }
+        let version = reader.u8()?;
+        if version > STATE_VERSION {
+            return Err(StateError::NewerVersion);
+        }
+        if version == 0 {
+            return Err(StateError::UnsupportedVersion);
+        }
+
+        let variant = variant_from_byte(reader.u8()?)?;
+        let pc = reader.u16()? as usize;
+        let i = reader.u16()? as usize;
+        let opcode = reader.u16()?;
+        let mut v = [0; 16];
+        v.copy_from_slice(reader.bytes(16)?);
+        let delay_timer = reader.u8()?;
+        let sound_timer = reader.u8()?;
+        let draw_flag = reader.u8()? != 0;
+        let frame = reader.u64()?;
+        let rom_hash = reader.u32()?;
+        let stack_len = reader.u16()? as usize;
+        if stack_len > STACK_DEPTH {
+            return Err(StateError::Corrupted);
+        }
+        let mut stack = Vec::with_capacity(stack_len);
+        for _ in 0..stack_len {
+            stack.push(reader.u16()? as usize);
+        }
+        let mut gfx: Gfx = [[0; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT];
+        for row in gfx.iter_mut() {
+            row.copy_from_slice(reader.bytes(CHIP8_DISPLAY_WIDTH)?);
+        }
+        let memory_len = reader.u32()? as usize;
+        if memory_len != variant.memory_size() {
+            return Err(StateError::Corrupted);
+        }
+        let memory = reader.bytes(memory_len)?.to_vec();
+        let (quirks, color_board) = if version >= 2 {
+            let mut quirks = Quirks::from_bits(reader.u8()?);
+            if version < 5 {
+                quirks.large_sprites = Quirks::for_variant(variant).large_sprites;
+            }
+            let color_board = ColorBoard::from_bytes(reader.bytes(COLOR_BOARD_BYTES)?);
+            (quirks, color_board.ok_or(StateError::Corrupted)?)
+        } else {
+            (Quirks::for_variant(variant), ColorBoard::default())
+        };
+        let total_cycles = if version >= 3 { reader.u64()? } else { 0 };
+        let planes = if version >= 4 { reader.u8()? } else { 1 };
+        if planes & !PLANES != 0 {
+            return Err(StateError::Corrupted);
+        }
+        let timer_hz = match if version >= 6 { reader.u8()? } else { 0 } {
+            0 => None,
+            hz @ (50 | 60) => Some(hz),
+            _ => return Err(StateError::Corrupted),
+        };
+        if !reader.data.is_empty() {
+            return Err(StateError::Corrupted);
+        }
+
+        self.variant = variant;
+        self.pc = pc;
+        self.i = i;
+        self.opcode = opcode;
+        self.v = v;
+        self.delay_timer = delay_timer;
+        self.sound_timer = sound_timer;
+        self.draw_flag = draw_flag;
+        self.frame = frame;
+        self.rom_hash = rom_hash;
+        self.stack = stack;
+        self.gfx = gfx;
+        self.planes = planes;
+        self.timer_hz = timer_hz;
+        self.memory = memory;
+        self.quirks = quirks;
+        self.color_board.restore(color_board);
+        self.total_cycles = total_cycles;
+        self.frame_cycle = 0;
+        self.journal.clear();
+        self.history.clear();
+        self.fault = None;
+
+        if self.sound_timer == 0 && self.audio.is_active() {
+            self.audio.stop();
+        }
+
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Makes `reset` restore the given savestate instead of the pristine machine, e.g. to restart
+    /// from a specific level over and over again.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - A slice of bytes (u8) returned by `save_state`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.load(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    /// emulator.tick_frame();
+    /// let boot = emulator.save_state();
+    /// emulator.set_boot_state(&boot).unwrap();
+    /// emulator.tick_frame();
+    /// emulator.reset();
+    /// assert_eq!(emulator.save_state(), boot);
+    /// ```
+    pub fn set_boot_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        // Validate on a scratch emulator so the running machine is not touched.
+        Emulator::headless().load_state(state)?;
+        self.boot_state = Some(state.to_vec());
+        Ok(())
+    }
+
+    /// Makes `reset` restore the pristine machine again.
+    pub fn clear_boot_state(&mut self) { self.boot_state = None; }
+
+    /// Returns `true` if `reset` restores a boot state.
+    pub fn has_boot_state(&self) -> bool { self.boot_state.is_some() }
+}
+
+/// In-memory copy of the machine state.
+///
+/// Unlike `save_state` it skips serialization and validation, and restoring it reuses the
+/// emulator's buffers, so it is cheap enough to take every frame (e.g. for rollback).
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    variant: Variant,
+    pc: usize,
+    i: usize,
+    opcode: u16,
+    v: [u8; 16],
+    delay_timer: u8,
+    sound_timer: u8,
+    draw_flag: bool,
+    frame: u64,
+    frame_cycle: u32,
+    total_cycles: u64,
+    rom_hash: u32,
+    rng: u32,
+    stack: Vec<usize>,
+    gfx: Gfx,
+    planes: u8,
+    memory: Vec<u8>,
+    keypad: Keypad,
+    color_board: ColorBoard,
+    history: History,
+    fault: Option<Fault>,
+}
+
+#[wasm_bindgen]
+impl Snapshot {
+    /// Index of the frame that runs next after the snapshot is restored.
+    pub fn frame(&self) -> u64 { self.frame }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Takes an in-memory snapshot of the machine state.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.load(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    /// let snapshot = emulator.snapshot();
+    /// let hash = emulator.state_hash();
+    /// emulator.tick_frame();
+    /// assert_ne!(emulator.state_hash(), hash);
+    /// emulator.restore_snapshot(&snapshot);
+    /// assert_eq!(emulator.state_hash(), hash);
+    ///
+    /// // The random number generator is part of the snapshot, so CXNN repeats after a restore.
+    /// emulator.set_random_seed(7);
+    /// // v0 := random 0xff
+    /// emulator.load(&[0xc0, 0xff]).unwrap();
+    /// let snapshot = emulator.snapshot();
+    /// emulator.tick();
+    /// let first = emulator.v(0);
+    /// emulator.restore_snapshot(&snapshot);
+    /// emulator.tick();
+    /// assert_eq!(emulator.v(0), first);
+    /// ```
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            variant: self.variant,
+            pc: self.pc,
+            i: self.i,
+            opcode: self.opcode,
+            v: self.v,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            draw_flag: self.draw_flag,
+            frame: self.frame,
+            frame_cycle: self.frame_cycle,
+            total_cycles: self.total_cycles,
+            rom_hash: self.rom_hash,
+            rng: self.rng.state(),
+            stack: self.stack.clone(),
+            gfx: self.gfx,
+            planes: self.planes,
+            memory: self.memory.clone(),
+            keypad: self.keypad.clone(),
+            color_board: self.color_board.clone(),
+            history: self.history,
+            fault: self.fault,
+        }
+    }
+
+    /// Restores a snapshot taken with `snapshot`.
+    ///
+    /// The beep follows the restored sound timer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, EventKind};
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 2, sound := v0, loop
+    /// emulator.load(&[0x60, 0x02, 0xf0, 0x18, 0x12, 0x04]).unwrap();
+    /// emulator.tick();
+    /// emulator.tick();
+    /// let beeping = emulator.snapshot();
+    /// emulator.tick_frame();
+    /// emulator.tick_frame();
+    /// emulator.restore_snapshot(&beeping);
+    /// let events: Vec<EventKind> = emulator.take_events().into_iter().map(|event| event.kind).collect();
+    /// assert_eq!(events, [EventKind::SoundStart, EventKind::SoundStop, EventKind::SoundStart]);
+    /// ```
+    pub fn restore_snapshot(&mut self, snapshot: &Snapshot) {
+        self.variant = snapshot.variant;
+        self.pc = snapshot.pc;
+        self.i = snapshot.i;
+        self.opcode = snapshot.opcode;
+        self.v = snapshot.v;
+        self.delay_timer = snapshot.delay_timer;
+        self.sound_timer = snapshot.sound_timer;
+        self.draw_flag = snapshot.draw_flag;
+        self.frame = snapshot.frame;
+        self.frame_cycle = snapshot.frame_cycle;
+        self.total_cycles = snapshot.total_cycles;
+        self.rom_hash = snapshot.rom_hash;
+        self.rng = XorShift::new(snapshot.rng);
+        self.stack.clone_from(&snapshot.stack);
+        self.gfx = snapshot.gfx;
+        self.planes = snapshot.planes;
+        self.memory.clone_from(&snapshot.memory);
+        self.keypad.clone_from(&snapshot.keypad);
+        self.color_board.restore(snapshot.color_board.clone());
+        self.history = snapshot.history;
+        self.fault = snapshot.fault;
+        self.journal.clear();
+        self.sync_audio();
+    }
+
+    /// Hash of everything that affects emulation, used to detect desyncs between peers.
+    ///
+    /// It does not allocate, so it can be called every frame.
+    pub fn state_hash(&self) -> u32 {
+        let mut hasher = Fnv1a::default();
+
+        hasher.write(&[variant_to_byte(self.variant), self.delay_timer, self.sound_timer, self.planes]);
+        hasher.write(&(self.pc as u16).to_le_bytes());
+        hasher.write(&(self.i as u16).to_le_bytes());
+        hasher.write(&self.v);
+        hasher.write(&self.frame.to_le_bytes());
+        hasher.write(&self.frame_cycle.to_le_bytes());
+        hasher.write(&self.rng.state().to_le_bytes());
+        for &address in &self.stack {
+            hasher.write(&(address as u16).to_le_bytes());
+        }
+        for row in self.gfx.iter() {
+            hasher.write(row);
+        }
+        hasher.write(&self.memory);
+        self.color_board.hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
@@ -0,0 +1,309 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+/// Frames a single `Scheduler::advance` call catches up by default.
+pub const MAX_CATCH_UP_FRAMES: u32 = 4;
+
+/// What happens to time beyond the catch-up budget, e.g. after a throttled background tab wakes up.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CatchUpPolicy {
+    /// Drop the lost time and continue from the present, the program appears to freeze briefly.
+    #[default]
+    SkipAhead,
+    /// Keep the lost time and run it off over the following calls at the budget per call, so no
+    /// emulated time is lost.
+    Spread,
+}
+
+// Fraction of a frame that still counts as a whole frame, so that time handed out in exact
+// fractions of a frame (e.g. a 144 Hz display) is not lost to rounding.
+const FRAME_TOLERANCE: f64 = 1e-6;
+
+/// Work to do for the elapsed time.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Schedule {
+    /// Frames to run with `tick_frame`.
+    pub frames: u32,
+    /// Instructions those frames execute.
+    pub cycles: u32,
+    /// Whether at least one frame ran, so a new picture should be presented.
+    pub render: bool,
+    /// Audio samples to produce with `fill_audio`.
+    pub samples: u32,
+}
+
+/// What a worker loop driven by `Emulator::run_loop` does next.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LoopStep {
+    /// Milliseconds until the next frame is due, 0 if frames are waiting to be run.
+    pub delay_ms: f64,
+    /// Whether no frame ran because the program is idle in low-power mode (see
+    /// `Emulator::set_low_power`), so the loop may sleep until the next key change.
+    pub idle: bool,
+}
+
+/// Turns wall-clock time into frames and audio samples.
+///
+/// Samples are counted from the number of frames run, so sound never drifts from the picture and
+/// rounding errors never accumulate: after N frames exactly `N * sample_rate / frame_rate` samples
+/// were requested.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct Scheduler {
+    frame_rate: u32,
+    cycles_per_frame: u32,
+    sample_rate: u32,
+    max_catch_up: u32,
+    policy: CatchUpPolicy,
+    // Time not yet turned into a frame, and frames and samples handed out so far.
+    pending_ms: f64,
+    frames: u64,
+    samples: u64,
+}
+
+#[wasm_bindgen]
+impl Scheduler {
+    #[wasm_bindgen(constructor)]
+    pub fn new(frame_rate: u32, cycles_per_frame: u32, sample_rate: u32) -> Scheduler {
+        Scheduler {
+            frame_rate: frame_rate.max(1),
+            cycles_per_frame,
+            sample_rate,
+            max_catch_up: MAX_CATCH_UP_FRAMES,
+            policy: CatchUpPolicy::default(),
+            pending_ms: 0.0,
+            frames: 0,
+            samples: 0,
+        }
+    }
+
+    /// Accounts `elapsed_ms` of real time and returns what to run for it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Scheduler;
+    /// let mut scheduler = Scheduler::new(60, 10, 48_000);
+    /// let schedule = scheduler.advance(1000.0 / 60.0);
+    /// assert_eq!((schedule.frames, schedule.cycles, schedule.samples), (1, 10, 800));
+    /// // Half a frame later nothing is due.
+    /// assert!(!scheduler.advance(1000.0 / 120.0).render);
+    /// ```
+    pub fn advance(&mut self, elapsed_ms: f64) -> Schedule {
+        if elapsed_ms.is_finite() && elapsed_ms > 0.0 {
+            self.pending_ms += elapsed_ms;
+        }
+
+        let interval = 1000.0 / f64::from(self.frame_rate);
+        let mut frames = (self.pending_ms / interval + FRAME_TOLERANCE) as u64;
+        if frames > u64::from(self.max_catch_up) {
+            frames = u64::from(self.max_catch_up);
+            match self.policy {
+                // Resuming after a stall: skip the lost time instead of fast-forwarding through it.
+                CatchUpPolicy::SkipAhead => self.pending_ms = 0.0,
+                CatchUpPolicy::Spread => self.pending_ms -= frames as f64 * interval,
+            }
+        } else {
+            self.pending_ms -= frames as f64 * interval;
+        }
+
+        self.frames += frames;
+        let samples = self.frames * u64::from(self.sample_rate) / u64::from(self.frame_rate);
+        let schedule = Schedule {
+            frames: frames as u32,
+            cycles: frames as u32 * self.cycles_per_frame,
+            render: frames > 0,
+            samples: (samples - self.samples) as u32,
+        };
+        self.samples = samples;
+        schedule
+    }
+
+    /// Like `advance`, for a caller driven by the refresh of a display at `display_refresh_hz`,
+    /// e.g. `requestAnimationFrame`. The elapsed time is rounded to whole refreshes, so timestamp
+    /// jitter does not make 60 Hz displays alternate between 0 and 2 frames. Faster or slower
+    /// displays get the frames (and timer ticks) of the variant's frame rate on average: fractions
+    /// of a frame carry over to the next call instead of every refresh running one frame.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Scheduler;
+    /// let mut scheduler = Scheduler::new(60, 10, 48_000);
+    /// // A second of 144 Hz refreshes with jittery timestamps.
+    /// let frames: u32 = (0..144)
+    ///     .map(|refresh| scheduler.advance_display(144.0, if refresh % 2 == 0 { 6.5 } else { 7.4 }).frames)
+    ///     .sum();
+    /// assert_eq!(frames, 60);
+    /// // 60 Hz with jitter runs a frame on every refresh.
+    /// assert!([16.2, 17.1, 16.4, 17.0].iter().all(|&ms| scheduler.advance_display(60.0, ms).frames == 1));
+    /// ```
+    pub fn advance_display(&mut self, display_refresh_hz: f64, elapsed_ms: f64) -> Schedule {
+        let positive = |value: f64| value.is_finite() && value > 0.0;
+        if !positive(display_refresh_hz) || !positive(elapsed_ms) {
+            return self.advance(elapsed_ms);
+        }
+        let refresh_ms = 1000.0 / display_refresh_hz;
+        let refreshes = (elapsed_ms / refresh_ms).round().max(1.0);
+        self.advance(refreshes * refresh_ms)
+    }
+
+    /// Sets how many frames a single call runs at most (at least 1) and what happens to the rest.
+    pub fn set_catch_up(&mut self, max_frames: u32, policy: CatchUpPolicy) {
+        self.max_catch_up = max_frames.max(1);
+        self.policy = policy;
+    }
+
+    /// Changes the instructions per frame of the following schedules.
+    pub fn set_cycles_per_frame(&mut self, cycles: u32) { self.cycles_per_frame = cycles; }
+
+    /// Frames handed out since the scheduler was created.
+    pub fn total_frames(&self) -> u64 { self.frames }
+
+    /// Forgets the time that did not make a frame yet, e.g. after the emulator was paused.
+    pub fn reset_clock(&mut self) { self.pending_ms = 0.0; }
+
+    /// Milliseconds of real time until the next frame is due, 0 if a frame is due already.
+    pub fn next_frame_in_ms(&self) -> f64 { (1000.0 / f64::from(self.frame_rate) - self.pending_ms).max(0.0) }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Creates a scheduler for the current frame rate (see `frame_rate`) and cycles per frame.
+    pub fn scheduler(&self, sample_rate: u32) -> Scheduler {
+        Scheduler::new(self.frame_rate(), self.cycles_per_frame, sample_rate)
+    }
+
+    /// Runs the frames due after `elapsed_ms` of real time and returns how many ran. A call never
+    /// runs more than the catch-up budget (see `set_catch_up`), so a tab that was throttled for
+    /// seconds does not execute millions of instructions at once. Time passing while the emulator
+    /// is paused is forgotten.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{CatchUpPolicy, Emulator};
+    /// let mut emulator = Emulator::new();
+    /// emulator.load(&[0x12, 0x00]).unwrap();
+    /// assert_eq!(emulator.advance_ms(1000.0 / 60.0), 1);
+    /// emulator.set_catch_up(2, CatchUpPolicy::Spread);
+    /// assert_eq!(emulator.advance_ms(5000.0), 2);
+    /// assert_eq!(emulator.advance_ms(0.0), 2);
+    /// emulator.set_catch_up(2, CatchUpPolicy::SkipAhead);
+    /// assert_eq!(emulator.advance_ms(0.0), 2);
+    /// assert_eq!(emulator.advance_ms(0.0), 0);
+    /// ```
+    pub fn advance_ms(&mut self, elapsed_ms: f64) -> u32 { self.run_scheduled(|clock| clock.advance(elapsed_ms)) }
+
+    /// Like `advance_ms` for a caller driven by a display refreshing at `display_refresh_hz`, see
+    /// `Scheduler::advance_display`. The timers keep counting at the frame rate of the variant
+    /// whatever the refresh rate is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 60, delay := v0, loop
+    /// emulator.load(&[0x60, 0x3c, 0xf0, 0x15, 0x12, 0x04]).unwrap();
+    /// let frames: u32 = (0..60).map(|_| emulator.advance_display(120.0, 1000.0 / 120.0)).sum();
+    /// assert_eq!(frames, 30);
+    /// assert_eq!(emulator.delay_timer(), 30);
+    /// ```
+    pub fn advance_display(&mut self, display_refresh_hz: f64, elapsed_ms: f64) -> u32 {
+        self.run_scheduled(|clock| clock.advance_display(display_refresh_hz, elapsed_ms))
+    }
+
+    /// Runs the frames due since the previous call, measured with the time source (see
+    /// `set_time_source`), and returns how many ran. The first call only starts the clock.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, MockClock};
+    /// let clock = MockClock::default();
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_time_source(Box::new(clock.clone()));
+    /// emulator.load(&[0x12, 0x00]).unwrap();
+    /// assert_eq!(emulator.advance(), 0);
+    /// clock.advance(50.0);
+    /// assert_eq!(emulator.advance(), 3);
+    /// ```
+    pub fn advance(&mut self) -> u32 {
+        let now = self.time.now_ms();
+        let elapsed = self.last_advance_ms.map_or(0.0, |last| now - last);
+        self.last_advance_ms = Some(now);
+        self.advance_ms(elapsed)
+    }
+
+    /// Runs the frames due like `advance` and returns in how many milliseconds the next frame is
+    /// due, minus the time the frames just took. A loop in a worker, where
+    /// `requestAnimationFrame` may not exist, sleeps that long with `setTimeout` instead of
+    /// polling. A delay of 0 means frames are waiting to be run, e.g. while
+    /// `CatchUpPolicy::Spread` runs off a backlog, so call again right away. In low-power mode an
+    /// idle program returns at once with `idle` set, and the loop can wait for a key change
+    /// instead of a timer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, MockClock};
+    /// let clock = MockClock::default();
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_time_source(Box::new(clock.clone()));
+    /// emulator.load(&[0x12, 0x00]).unwrap();
+    /// let round = |ms: f64| (ms * 100.0).round() / 100.0;
+    /// assert_eq!(round(emulator.run_loop().delay_ms), 16.67);
+    /// clock.advance(10.0);
+    /// assert_eq!(round(emulator.run_loop().delay_ms), 6.67);
+    /// clock.advance(10.0);
+    /// assert_eq!(round(emulator.run_loop().delay_ms), 13.33);
+    /// assert_eq!(emulator.frame(), 1);
+    ///
+    /// // halt: jump halt
+    /// emulator.set_low_power(true);
+    /// assert!(emulator.run_loop().idle);
+    /// clock.advance(1000.0);
+    /// assert!(emulator.run_loop().idle);
+    /// assert_eq!(emulator.frame(), 1);
+    /// emulator.set_low_power(false);
+    /// clock.advance(20.0);
+    /// assert!(!emulator.run_loop().idle);
+    /// assert_eq!(emulator.frame(), 2);
+    /// ```
+    pub fn run_loop(&mut self) -> LoopStep {
+        // A stop requested since the last call was meant for something else.
+        self.stop.take();
+        let frames = self.advance();
+        let idle = frames == 0 && self.low_power && self.is_idle();
+        let spent = self.last_advance_ms.map_or(0.0, |started| self.time.now_ms() - started);
+        LoopStep { delay_ms: (self.clock.next_frame_in_ms() - spent).max(0.0), idle }
+    }
+
+    /// Sets the catch-up budget of `advance_ms` in frames per call and what happens to the rest.
+    pub fn set_catch_up(&mut self, max_frames: u32, policy: CatchUpPolicy) {
+        self.clock.set_catch_up(max_frames, policy);
+    }
+}
+
+impl Emulator {
+    // Runs the frames the clock schedules and returns how many ran.
+    fn run_scheduled<F: FnOnce(&mut Scheduler) -> Schedule>(&mut self, schedule: F) -> u32 {
+        if self.paused || (self.low_power && self.is_idle()) {
+            self.clock.reset_clock();
+            return 0;
+        }
+
+        self.clock.frame_rate = self.frame_rate();
+        self.clock.cycles_per_frame = self.cycles_per_frame;
+        let schedule = schedule(&mut self.clock);
+        for _ in 0..schedule.frames {
+            self.tick_frame();
+        }
+        schedule.frames
+    }
+}
@@ -0,0 +1,221 @@
+//! Compact text payloads of the current moment for "share this exact moment" links, enabled by
+//! the `share` feature.
+//!
+//! A payload is deflated and written in URL-safe base64 without padding. Before compression it
+//! is `C8SH` followed by `SHARE_VERSION`, the ROM hash and the state of the random number
+//! generator as little endian `u32`, the length of the savestate as `u32` and the savestate,
+//! which includes the quirks, then a byte telling whether an input
+//! recording follows. A recording is the id of its macro and its number of frames as `u32` and
+//! the keypad bitmask of every frame as `u16`.
+
+use std::convert::TryFrom;
+
+use wasm_bindgen::prelude::*;
+
+use crate::utils::XorShift;
+use crate::{Emulator, MAX_MACRO_FRAMES};
+
+const MAGIC: &[u8; 4] = b"C8SH";
+
+/// Version of the payload format written by `export_shareable_link_payload`. Version 1 had the
+/// quirk bits where version 2 has the random number generator.
+pub const SHARE_VERSION: u8 = 2;
+
+// Nothing larger than a 64K XO-CHIP savestate and the longest macro is ever inflated.
+const MAX_PAYLOAD_SIZE: usize = 0x10000 + 0x1000 + 2 * MAX_MACRO_FRAMES + 64;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Reason a shared payload could not be imported. The emulator is left untouched.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShareError {
+    /// The text is not URL-safe base64.
+    InvalidEncoding,
+    /// The data fails to inflate or does not start with the payload signature.
+    BadMagic,
+    /// The payload was written by an unknown format version.
+    UnsupportedVersion,
+    /// The data ended before the payload was complete.
+    Truncated,
+    /// The savestate of the payload cannot be restored.
+    InvalidState,
+    /// The recording is longer than `MAX_MACRO_FRAMES`.
+    TooLong,
+}
+
+fn encode_base64(data: &[u8]) -> String {
+    let mut text = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (at, &byte)| bits | u32::from(byte) << (16 - 8 * at));
+        for digit in 0..=chunk.len() {
+            text.push(char::from(ALPHABET[(bits >> (18 - 6 * digit) & 0x3f) as usize]));
+        }
+    }
+    text
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, ShareError> {
+    let digits = text
+        .trim()
+        .bytes()
+        .map(|byte| ALPHABET.iter().position(|&digit| digit == byte).map(|value| value as u32))
+        .collect::<Option<Vec<u32>>>()
+        .ok_or(ShareError::InvalidEncoding)?;
+    if digits.len() % 4 == 1 {
+        return Err(ShareError::InvalidEncoding);
+    }
+
+    let mut data = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        let bits = chunk.iter().enumerate().fold(0, |bits, (at, &digit)| bits | digit << (18 - 6 * at));
+        for byte in 0..chunk.len() - 1 {
+            data.push((bits >> (16 - 8 * byte)) as u8);
+        }
+    }
+    Ok(data)
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], ShareError> {
+    if data.len() < len {
+        return Err(ShareError::Truncated);
+    }
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+fn take_u32(data: &mut &[u8]) -> Result<u32, ShareError> {
+    let bytes = take(data, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+// Contents of a payload.
+struct Shared {
+    rom_hash: u32,
+    rng: u32,
+    state: Vec<u8>,
+    recording: Option<(u32, Vec<u16>)>,
+}
+
+fn parse(payload: &str) -> Result<Shared, ShareError> {
+    let compressed = decode_base64(payload)?;
+    let inflated = miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, MAX_PAYLOAD_SIZE)
+        .map_err(|_| ShareError::BadMagic)?;
+    let mut data = &inflated[..];
+    if take(&mut data, MAGIC.len())? != MAGIC {
+        return Err(ShareError::BadMagic);
+    }
+    if take(&mut data, 1)?[0] != SHARE_VERSION {
+        return Err(ShareError::UnsupportedVersion);
+    }
+    let rom_hash = take_u32(&mut data)?;
+    let rng = take_u32(&mut data)?;
+    let state_len = take_u32(&mut data)? as usize;
+    let state = take(&mut data, state_len)?.to_vec();
+    let recording = match take(&mut data, 1)?[0] {
+        0 => None,
+        _ => {
+            let id = take_u32(&mut data)?;
+            let frames = usize::try_from(take_u32(&mut data)?).map_err(|_| ShareError::TooLong)?;
+            if frames > MAX_MACRO_FRAMES {
+                return Err(ShareError::TooLong);
+            }
+            let masks = take(&mut data, frames * 2)?
+                .chunks_exact(2)
+                .map(|mask| u16::from_le_bytes([mask[0], mask[1]]))
+                .collect();
+            Some((id, masks))
+        }
+    };
+    Ok(Shared { rom_hash, rng, state, recording })
+}
+
+/// Returns the hash of the ROM a payload of `export_shareable_link_payload` was made with, so a
+/// frontend can check the user has the program before importing it.
+///
+/// # Example
+///
+/// ```
+/// use wasm_chip8::{shared_rom_hash, Emulator, ShareError};
+/// let mut emulator = Emulator::new();
+/// emulator.load(&[0x12, 0x00]).unwrap();
+/// let payload = emulator.export_shareable_link_payload(None);
+/// assert_eq!(shared_rom_hash(&payload), Ok(emulator.rom_hash()));
+/// assert_eq!(shared_rom_hash("not base64!"), Err(ShareError::InvalidEncoding));
+/// ```
+#[wasm_bindgen]
+pub fn shared_rom_hash(payload: &str) -> Result<u32, ShareError> { parse(payload).map(|shared| shared.rom_hash) }
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Packs the current moment into a short text for links or the clipboard: the ROM hash, the
+    /// random number generator, a savestate and, if `recording` is the id of a recorded macro, its
+    /// input. Unknown ids are left out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // v0 += 1, loop: jump loop
+    /// emulator.load(&[0x70, 0x01, 0x12, 0x02]).unwrap();
+    /// emulator.tick();
+    /// emulator.record_macro(3);
+    /// emulator.set_key(0x5, true);
+    /// emulator.tick_frame();
+    /// emulator.stop_recording();
+    /// let payload = emulator.export_shareable_link_payload(Some(3));
+    /// assert!(payload.len() < 256);
+    ///
+    /// let mut other = Emulator::new();
+    /// other.import_shareable_payload(&payload).unwrap();
+    /// assert_eq!((other.v(0), other.rom_hash()), (1, emulator.rom_hash()));
+    /// assert_eq!(other.macro_frames(3), 1);
+    ///
+    /// // The random numbers continue where they were.
+    /// emulator.set_random_seed(7);
+    /// // v0 := random 0xff
+    /// emulator.load(&[0xc0, 0xff]).unwrap();
+    /// other.import_shareable_payload(&emulator.export_shareable_link_payload(None)).unwrap();
+    /// emulator.tick();
+    /// other.tick();
+    /// assert_eq!(other.v(0), emulator.v(0));
+    /// ```
+    pub fn export_shareable_link_payload(&self, recording: Option<u32>) -> String {
+        let state = self.save_state();
+        let mut data = Vec::with_capacity(state.len() + 32);
+        data.extend_from_slice(MAGIC);
+        data.push(SHARE_VERSION);
+        data.extend_from_slice(&self.rom_hash.to_le_bytes());
+        data.extend_from_slice(&self.rng.state().to_le_bytes());
+        data.extend_from_slice(&(state.len() as u32).to_le_bytes());
+        data.extend_from_slice(&state);
+        match recording.and_then(|id| self.macros.get(id).map(|masks| (id, masks))) {
+            Some((id, masks)) => {
+                data.push(1);
+                data.extend_from_slice(&id.to_le_bytes());
+                data.extend_from_slice(&(masks.len() as u32).to_le_bytes());
+                data.extend(masks.iter().flat_map(|mask| mask.to_le_bytes()));
+            }
+            None => data.push(0),
+        }
+        encode_base64(&miniz_oxide::deflate::compress_to_vec(&data, 9))
+    }
+
+    /// Restores a moment packed by `export_shareable_link_payload`. A recording it holds is
+    /// stored as the macro it was recorded as, replacing a macro with the same id, and can be
+    /// played with `play_macro`. Compare `rom_hash` with the program the user has to tell them
+    /// which ROM the moment is from, the program itself is part of the savestate.
+    pub fn import_shareable_payload(&mut self, payload: &str) -> Result<(), ShareError> {
+        let shared = parse(payload)?;
+        // Validate on a scratch emulator so the running machine is not touched.
+        Emulator::headless().load_state(&shared.state).map_err(|_| ShareError::InvalidState)?;
+        let _ = self.load_state(&shared.state);
+        self.rng = XorShift::new(shared.rng);
+        if let Some((id, masks)) = shared.recording {
+            self.macros.insert(id, masks);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use wasm_bindgen::prelude::*;
+
+use crate::savestate::StateError;
+use crate::{utils, Emulator, CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH};
+
+/// How many savestate slots every emulator has.
+pub const SLOT_COUNT: usize = 10;
+
+/// Thumbnail size. Every thumbnail pixel covers a 2x2 block of the display.
+pub const THUMBNAIL_WIDTH: usize = CHIP8_DISPLAY_WIDTH / 2;
+pub const THUMBNAIL_HEIGHT: usize = CHIP8_DISPLAY_HEIGHT / 2;
+
+/// Description of a filled savestate slot, enough to render a savestate picker.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlotInfo {
+    slot: u8,
+    // Milliseconds since the Unix epoch.
+    timestamp: f64,
+    rom_hash: u32,
+    // THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT bytes in row-major order, planes packed like `pixels_packed`.
+    thumbnail: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl SlotInfo {
+    pub fn slot(&self) -> u8 { self.slot }
+
+    /// When the slot was saved, in milliseconds since the Unix epoch.
+    pub fn timestamp(&self) -> f64 { self.timestamp }
+
+    /// Hash of the ROM that was loaded when the slot was saved (see `Emulator::rom_hash`).
+    pub fn rom_hash(&self) -> u32 { self.rom_hash }
+
+    /// Downscaled display, `thumbnail_width() * thumbnail_height()` bytes in row-major order.
+    pub fn thumbnail(&self) -> Vec<u8> { self.thumbnail.clone() }
+
+    pub fn thumbnail_width(&self) -> usize { THUMBNAIL_WIDTH }
+
+    pub fn thumbnail_height(&self) -> usize { THUMBNAIL_HEIGHT }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Slot {
+    info: SlotInfo,
+    state: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Saves the current state into a slot, replacing what was stored there.
+    ///
+    /// # Arguments
+    ///
+    /// * `slot` - Slot index below `SLOT_COUNT`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.load(&[0x00, 0xe0, 0x12, 0x00]).unwrap();
+    /// let info = emulator.save_slot(3).unwrap();
+    /// assert_eq!(emulator.slot_info(3), Some(info));
+    /// emulator.load_slot(3).unwrap();
+    /// ```
+    pub fn save_slot(&mut self, slot: u8) -> Result<SlotInfo, StateError> {
+        let index = slot as usize;
+        if index >= SLOT_COUNT {
+            return Err(StateError::InvalidSlot);
+        }
+
+        let info = SlotInfo {
+            slot,
+            timestamp: utils::now_ms(),
+            rom_hash: self.rom_hash,
+            thumbnail: self.thumbnail(),
+        };
+
+        if self.slots.len() < SLOT_COUNT {
+            self.slots.resize(SLOT_COUNT, None);
+        }
+        self.slots[index] = Some(Slot { info: info.clone(), state: self.save_state() });
+
+        Ok(info)
+    }
+
+    /// Restores the state stored in a slot.
+    pub fn load_slot(&mut self, slot: u8) -> Result<(), StateError> {
+        if slot as usize >= SLOT_COUNT {
+            return Err(StateError::InvalidSlot);
+        }
+
+        let state = match self.slots.get(slot as usize) {
+            Some(Some(saved)) => saved.state.clone(),
+            _ => return Err(StateError::EmptySlot),
+        };
+        self.load_state(&state)
+    }
+
+    /// Returns the description of a slot or nothing if the slot is empty.
+    pub fn slot_info(&self, slot: u8) -> Option<SlotInfo> {
+        match self.slots.get(slot as usize) {
+            Some(Some(saved)) => Some(saved.info.clone()),
+            _ => None,
+        }
+    }
+
+    fn thumbnail(&self) -> Vec<u8> {
+        let mut thumbnail = vec![0; THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT];
+        self.write_thumbnail(&mut thumbnail);
+        thumbnail
+    }
+
+    /// Downscales the display into `THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT` bytes.
+    pub(crate) fn write_thumbnail(&self, thumbnail: &mut [u8]) {
+        thumbnail.iter_mut().for_each(|cell| *cell = 0);
+        for pixel in self.pixels() {
+            thumbnail[(pixel.y / 2) * THUMBNAIL_WIDTH + pixel.x / 2] |= pixel.planes;
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+// How many instructions after an ANNN the static scan looks for the DXYN that uses it.
+const LOOKAHEAD: usize = 8;
+
+/// How a sprite was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpriteSource {
+    /// A DXYN instruction drew it while the program was running.
+    Runtime,
+    /// An ANNN instruction in the program is followed by a DXYN that draws from NNN.
+    Static,
+}
+
+/// Sprite found in memory.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Sprite {
+    pub address: u16,
+    pub height: u8,
+    pub source: SpriteSource,
+    // 8 pixels per row, `height` rows, one byte (0 or 1) per pixel.
+    pub bitmap: Vec<u8>,
+}
+
+impl Emulator {
+    pub(crate) fn record_sprite(&mut self, address: usize, height: u8) {
+        if height == 0 {
+            return;
+        }
+        let tallest = self.drawn_sprites.entry(address as u16).or_insert(0);
+        *tallest = (*tallest).max(height);
+    }
+
+    /// Returns sprites drawn so far followed by the ones the static scan of the program finds.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_height` - Taller sprites are cut to this many rows.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, SpriteSource};
+    /// let mut emulator = Emulator::new();
+    /// // i := 0x206, sprite v0 v0 2, jump 0x204, then the sprite itself.
+    /// emulator.load(&[0xa2, 0x06, 0xd0, 0x02, 0x12, 0x04, 0xf0, 0x90]).unwrap();
+    /// let sprites = emulator.extract_sprites(15);
+    /// assert_eq!(sprites[0].address, 0x206);
+    /// assert_eq!(sprites[0].source, SpriteSource::Static);
+    /// assert_eq!(&sprites[0].bitmap[..8], &[1, 1, 1, 1, 0, 0, 0, 0]);
+    /// ```
+    pub fn extract_sprites(&self, max_height: u8) -> Vec<Sprite> {
+        let mut found: BTreeMap<u16, (u8, SpriteSource)> = BTreeMap::new();
+
+        for (&address, &height) in &self.drawn_sprites {
+            found.insert(address, (height, SpriteSource::Runtime));
+        }
+
+        for (address, height) in self.scan_sprites() {
+            let entry = found.entry(address).or_insert((height, SpriteSource::Static));
+            if entry.1 == SpriteSource::Static {
+                entry.0 = entry.0.max(height);
+            }
+        }
+
+        let mut sprites: Vec<Sprite> = found
+            .into_iter()
+            .map(|(address, (height, source))| {
+                let height = height.min(max_height);
+                Sprite { address, height, source, bitmap: self.render_sprite(address, height) }
+            })
+            .collect();
+        sprites.sort_by_key(|sprite| sprite.source == SpriteSource::Static);
+        sprites
+    }
+
+    // Finds every ANNN that is followed by a DXYN before I is changed again.
+    fn scan_sprites(&self) -> Vec<(u16, u8)> {
+        let program = &self.memory[self.variant.load_address()..];
+        let word = |at: usize| -> Option<u16> {
+            Some(u16::from(*program.get(at)?) << 8 | u16::from(*program.get(at + 1)?))
+        };
+
+        let mut sprites = vec![];
+        let mut at = 0;
+        while let Some(opcode) = word(at) {
+            if opcode & 0xf000 == 0xa000 {
+                for step in 1..=LOOKAHEAD {
+                    match word(at + step * 2) {
+                        Some(next) if next & 0xf000 == 0xd000 && next & 0xf != 0 => {
+                            sprites.push((opcode & 0x0fff, (next & 0xf) as u8));
+                            break;
+                        }
+                        Some(next) if next & 0xf000 == 0xa000 || next & 0xf0ff == 0xf01e => break,
+                        Some(_) => {}
+                        None => break,
+                    }
+                }
+            }
+            at += 2;
+        }
+        sprites
+    }
+
+    fn render_sprite(&self, address: u16, height: u8) -> Vec<u8> {
+        let mut bitmap = Vec::with_capacity(height as usize * 8);
+        for row in 0..height as usize {
+            let byte = self.memory.get(address as usize + row).copied().unwrap_or(0);
+            bitmap.extend((0..8).map(|bit| (byte >> (7 - bit)) & 1));
+        }
+        bitmap
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns the sprites found by `extract_sprites` as an array of
+    /// `{ address, height, source, bitmap }` objects.
+    #[wasm_bindgen(js_name = extract_sprites)]
+    pub fn extract_sprites_js(&self, max_height: u8) -> JsValue {
+        crate::utils::to_js(&self.extract_sprites(max_height))
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{utils, Emulator, Variant};
+
+/// Identifier of the `dump_state_json` schema. It changes only when the schema does.
+pub const STATE_SCHEMA: &str = "chip8-state/1";
+
+#[derive(Serialize)]
+struct StateDump {
+    schema: &'static str,
+    platform: &'static str,
+    frame: u64,
+    pc: u16,
+    i: u16,
+    v: [u8; 16],
+    dt: u8,
+    st: u8,
+    stack: Vec<u16>,
+    memory_fnv1a: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<String>,
+    display: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Dumps the machine state as JSON for diffing against other emulators at the same point.
+    ///
+    /// The schema is stable and identified by the `schema` field (see `STATE_SCHEMA`):
+    ///
+    /// * `platform` - `"chip8"`, `"xochip"`, `"dream6800"` or `"chip8x"`.
+    /// * `frame` - Index of the frame that runs next.
+    /// * `pc`, `i`, `dt`, `st` - Registers as numbers, `v` - V0 to VF.
+    /// * `stack` - Return addresses from the oldest to the innermost call.
+    /// * `memory_fnv1a` - 32-bit FNV-1a hash of the whole memory.
+    /// * `memory` - The whole memory as lowercase hex, only with `full_memory`.
+    /// * `display` - One string per row with a hex digit per pixel, the bitmask of its lit planes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.load(&[0x60, 0x2a]).unwrap();
+    /// emulator.tick();
+    /// let dump: serde_json::Value = serde_json::from_str(&emulator.dump_state_json(false)).unwrap();
+    /// assert_eq!(dump["schema"], "chip8-state/1");
+    /// assert_eq!(dump["pc"], 0x202);
+    /// assert_eq!(dump["v"][0], 42);
+    /// assert_eq!(dump["display"][0].as_str().unwrap().len(), 64);
+    /// ```
+    pub fn dump_state_json(&self, full_memory: bool) -> String {
+        let memory = full_memory.then(|| {
+            let mut hex = String::with_capacity(self.memory.len() * 2);
+            for byte in &self.memory {
+                let _ = write!(hex, "{:02x}", byte);
+            }
+            hex
+        });
+        let digit = |planes: u8| char::from_digit(u32::from(planes & 0xf), 16).unwrap_or('0');
+        let display = self.gfx.iter().map(|row| row.iter().map(|&planes| digit(planes)).collect()).collect();
+
+        let dump = StateDump {
+            schema: STATE_SCHEMA,
+            platform: platform_name(self.variant),
+            frame: self.frame,
+            pc: self.pc as u16,
+            i: self.i as u16,
+            v: self.v,
+            dt: self.delay_timer,
+            st: self.sound_timer,
+            stack: self.stack.iter().map(|&address| address as u16).collect(),
+            memory_fnv1a: utils::fnv1a(&self.memory),
+            memory,
+            display,
+        };
+        serde_json::to_string(&dump).unwrap_or_default()
+    }
+}
+
+pub(crate) fn platform_name(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Chip8 => "chip8",
+        Variant::XoChip => "xochip",
+        Variant::Dream6800 => "dream6800",
+        Variant::Chip8X => "chip8x",
+        Variant::Vip2K => "vip2k",
+    }
+}
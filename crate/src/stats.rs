@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::{utils, Instruction, Variant};
+
+/// Summary of a ROM for curating collections.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RomStats {
+    pub size: usize,
+    /// Same hash as `Emulator::rom_hash`, for spotting duplicates.
+    pub hash: u32,
+    /// Bytes of instructions reachable from the entry point, the rest is assumed to be data.
+    pub code_bytes: usize,
+    pub data_bytes: usize,
+    /// How often every mnemonic occurs in the reachable code.
+    pub histogram: BTreeMap<&'static str, u32>,
+    /// Reachable opcodes the variant does not define. Many of them hint at a corrupted dump or
+    /// the wrong variant.
+    pub unknown_opcodes: u32,
+    /// Shannon entropy in bits per byte, 0 to 8. Values close to 8 look like noise or compression.
+    pub entropy: f64,
+}
+
+/// Analyzes a ROM without running it.
+///
+/// Code is found by following every path from the load address. Jump targets computed at runtime
+/// (BNNN) are not followed, so code only reachable through them counts as data.
+///
+/// # Example
+///
+/// ```
+/// use wasm_chip8::{rom_stats, Variant};
+/// // i := sprite, draw, loop, sprite: 0xff
+/// let stats = rom_stats(&[0xa2, 0x06, 0xd0, 0x01, 0x12, 0x04, 0xff], Variant::Chip8);
+/// assert_eq!((stats.code_bytes, stats.data_bytes), (6, 1));
+/// assert_eq!(stats.histogram["LD"], 1);
+/// assert_eq!(stats.unknown_opcodes, 0);
+/// ```
+pub fn rom_stats(rom: &[u8], variant: Variant) -> RomStats {
+    let starts = instruction_starts(rom, variant);
+    let mut histogram = BTreeMap::new();
+    let mut unknown_opcodes = 0;
+    let mut code_bytes = 0;
+    for (offset, &start) in starts.iter().enumerate() {
+        if !start {
+            continue;
+        }
+
+        let instruction = Instruction::decode(word(rom, offset), variant);
+        code_bytes += instruction_size(instruction).min(rom.len() - offset);
+        *histogram.entry(instruction.mnemonic()).or_insert(0) += 1;
+        if instruction.is_unknown() {
+            unknown_opcodes += 1;
+        }
+    }
+
+    RomStats {
+        size: rom.len(),
+        hash: utils::fnv1a(rom),
+        code_bytes,
+        data_bytes: rom.len() - code_bytes,
+        histogram,
+        unknown_opcodes,
+        entropy: entropy(rom),
+    }
+}
+
+/// Returns `rom_stats` as a plain JS object.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = rom_stats)]
+pub fn rom_stats_js(rom: &[u8], variant: Variant) -> JsValue { utils::to_js(&rom_stats(rom, variant)) }
+
+/// Marks the ROM offsets where a reachable instruction starts.
+pub(crate) fn instruction_starts(rom: &[u8], variant: Variant) -> Vec<bool> {
+    let mut starts = vec![false; rom.len()];
+    let mut pending = vec![0usize];
+    while let Some(offset) = pending.pop() {
+        if offset + 1 >= rom.len() || starts[offset] {
+            continue;
+        }
+        starts[offset] = true;
+
+        let instruction = Instruction::decode(word(rom, offset), variant);
+        let next = offset + instruction_size(instruction);
+        let target = |address: u16| (address as usize).checked_sub(variant.load_address());
+        match instruction {
+            Instruction::Jump(nnn) => pending.extend(target(nnn)),
+            Instruction::Call(nnn) => {
+                pending.extend(target(nnn));
+                pending.push(next);
+            }
+            Instruction::Return | Instruction::JumpOffset(_) | Instruction::Unknown(_) => {}
+            Instruction::SkipEqImm { .. }
+            | Instruction::SkipNeImm { .. }
+            | Instruction::SkipEq { .. }
+            | Instruction::SkipNe { .. }
+            | Instruction::SkipKey { .. }
+            | Instruction::SkipKey2 { .. }
+            | Instruction::SkipNotKey2 { .. }
+            | Instruction::SkipNotKey { .. } => {
+                pending.push(next);
+                if next + 1 < rom.len() {
+                    pending.push(next + instruction_size(Instruction::decode(word(rom, next), variant)));
+                }
+            }
+            _ => pending.push(next),
+        }
+    }
+    starts
+}
+
+fn word(rom: &[u8], offset: usize) -> u16 {
+    let byte = |at: usize| u16::from(rom.get(at).copied().unwrap_or(0));
+    byte(offset) << 8 | byte(offset + 1)
+}
+
+pub(crate) fn instruction_size(instruction: Instruction) -> usize {
+    match instruction {
+        Instruction::LongI => 4,
+        _ => 2,
+    }
+}
+
+fn entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0u32; 256];
+    for &byte in bytes {
+        counts[byte as usize] += 1;
+    }
+
+    let total = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = f64::from(count) / total;
+            -p * p.log2()
+        })
+        .sum()
+}
@@ -0,0 +1,200 @@
+use serde::Serialize;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Instruction};
+
+/// Memory byte written by an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct MemoryWrite {
+    pub address: u16,
+    pub value: u8,
+}
+
+/// What a single instruction did, for UIs that explain a program step by step.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StepInfo {
+    /// Address and opcode of the executed instruction.
+    pub pc_before: u16,
+    pub opcode: u16,
+    pub mnemonic: &'static str,
+    /// Names of the registers the instruction changed: `"V0"` to `"VF"`, `"I"`, `"DT"` and `"ST"`.
+    pub changed_registers: Vec<&'static str>,
+    /// Memory bytes whose value changed, in ascending address order.
+    pub memory_writes: Vec<MemoryWrite>,
+}
+
+/// Register an instruction read and the value it held.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Operand {
+    /// `"V0"` to `"VF"` or `"I"`.
+    pub name: &'static str,
+    pub value: u16,
+}
+
+/// The last executed instruction with the register values it used, for a one-line live
+/// disassembly without tracing.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LastInstruction {
+    pub pc: u16,
+    pub opcode: u16,
+    pub mnemonic: &'static str,
+    /// Disassembly as in listings, e.g. `"ADD V1, 0x05"`.
+    pub text: String,
+    /// Registers read by the instruction with their values before it ran.
+    pub operands: Vec<Operand>,
+}
+
+/// What `last_instruction` is built from, kept for every executed instruction.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Executed {
+    pc: u16,
+    opcode: u16,
+    instruction: Instruction,
+    v: [u8; 16],
+    i: u16,
+}
+
+const V_NAMES: [&str; 16] =
+    ["V0", "V1", "V2", "V3", "V4", "V5", "V6", "V7", "V8", "V9", "VA", "VB", "VC", "VD", "VE", "VF"];
+
+impl Emulator {
+    /// Executes one instruction like `step` and describes its effects.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, MemoryWrite};
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 7, i := 0x300, save v0
+    /// emulator.load(&[0x60, 0x07, 0xa3, 0x00, 0xf0, 0x55]).unwrap();
+    /// let info = emulator.step_info();
+    /// assert_eq!((info.pc_before, info.mnemonic), (0x200, "LD"));
+    /// assert_eq!(info.changed_registers, ["V0"]);
+    /// emulator.step();
+    /// let info = emulator.step_info();
+    /// assert_eq!(info.memory_writes, [MemoryWrite { address: 0x300, value: 7 }]);
+    /// ```
+    pub fn step_info(&mut self) -> StepInfo {
+        let (pc_before, opcode) = (self.pc as u16, self.get_opcode());
+        let instruction = Instruction::decode(opcode, self.variant);
+        let (v, i, delay, sound) = (self.v, self.i, self.delay_timer, self.sound_timer);
+
+        // Only FX33 and FX55 write memory, so the bytes they can reach are enough to compare.
+        let written = match instruction {
+            Instruction::Bcd { .. } => 3,
+            Instruction::Store { x } => usize::from(x) + 1,
+            _ => 0,
+        };
+        let addresses: Vec<usize> = (0..written).map(|offset| self.address(i + offset)).collect();
+        let before: Vec<u8> = addresses.iter().map(|&address| self.memory[address]).collect();
+
+        self.step();
+
+        let mut changed_registers: Vec<&'static str> =
+            (0..16).filter(|&x| self.v[x] != v[x]).map(|x| V_NAMES[x]).collect();
+        let others = [("I", self.i != i), ("DT", self.delay_timer != delay), ("ST", self.sound_timer != sound)];
+        changed_registers.extend(others.iter().filter(|&&(_, changed)| changed).map(|&(name, _)| name));
+
+        let mut memory_writes: Vec<MemoryWrite> = addresses
+            .iter()
+            .zip(before)
+            .filter(|&(&address, old)| self.memory[address] != old)
+            .map(|(&address, _)| MemoryWrite { address: address as u16, value: self.memory[address] })
+            .collect();
+        memory_writes.sort_by_key(|write| write.address);
+
+        StepInfo { pc_before, opcode, mnemonic: instruction.mnemonic(), changed_registers, memory_writes }
+    }
+}
+
+impl Emulator {
+    /// Returns the last executed instruction, `None` before the first one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Operand};
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 7, v1 := 3, v0 += v1
+    /// emulator.load(&[0x60, 0x07, 0x61, 0x03, 0x80, 0x14]).unwrap();
+    /// assert_eq!(emulator.last_instruction(), None);
+    /// for _ in 0..3 {
+    ///     emulator.tick();
+    /// }
+    /// let last = emulator.last_instruction().unwrap();
+    /// assert_eq!((last.pc, last.opcode, last.text.as_str()), (0x204, 0x8014, "ADD V0, V1"));
+    /// assert_eq!(last.operands, [Operand { name: "V0", value: 7 }, Operand { name: "V1", value: 3 }]);
+    /// ```
+    pub fn last_instruction(&self) -> Option<LastInstruction> {
+        let executed = self.last_executed?;
+        let (v, i) = (executed.v, executed.i);
+        let register = |x: u8| {
+            let x = usize::from(x) & 0xf;
+            Operand { name: V_NAMES[x], value: u16::from(v[x]) }
+        };
+        let index = Operand { name: "I", value: i };
+        let operands = match executed.instruction {
+            Instruction::SkipEqImm { x, .. }
+            | Instruction::SkipNeImm { x, .. }
+            | Instruction::AddImm { x, .. }
+            | Instruction::SkipKey { x }
+            | Instruction::SkipNotKey { x }
+            | Instruction::SkipKey2 { x }
+            | Instruction::SkipNotKey2 { x }
+            | Instruction::SetDelay { x }
+            | Instruction::SetSound { x }
+            | Instruction::Font { x } => vec![register(x)],
+            Instruction::SkipEq { x, y }
+            | Instruction::SkipNe { x, y }
+            | Instruction::Or { x, y }
+            | Instruction::And { x, y }
+            | Instruction::Xor { x, y }
+            | Instruction::Add { x, y }
+            | Instruction::Sub { x, y }
+            | Instruction::SubReverse { x, y }
+            | Instruction::AddOctal { x, y } => vec![register(x), register(y)],
+            Instruction::Set { y, .. } => vec![register(y)],
+            Instruction::ShiftRight { x, y } | Instruction::ShiftLeft { x, y } => {
+                vec![register(if self.quirks.shift_uses_vy { y } else { x })]
+            }
+            Instruction::JumpOffset(nnn) => {
+                vec![register(if self.quirks.jump_uses_vx { (nnn >> 8) as u8 } else { 0 })]
+            }
+            Instruction::Draw { x, y, .. } => vec![register(x), register(y), index],
+            Instruction::Color { x, y, .. } => vec![register(x), register(y), register(x + 1)],
+            Instruction::AddI { x } | Instruction::Bcd { x } => vec![register(x), index],
+            Instruction::Store { x } => (0..=x).map(register).chain(Some(index)).collect(),
+            Instruction::Load { .. } => vec![index],
+            _ => vec![],
+        };
+        Some(LastInstruction {
+            pc: executed.pc,
+            opcode: executed.opcode,
+            mnemonic: executed.instruction.mnemonic(),
+            text: executed.instruction.to_string(),
+            operands,
+        })
+    }
+
+    /// Remembers the instruction about to execute for `last_instruction`.
+    pub(crate) fn record_executed(&mut self, instruction: Instruction) {
+        self.last_executed =
+            Some(Executed { pc: self.pc as u16, opcode: self.opcode, instruction, v: self.v, i: self.i as u16 });
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Emulator {
+    /// Returns `step_info` as a plain JS object.
+    #[wasm_bindgen(js_name = step_info)]
+    pub fn step_info_js(&mut self) -> JsValue { crate::utils::to_js(&self.step_info()) }
+
+    /// Returns `last_instruction` as a plain JS object with `pc`, `opcode`, `mnemonic`, `text` and
+    /// `operands`, or `undefined` before the first instruction.
+    #[wasm_bindgen(js_name = last_instruction)]
+    pub fn last_instruction_js(&self) -> JsValue {
+        self.last_instruction().map_or(JsValue::UNDEFINED, |last| crate::utils::to_js(&last))
+    }
+}
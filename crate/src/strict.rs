@@ -0,0 +1,64 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, FaultKind, Instruction};
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Turns strict mode on or off, it is off by default.
+    ///
+    /// In strict mode every instruction is validated before it executes: jumps, calls and returns
+    /// must land inside memory, I must not be moved past the end of memory, sprite, BCD and register
+    /// transfers must stay inside memory and undefined opcodes are rejected. A violation stops the
+    /// program with a fault that names the offending instruction instead of wrapping or ignoring it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, FaultKind};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_strict(true);
+    /// // i := 0xfff, save v3: writes four bytes starting at the last byte of memory.
+    /// emulator.load(&[0xaf, 0xff, 0xf3, 0x55]).unwrap();
+    /// emulator.tick_frame();
+    /// assert_eq!(emulator.fault_kind(), Some(FaultKind::MemoryOutOfBounds));
+    /// assert_eq!(emulator.fault().unwrap().opcode, 0xf355);
+    /// ```
+    pub fn set_strict(&mut self, strict: bool) { self.strict = strict; }
+
+    pub fn is_strict(&self) -> bool { self.strict }
+}
+
+impl Emulator {
+    /// Checks the instruction at PC against the strict mode rules.
+    pub(crate) fn check_strict(&self, instruction: Instruction) -> Result<(), FaultKind> {
+        let size = self.memory.len();
+        let pc = |target: usize| if target + 1 < size { Ok(()) } else { Err(FaultKind::PcOutOfBounds) };
+        let i = |value: usize| if value < size { Ok(()) } else { Err(FaultKind::IOutOfBounds) };
+        let memory = |len: usize| if self.i + len <= size { Ok(()) } else { Err(FaultKind::MemoryOutOfBounds) };
+        // DXYN reads a sprite per selected plane.
+        let planes = self.planes.count_ones() as usize;
+
+        match instruction {
+            Instruction::Unknown(_) => Err(FaultKind::UnknownOpcode),
+            Instruction::Jump(nnn) | Instruction::Call(nnn) => pc(nnn as usize),
+            Instruction::JumpOffset(nnn) => {
+                let register = if self.quirks.jump_uses_vx { (nnn >> 8) as usize } else { 0 };
+                pc(nnn as usize + usize::from(self.v[register]))
+            }
+            Instruction::Return => self.stack.last().map_or(Ok(()), |&address| pc(address)),
+            Instruction::AddI { x } => i(self.i + usize::from(self.v[x as usize])),
+            Instruction::LongI => pc(self.pc + 2),
+            Instruction::Draw { n: 0, .. } if self.quirks.large_sprites => memory(32 * planes),
+            Instruction::Draw { n, .. } => memory(n as usize * planes),
+            Instruction::Bcd { .. } => memory(3),
+            Instruction::Store { x } | Instruction::Load { x } => {
+                memory(x as usize + 1)?;
+                if self.quirks.load_store_increments_i {
+                    i(self.i + x as usize + 1)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
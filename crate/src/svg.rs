@@ -0,0 +1,65 @@
+use std::fmt::Write;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH};
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Captures the display as an SVG image with one display pixel per user unit. Horizontal runs
+    /// of lit pixels become a single rectangle, so the markup stays small. `fg` and `bg` are any
+    /// SVG colors, e.g. `"#fff"` or `"black"`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // i := font 0, drw v0, v0, 5
+    /// emulator.load(&[0xf0, 0x29, 0xd0, 0x05]).unwrap();
+    /// emulator.tick();
+    /// emulator.tick();
+    /// let svg = emulator.screenshot_svg("#fff", "#000");
+    /// assert!(svg.starts_with("<svg"));
+    /// assert!(svg.contains(r#"<rect x="0" y="0" width="4" height="1"/>"#));
+    /// ```
+    pub fn screenshot_svg(&self, fg: &str, bg: &str) -> String {
+        let (width, height) = (CHIP8_DISPLAY_WIDTH, CHIP8_DISPLAY_HEIGHT);
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}" "#,
+            w = width,
+            h = height
+        );
+        let _ = write!(
+            svg,
+            r#"shape-rendering="crispEdges"><rect width="{}" height="{}" fill="{}"/><g fill="{}">"#,
+            width,
+            height,
+            escape(bg),
+            escape(fg)
+        );
+
+        for (y, row) in self.gfx.iter().enumerate() {
+            let mut x = 0;
+            while x < width {
+                if row[x] == 0 {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < width && row[x] != 0 {
+                    x += 1;
+                }
+                let _ = write!(svg, r#"<rect x="{}" y="{}" width="{}" height="1"/>"#, start, y, x - start);
+            }
+        }
+
+        svg.push_str("</g></svg>");
+        svg
+    }
+}
+
+// Makes a color safe to put into a double quoted attribute.
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,126 @@
+//! Instruction traces for analysis outside the emulator, e.g. in pandas or Observable.
+//!
+//! Traces are CSV with the columns `cycle`, `frame`, `pc`, `opcode`, `mnemonic` and `changes`,
+//! the registers the instruction changed as space separated `NAME=VALUE` pairs in hex, e.g.
+//! `V0=07 VF=01`. The host takes the rows in chunks while the program runs, so a trace of millions
+//! of instructions never has to be held in memory at once.
+
+use std::fmt::Write;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, Instruction};
+
+/// Rows a trace buffers until the host takes them, later rows are dropped.
+pub const MAX_TRACE_ROWS: usize = 100_000;
+
+const HEADER: &str = "cycle,frame,pc,opcode,mnemonic,changes\n";
+
+/// Trace being recorded.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Trace {
+    csv: String,
+    rows: usize,
+    dropped: u64,
+}
+
+/// Registers an instruction can change, taken before it executes.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Registers {
+    v: [u8; 16],
+    i: usize,
+    delay_timer: u8,
+    sound_timer: u8,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Starts tracing every executed instruction, discarding a trace that was not stopped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // v0 := 7, i := 0x300, loop
+    /// emulator.load(&[0x60, 0x07, 0xa3, 0x00, 0x12, 0x04]).unwrap();
+    /// emulator.start_trace();
+    /// emulator.tick();
+    /// emulator.tick();
+    /// let chunk = emulator.take_trace_chunk();
+    /// let rows: Vec<&str> = chunk.lines().collect();
+    /// assert_eq!(rows[0], "cycle,frame,pc,opcode,mnemonic,changes");
+    /// assert_eq!(rows[1], "0,0,0x0200,0x6007,LD,V0=07");
+    /// assert_eq!(rows[2], "1,0,0x0202,0xA300,LD,I=0300");
+    /// emulator.tick();
+    /// assert_eq!(emulator.stop_trace(), "2,0,0x0204,0x1204,JP,\n");
+    /// assert!(!emulator.is_tracing());
+    /// ```
+    pub fn start_trace(&mut self) {
+        self.trace = Some(Trace { csv: HEADER.to_string(), ..Trace::default() });
+    }
+
+    /// Removes and returns the rows traced since the previous chunk, starting with the header in
+    /// the first chunk. Empty when not tracing.
+    pub fn take_trace_chunk(&mut self) -> String {
+        match &mut self.trace {
+            Some(trace) => {
+                trace.rows = 0;
+                std::mem::take(&mut trace.csv)
+            }
+            None => String::new(),
+        }
+    }
+
+    /// Stops tracing and returns the rows not taken yet.
+    pub fn stop_trace(&mut self) -> String {
+        let rest = self.take_trace_chunk();
+        self.trace = None;
+        rest
+    }
+
+    pub fn is_tracing(&self) -> bool { self.trace.is_some() }
+
+    /// Rows dropped because `MAX_TRACE_ROWS` were waiting to be taken.
+    pub fn trace_dropped_rows(&self) -> u64 { self.trace.as_ref().map_or(0, |trace| trace.dropped) }
+}
+
+impl Emulator {
+    /// Registers to compare with after the next instruction, if tracing.
+    pub(crate) fn trace_before(&self) -> Option<Registers> {
+        self.trace.as_ref().map(|_| Registers {
+            v: self.v,
+            i: self.i,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+        })
+    }
+
+    /// Adds the instruction at `pc` that just executed to the trace.
+    pub(crate) fn record_trace(&mut self, pc: usize, before: Registers) {
+        let mnemonic = Instruction::decode(self.opcode, self.variant).mnemonic();
+        let (cycle, frame, opcode) = (self.total_cycles - 1, self.frame, self.opcode);
+        let mut changes = vec![];
+        let changed = (0..16).filter(|&x| self.v[x] != before.v[x]);
+        changes.extend(changed.map(|x| format!("V{:X}={:02X}", x, self.v[x])));
+        if self.i != before.i {
+            changes.push(format!("I={:04X}", self.i));
+        }
+        if self.delay_timer != before.delay_timer {
+            changes.push(format!("DT={:02X}", self.delay_timer));
+        }
+        if self.sound_timer != before.sound_timer {
+            changes.push(format!("ST={:02X}", self.sound_timer));
+        }
+
+        if let Some(trace) = &mut self.trace {
+            if trace.rows == MAX_TRACE_ROWS {
+                trace.dropped += 1;
+                return;
+            }
+            trace.rows += 1;
+            let row = format!("{},{},{:#06x},0x{:04X},{}", cycle, frame, pc, opcode, mnemonic);
+            let _ = writeln!(trace.csv, "{},{}", row, changes.join(" "));
+        }
+    }
+}
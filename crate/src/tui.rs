@@ -0,0 +1,137 @@
+//! Terminal frontend that renders the display with Unicode half-blocks.
+//!
+//! Every character cell shows two display rows, so the whole 64x32 screen fits into a 64x16
+//! terminal area. Terminals only report key presses, so a pressed key is held down for
+//! `KEY_HOLD_FRAMES` frames.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use crate::Emulator;
+
+const FRAME: Duration = Duration::from_micros(16_667);
+const KEY_HOLD_FRAMES: u8 = 6;
+
+/// Maps a host key to the hex keypad using the usual layout:
+///
+/// ```text
+/// 1 2 3 4      1 2 3 C
+/// Q W E R  ->  4 5 6 D
+/// A S D F      7 8 9 E
+/// Z X C V      A 0 B F
+/// ```
+pub fn map_key(code: KeyCode) -> Option<u8> {
+    let key = match code {
+        KeyCode::Char(c) => match c.to_ascii_lowercase() {
+            '1' => 0x1,
+            '2' => 0x2,
+            '3' => 0x3,
+            '4' => 0xc,
+            'q' => 0x4,
+            'w' => 0x5,
+            'e' => 0x6,
+            'r' => 0xd,
+            'a' => 0x7,
+            's' => 0x8,
+            'd' => 0x9,
+            'f' => 0xe,
+            'z' => 0xa,
+            'x' => 0x0,
+            'c' => 0xb,
+            'v' => 0xf,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Writes the current display to `out` using half-block characters.
+pub fn render<W: Write>(emulator: &Emulator, out: &mut W) -> io::Result<()> {
+    let width = emulator.display_width();
+    let packed = emulator.pixels_packed();
+
+    for (row, pair) in packed.chunks(width * 2).enumerate() {
+        let (top, bottom) = pair.split_at(width.min(pair.len()));
+        let line: String = (0..width)
+            .map(|x| {
+                let top = top.get(x).is_some_and(|&planes| planes != 0);
+                let bottom = bottom.get(x).is_some_and(|&planes| planes != 0);
+                match (top, bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                }
+            })
+            .collect();
+        queue!(out, MoveTo(0, row as u16), Print(line))?;
+    }
+
+    out.flush()
+}
+
+/// Runs the emulator in the terminal until Esc or Ctrl+C is pressed.
+///
+/// # Arguments
+///
+/// * `emulator` - Emulator with a program already loaded.
+/// * `ticks_per_frame` - How many instructions are executed per rendered frame.
+pub fn run(emulator: &mut Emulator, ticks_per_frame: usize) -> io::Result<()> {
+    let mut out = io::stdout();
+    emulator.set_cycles_per_frame(ticks_per_frame as u32);
+
+    terminal::enable_raw_mode()?;
+    execute!(out, EnterAlternateScreen, Hide)?;
+
+    let result = run_loop(emulator, &mut out);
+
+    execute!(out, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+
+    result
+}
+
+fn run_loop<W: Write>(emulator: &mut Emulator, out: &mut W) -> io::Result<()> {
+    // Frames left until the key is released.
+    let mut held = [0u8; 16];
+
+    loop {
+        let frame_start = Instant::now();
+
+        while event::poll(Duration::from_millis(0))? {
+            if let Event::Key(KeyEvent { code, modifiers, kind, .. }) = event::read()? {
+                let ctrl_c = code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL);
+                if code == KeyCode::Esc || ctrl_c {
+                    return Ok(());
+                }
+
+                if let Some(key) = map_key(code) {
+                    match kind {
+                        KeyEventKind::Release => held[key as usize] = 0,
+                        _ => held[key as usize] = KEY_HOLD_FRAMES,
+                    }
+                }
+            }
+        }
+
+        for (key, frames) in held.iter_mut().enumerate() {
+            emulator.set_key(key as u8, *frames > 0);
+            *frames = frames.saturating_sub(1);
+        }
+
+        emulator.tick_frame();
+
+        render(emulator, out)?;
+
+        if let Some(rest) = FRAME.checked_sub(frame_start.elapsed()) {
+            std::thread::sleep(rest);
+        }
+    }
+}
@@ -1,8 +1,124 @@
-use wasm_bindgen::prelude::*;
-
-fn set_panic_hook() {
+pub fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
     // `set_panic_hook` function to get better error messages if we ever panic.
     #[cfg(feature = "console_error_panic_hook")]
         console_error_panic_hook::set_once();
 }
+
+/// Sends log records of the given level (`"error"`, `"warn"`, `"info"`, `"debug"` or `"trace"`)
+/// and above to the browser console. Returns `false` if the level is unknown or a logger was
+/// already installed.
+#[cfg(all(feature = "console_log", target_arch = "wasm32"))]
+#[wasm_bindgen::prelude::wasm_bindgen]
+pub fn init_console_log(level: &str) -> bool {
+    match level.parse::<log::Level>() {
+        Ok(level) => console_log::init_with_level(level).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// 32-bit FNV-1a hash, used to identify ROMs.
+pub fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hasher = Fnv1a::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// Streaming version of `fnv1a` for hashing data that is not stored contiguously.
+pub struct Fnv1a {
+    hash: u32,
+}
+
+impl Default for Fnv1a {
+    fn default() -> Fnv1a { Fnv1a { hash: 0x811c_9dc5 } }
+}
+
+impl Fnv1a {
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash = (self.hash ^ u32::from(byte)).wrapping_mul(0x0100_0193);
+        }
+    }
+
+    pub fn finish(&self) -> u32 { self.hash }
+}
+
+/// Xorshift PRNG behind CXNN. It is tiny and, unlike `rand`, adds next to nothing to the wasm binary.
+#[derive(Clone, Debug)]
+pub struct XorShift {
+    state: u32,
+}
+
+impl XorShift {
+    pub fn new(seed: u32) -> XorShift {
+        // Zero is the one state xorshift never leaves.
+        XorShift { state: if seed == 0 { 0x9e37_79b9 } else { seed } }
+    }
+
+    /// Seed for a new emulator: OS entropy with the `rand` feature, the clock otherwise.
+    #[cfg(feature = "rand")]
+    pub fn entropy() -> XorShift { XorShift::new(rand::random()) }
+
+    /// Seed for a new emulator: OS entropy with the `rand` feature, the clock otherwise.
+    #[cfg(not(feature = "rand"))]
+    pub fn entropy() -> XorShift {
+        let ms = now_ms() as u64;
+        XorShift::new((ms ^ (ms >> 32)) as u32)
+    }
+
+    /// State to continue the sequence from with `XorShift::new`.
+    pub fn state(&self) -> u32 { self.state }
+
+    pub fn next_u8(&mut self) -> u8 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        (self.state >> 24) as u8
+    }
+}
+
+/// Wall-clock time in milliseconds since the Unix epoch.
+#[cfg(target_arch = "wasm32")]
+pub fn now_ms() -> f64 { js_sys::Date::now() }
+
+/// Wall-clock time in milliseconds since the Unix epoch.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_ms() -> f64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs_f64() * 1000.0).unwrap_or(0.0)
+}
+
+/// Monotonic time in milliseconds for measuring durations, `performance.now()` in the browser.
+#[cfg(target_arch = "wasm32")]
+pub fn monotonic_ms() -> f64 {
+    use wasm_bindgen::JsCast;
+
+    let performance = js_sys::Reflect::get(&js_sys::global(), &"performance".into()).ok();
+    let now = performance
+        .as_ref()
+        .and_then(|performance| js_sys::Reflect::get(performance, &"now".into()).ok())
+        .and_then(|now| now.dyn_into::<js_sys::Function>().ok());
+    match (performance, now) {
+        (Some(performance), Some(now)) => {
+            now.call0(&performance).ok().and_then(|ms| ms.as_f64()).unwrap_or_else(now_ms)
+        }
+        _ => now_ms(),
+    }
+}
+
+/// Monotonic time in milliseconds for measuring durations, counted from the first call.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn monotonic_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// Converts a value to a plain JS object (maps become objects, 64-bit integers become numbers).
+#[cfg(target_arch = "wasm32")]
+pub fn to_js<T: serde::Serialize>(value: &T) -> wasm_bindgen::JsValue {
+    value.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).unwrap_or(wasm_bindgen::JsValue::NULL)
+}
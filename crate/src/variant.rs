@@ -0,0 +1,180 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Emulator, CHIP8_FONTSET, DREAM6800_FONTSET};
+
+/// CHIP-8 dialect the emulator executes.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize)]
+pub enum Variant {
+    /// Original CHIP-8 with 4K of memory and a 12-bit I register.
+    #[default]
+    Chip8,
+    /// Octo's XO-CHIP with 64K of memory, a 16-bit I register and the `F000 NNNN` long load.
+    XoChip,
+    /// CHIPOS of the DREAM 6800 with its own font and 50Hz timers. It lacks 8XY3, 8XY6, 8XY7 and 8XYE.
+    Dream6800,
+    /// CHIP-8X of the RCA VIP with the VP-590 color board and a second keypad. Programs load at
+    /// 0x300 and BNNN colors the display instead of jumping.
+    Chip8X,
+    /// CHIP-8 on a COSMAC VIP with 2K of RAM. The VIP ignores the address lines above 2K, so
+    /// every address, including jumps and calls, mirrors the first 2K.
+    Vip2K,
+}
+
+impl Variant {
+    /// Every supported variant.
+    pub const ALL: [Variant; 5] =
+        [Variant::Chip8, Variant::XoChip, Variant::Dream6800, Variant::Chip8X, Variant::Vip2K];
+
+    /// Size of the addressable memory in bytes.
+    pub fn memory_size(self) -> usize {
+        match self {
+            Variant::Chip8 | Variant::Dream6800 | Variant::Chip8X => 0x1000,
+            Variant::XoChip => 0x10000,
+            Variant::Vip2K => 0x800,
+        }
+    }
+
+    /// Address programs are loaded to and start at.
+    pub fn load_address(self) -> usize {
+        match self {
+            Variant::Chip8X => 0x300,
+            Variant::Chip8 | Variant::XoChip | Variant::Dream6800 | Variant::Vip2K => 0x200,
+        }
+    }
+
+    /// Largest program that fits into memory after the load address.
+    pub fn max_rom_size(self) -> usize { self.memory_size() - self.load_address() }
+
+    /// Returns `true` if memory is smaller than the 4K CHIP-8 addresses reach, so PC wraps around
+    /// at its end like the addresses reached through I.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Variant};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_variant(Variant::Vip2K);
+    /// // jump 0xa04, v0 := 1, v1 := 2, the jump lands on v1 := 2 at 0x204
+    /// emulator.load(&[0x1a, 0x04, 0x60, 0x01, 0x61, 0x02]).unwrap();
+    /// emulator.tick();
+    /// emulator.tick();
+    /// assert_eq!((emulator.v(0), emulator.v(1)), (0, 2));
+    /// ```
+    pub fn mirrors_memory(self) -> bool { self == Variant::Vip2K }
+
+    /// Returns `true` if I is a full 16-bit register and addresses wrap around at 64K.
+    pub fn has_long_addresses(self) -> bool { self == Variant::XoChip }
+
+    /// Returns `true` if the variant has the 00CN, 00FB and 00FC scroll instructions. SUPER-CHIP
+    /// programs that stay in low resolution run as CHIP-8, so CHIP-8 has them as well, and
+    /// `lores_scroll_halved` chooses between the distances of legacy SUPER-CHIP and Octo.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Quirks, Variant};
+    /// let mut emulator = Emulator::new();
+    /// for (variant, lores_scroll_halved) in [(Variant::Chip8, true), (Variant::XoChip, false)] {
+    ///     emulator.set_variant(variant);
+    ///     emulator.set_quirks(Quirks { lores_scroll_halved, ..emulator.quirks() });
+    ///     // i := font 0, drw v0, v0, 1, scroll-down 4, scroll-right
+    ///     emulator.load(&[0xa0, 0x00, 0xd0, 0x01, 0x00, 0xc4, 0x00, 0xfb]).unwrap();
+    ///     for _ in 0..4 {
+    ///         emulator.tick();
+    ///     }
+    ///     let lit: Vec<(usize, usize)> =
+    ///         emulator.pixels().filter(|pixel| pixel.is_lit()).map(|pixel| (pixel.x, pixel.y)).collect();
+    ///     // Legacy SUPER-CHIP moves the low resolution display by half the distance.
+    ///     let (x, y) = if lores_scroll_halved { (2, 2) } else { (4, 4) };
+    ///     assert_eq!(lit, [(x, y), (x + 1, y), (x + 2, y), (x + 3, y)]);
+    /// }
+    /// ```
+    pub fn has_scrolling(self) -> bool { matches!(self, Variant::Chip8 | Variant::XoChip) }
+
+    /// Returns `true` if the display has a second plane, selected with FN01. Drawing, clearing and
+    /// scrolling only affect the selected planes, and DXYN draws a sprite per selected plane, the
+    /// one of the second plane following the one of the first in memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{Emulator, Variant};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_variant(Variant::XoChip);
+    /// // i := font 0, plane 3, sprite v0 v0 1, plane 2, clear
+    /// emulator.load(&[0xa0, 0x00, 0xf3, 0x01, 0xd0, 0x01, 0xf2, 0x01, 0x00, 0xe0]).unwrap();
+    /// for _ in 0..3 {
+    ///     emulator.tick();
+    /// }
+    /// // The first row of the 0 glyph in plane 1 and its second row in plane 2.
+    /// let row: Vec<u8> = emulator.pixels().take(4).map(|pixel| pixel.planes).collect();
+    /// assert_eq!(row, [3, 1, 1, 3]);
+    /// emulator.tick();
+    /// emulator.tick();
+    /// let row: Vec<u8> = emulator.pixels().take(4).map(|pixel| pixel.planes).collect();
+    /// assert_eq!(row, [1, 1, 1, 1]);
+    /// ```
+    pub fn has_planes(self) -> bool { self == Variant::XoChip }
+
+    /// Hex digit glyphs loaded at address 0, five bytes per digit.
+    pub fn font(self) -> &'static [u8; 80] {
+        match self {
+            Variant::Dream6800 => &DREAM6800_FONTSET,
+            Variant::Chip8 | Variant::XoChip | Variant::Chip8X | Variant::Vip2K => &CHIP8_FONTSET,
+        }
+    }
+
+    /// How many times per second the timers count down, which is the rate `tick_frame` should be
+    /// called at. The DREAM 6800 derives it from the 50Hz Australian mains.
+    pub fn frame_rate(self) -> u32 {
+        match self {
+            Variant::Dream6800 => 50,
+            Variant::Chip8 | Variant::XoChip | Variant::Chip8X | Variant::Vip2K => 60,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Rate in Hz the host should call `tick_frame` at, the rate of the variant unless
+    /// `set_timer_hz` changed it.
+    pub fn frame_rate(&self) -> u32 { self.timer_hz.map_or(self.variant.frame_rate(), u32::from) }
+
+    /// Runs the timers, and with them the frames, at 50Hz like PAL systems or at 60Hz, so programs
+    /// written for such a machine keep their pacing. The schedulers of `advance_ms` and
+    /// `advance` follow, as does the audio of `step_frame_with_audio`. Other rates are ignored.
+    /// `set_variant` goes back to the rate of the variant. The rate is part of savestates, and
+    /// with them of movies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasm_chip8::{CatchUpPolicy, Emulator};
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_timer_hz(50);
+    /// emulator.set_catch_up(100, CatchUpPolicy::SkipAhead);
+    /// // v0 := 50, delay := v0, loop: jump loop
+    /// emulator.load(&[0x60, 0x32, 0xf0, 0x15, 0x12, 0x04]).unwrap();
+    /// // The delay of 50 ticks lasts a second.
+    /// assert_eq!(emulator.advance_ms(1000.0), 50);
+    /// assert_eq!(emulator.delay_timer(), 0);
+    /// emulator.set_timer_hz(30);
+    /// assert_eq!(emulator.timer_hz(), 50);
+    ///
+    /// let state = emulator.save_state();
+    /// emulator.set_variant(emulator.variant());
+    /// assert_eq!(emulator.timer_hz(), 60);
+    /// emulator.load_state(&state).unwrap();
+    /// assert_eq!(emulator.timer_hz(), 50);
+    /// ```
+    pub fn set_timer_hz(&mut self, hz: u8) {
+        if hz == 50 || hz == 60 {
+            self.timer_hz = Some(hz);
+        }
+    }
+
+    /// Rate the timers count down at in Hz.
+    pub fn timer_hz(&self) -> u8 { self.frame_rate() as u8 }
+}
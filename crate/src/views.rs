@@ -0,0 +1,45 @@
+//! Typed arrays over the buffers of the emulator, for JS code that does not want to deal with
+//! raw pointers and offsets into `WebAssembly.Memory`.
+//!
+//! Every helper returns either a copy or a view. A copy is always safe to keep. A view reads the
+//! buffer in place without copying, but it is only valid until the next call into the module:
+//! the emulator may replace the buffer, and growing the wasm memory detaches every view. Take a
+//! fresh view each frame and never hold on to it.
+
+use js_sys::{Float32Array, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+use crate::Emulator;
+
+fn view_or_copy(data: &[u8], copy: bool) -> Uint8Array {
+    if copy {
+        return Uint8Array::from(data);
+    }
+    // The view is handed to JS right away and the documentation of every helper tells callers it
+    // is invalidated by the next call into the module.
+    unsafe { Uint8Array::view(data) }
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Display in row-major order, one byte per pixel with bit N set when plane N is lit, like
+    /// `pixels_packed`. With `copy` unset it is a view that is only valid until the next call
+    /// into the emulator.
+    pub fn gfx_view(&self, copy: bool) -> Uint8Array { view_or_copy(self.gfx.as_flattened(), copy) }
+
+    /// Whole emulator memory, see `memory`. With `copy` unset it is a view that is only valid
+    /// until the next call into the emulator.
+    pub fn memory_view(&self, copy: bool) -> Uint8Array { view_or_copy(&self.memory, copy) }
+
+    /// Image of the last `render_rgba` call. With `copy` unset it is a view that is only valid
+    /// until the next call into the emulator.
+    pub fn rgba_view(&self, copy: bool) -> Uint8Array { view_or_copy(self.rgba(), copy) }
+
+    /// Returns `count` samples of `fill_audio` as a new array, ready for an `AudioBuffer`. The
+    /// samples are generated for the call, so there is no view of them to take.
+    pub fn fill_audio_array(&mut self, count: usize) -> Float32Array {
+        let mut samples = vec![0.0; count];
+        self.fill_audio(&mut samples);
+        Float32Array::from(&samples[..])
+    }
+}
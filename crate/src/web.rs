@@ -0,0 +1,79 @@
+//! Browser helpers that need `fetch`, enabled by the `web` feature.
+
+use js_sys::{Error, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Response;
+
+use crate::{Emulator, Variant};
+
+/// Downloads a ROM and checks that it fits into the memory of the variant.
+///
+/// The promise rejects with an `Error` describing what went wrong: the network request failed,
+/// the server answered with an error status, or the ROM is empty or too large.
+#[wasm_bindgen]
+pub async fn fetch_rom(url: String, variant: Variant) -> Result<Vec<u8>, JsValue> {
+    let max_size = variant.max_rom_size();
+    let window = web_sys::window().ok_or_else(|| Error::new("fetch is not available outside of a window"))?;
+
+    let response = JsFuture::from(window.fetch_with_str(&url))
+        .await
+        .map_err(|err| Error::new(&format!("failed to fetch {}: {}", url, describe(&err))))?;
+    let response: Response = response.dyn_into()?;
+
+    if !response.ok() {
+        return Err(Error::new(&format!("failed to fetch {}: HTTP {}", url, response.status())).into());
+    }
+
+    // Reject oversized ROMs before downloading the body when the server announces the size.
+    let announced = response.headers().get("Content-Length").ok().flatten();
+    if let Some(size) = announced.and_then(|len| len.parse::<usize>().ok()) {
+        if size > max_size {
+            return Err(too_large(&url, size, max_size));
+        }
+    }
+
+    let body = JsFuture::from(response.array_buffer()?)
+        .await
+        .map_err(|err| Error::new(&format!("failed to read {}: {}", url, describe(&err))))?;
+    let rom = Uint8Array::new(&body).to_vec();
+
+    if rom.is_empty() {
+        return Err(Error::new(&format!("{} is empty", url)).into());
+    }
+    if rom.len() > max_size {
+        return Err(too_large(&url, rom.len(), max_size));
+    }
+
+    Ok(rom)
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    /// Downloads a ROM with `fetch_rom` and resolves to a new emulator of the variant with the ROM
+    /// loaded.
+    ///
+    /// ```js
+    /// const emulator = await Emulator.load_url('roms/pong.ch8', Variant.Chip8)
+    /// ```
+    pub async fn load_url(url: String, variant: Variant) -> Result<Emulator, JsValue> {
+        let rom = fetch_rom(url, variant).await?;
+
+        let mut emulator = Emulator::new();
+        emulator.set_variant(variant);
+        emulator.load(&rom)?;
+        Ok(emulator)
+    }
+}
+
+fn too_large(url: &str, size: usize, max_size: usize) -> JsValue {
+    Error::new(&format!("{} is too large: {} bytes, at most {} bytes fit into memory", url, size, max_size)).into()
+}
+
+fn describe(err: &JsValue) -> String {
+    err.dyn_ref::<Error>()
+        .map(|err| String::from(err.message()))
+        .or_else(|| err.as_string())
+        .unwrap_or_else(|| String::from("unknown error"))
+}
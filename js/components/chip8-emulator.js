@@ -1,5 +1,5 @@
 import ('../../crate/pkg/wasm_chip8_bg.wasm').then(({ memory }) => {
-  import('../../crate/pkg').then(({ Emulator }) => {
+  import('../../crate/pkg').then(({ Builtin, Emulator }) => {
 
     const WIDTH = 64
     const HEIGHT = 32
@@ -42,6 +42,8 @@ import ('../../crate/pkg/wasm_chip8_bg.wasm').then(({ memory }) => {
           this._programLoaded = false
           this._animationId = null
           this._emulator = Emulator.new()
+          // Show the splash screen until a game is uploaded.
+          this._emulator.load_builtin(Builtin.Splash)
 
           this.$canvas = $container.querySelector('canvas')
           this.$startBtn = $container.querySelector('button.ch8e-start-btn')
@@ -83,6 +85,10 @@ import ('../../crate/pkg/wasm_chip8_bg.wasm').then(({ memory }) => {
         connectedCallback() {
           this.$startBtn.addEventListener('click', this.toggle)
           this.$uploadBtn.addEventListener('file-selected', this.uploadProgram)
+          if (!this._programLoaded) {
+            this._emulator.run_cycles(1000)
+            this.renderGfx()
+          }
         }
 
         disconnectedCallback() {
@@ -123,9 +129,21 @@ import ('../../crate/pkg/wasm_chip8_bg.wasm').then(({ memory }) => {
           this.renderCellsByCond(gfx, EMPTY_COLOR, (row, col) => gfx[getIndex(row, col)])
         }
 
-        loop() {
-          for (let i = 0; i < this.ticksPerFrame; i++) {
-            this._emulator.tick()
+        loop(timestamp) {
+          // Run as many frames as the variant's frame rate asks for since the last display refresh.
+          const interval = 1000 / this._emulator.frame_rate()
+          if (timestamp === undefined || this._lastTimestamp === undefined) {
+            this._lastTimestamp = timestamp
+            this._emulator.tick_frame()
+          } else {
+            while (timestamp - this._lastTimestamp >= interval) {
+              this._lastTimestamp += interval
+              this._emulator.tick_frame()
+            }
+            // Do not catch up after the tab was in the background.
+            if (timestamp - this._lastTimestamp > interval * 4) {
+              this._lastTimestamp = timestamp
+            }
           }
           this.renderGfx()
           this._animationId = requestAnimationFrame(this.loop)
@@ -133,6 +151,8 @@ import ('../../crate/pkg/wasm_chip8_bg.wasm').then(({ memory }) => {
 
         start() {
           if (!this.started || this._programLoaded) {
+            this._emulator.set_cycles_per_frame(this.ticksPerFrame)
+            this._emulator.resume()
             this.loop()
             this.started = true
           }
@@ -141,7 +161,9 @@ import ('../../crate/pkg/wasm_chip8_bg.wasm').then(({ memory }) => {
         pause() {
           if (this.started) {
             cancelAnimationFrame(this._animationId)
+            this._emulator.pause()
             this._animationId = null
+            this._lastTimestamp = undefined
             this.started = false
           }
         }
@@ -165,7 +187,12 @@ import ('../../crate/pkg/wasm_chip8_bg.wasm').then(({ memory }) => {
 
           reader.onload = function (e) {
             const program = new Uint8Array(e.target.result)
-            self._emulator.load(program)
+            try {
+              self._emulator.load(program)
+            } catch (err) {
+              window.alert(`${file.name} is ${err.size} bytes, at most ${err.max_size} bytes fit into memory`)
+              return
+            }
             self._programLoaded = true
           }
 
@@ -2,13 +2,17 @@ export class Audio {
   constructor() {
     this.ctx = new AudioContext()
     this.o = null
+    this.frequency = 440
+    this.gain = this.ctx.createGain()
+    this.gain.connect(this.ctx.destination)
   }
 
   start() {
     if (!this.is_active()) {
       this.o = this.ctx.createOscillator()
       this.o.type = 'sine'
-      this.o.connect(this.ctx.destination)
+      this.o.frequency.value = this.frequency
+      this.o.connect(this.gain)
       this.o.start()
     }
   }
@@ -20,6 +24,17 @@ export class Audio {
     }
   }
 
+  set_frequency(hz) {
+    this.frequency = hz
+    if (this.is_active()) {
+      this.o.frequency.value = hz
+    }
+  }
+
+  set_volume(volume) {
+    this.gain.gain.value = volume
+  }
+
   is_active() {
     return Boolean(this.o)
   }
@@ -0,0 +1,37 @@
+const NOTE_OFF = 0x80
+const NOTE_ON = 0x90
+
+// Forwards notes of every connected MIDI input to the emulator keypad.
+export class Midi {
+  constructor(emulator) {
+    this.emulator = emulator
+    this.handle_message = this.handle_message.bind(this)
+  }
+
+  start_detection() {
+    if (!navigator.requestMIDIAccess) {
+      return Promise.resolve(false)
+    }
+
+    return navigator.requestMIDIAccess().then(access => {
+      access.inputs.forEach(input => {
+        input.onmidimessage = this.handle_message
+      })
+      access.onstatechange = e => {
+        if (e.port.type === 'input') {
+          e.port.onmidimessage = this.handle_message
+        }
+      }
+      return true
+    }, () => false)
+  }
+
+  handle_message(e) {
+    const [status, note, velocity] = e.data
+    const command = status & 0xf0
+
+    if (command === NOTE_ON || command === NOTE_OFF) {
+      this.emulator.set_midi_note_state(note, command === NOTE_ON && velocity > 0)
+    }
+  }
+}
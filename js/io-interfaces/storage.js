@@ -0,0 +1,24 @@
+const PREFIX = 'chip8-save-'
+
+// Keeps the save window of every ROM in localStorage, base64 encoded under its ROM hash.
+export class LocalSaveStorage {
+  load(rom_hash) {
+    try {
+      const text = window.localStorage.getItem(PREFIX + rom_hash)
+      if (text === null) {
+        return undefined
+      }
+      return Uint8Array.from(window.atob(text), c => c.charCodeAt(0))
+    } catch (e) {
+      return undefined
+    }
+  }
+
+  store(rom_hash, data) {
+    try {
+      window.localStorage.setItem(PREFIX + rom_hash, window.btoa(String.fromCharCode(...data)))
+    } catch (e) {
+      // Storage full or disabled, the game keeps running without saving.
+    }
+  }
+}